@@ -1,43 +1,1074 @@
 use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{BTreeMap, VecDeque},
     fmt::Display,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::mpsc,
-    time::{Duration, Instant},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Sender},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use color_eyre::eyre::{Error, Result};
+use clap::ValueEnum;
+use color_eyre::eyre::{Error, Result, eyre};
+use cpal::traits::DeviceTrait;
 use itertools::Itertools;
 use ratatui::{
     DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyCode},
-    layout::{Constraint, Direction, Layout, Rect},
+    crossterm::event::{
+        self, Event, KeyCode, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+    },
+    layout::{Constraint, Direction, Flex, Layout, Position, Rect},
     style::{Color, Modifier, Style, Stylize},
     symbols,
     text::{Line, Span, Text},
-    widgets::{Axis, Block, Chart, Dataset, Paragraph},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Chart, Clear, Dataset, List, ListItem, ListState,
+        Padding, Paragraph, Tabs, Wrap,
+        canvas::{Canvas, Points},
+    },
 };
+use serde::Deserialize;
+use tracing::Level;
 
-use crate::audio::{AudioListener, FreqData, get_note_from_frequency};
+use crate::{
+    audio::{
+        AnalysisMode, AnalysisOptions, AudioListener, AudioMessage, ChannelMode, FreqData,
+        InputDeviceInfo, InputLevel, ListenerOptions, WINDOW_SIZES, WindowFunction,
+        ZERO_PADDING_FACTORS, capture_config, check_channel, find_capture_device,
+        find_input_device, linear_to_db, list_input_devices, select_host,
+    },
+    cli::Cli,
+    config::{Config, write_starter_config},
+    cqt::{CQT_BINS, CQT_LOWEST_MIDI},
+    envelope::EnvelopePhase,
+    features::SpectralFeatures,
+    formants::Formants,
+    guitar::{FRETBOARD_FRETS, IN_TUNE_CENTS, STANDARD_TUNING, nearest_string},
+    harmonics::{Harmonic, HarmonicSeries},
+    history::SharedSpectrumHistory,
+    instrument::{Instrument, InstrumentPreset},
+    layout::{LayoutOptions, Widget},
+    logging::{LOG_BUFFER, get_data_dir, log_path},
+    mel::{hz_to_mel, mel_to_hz},
+    monitor::{MonitorOptions, find_output_device},
+    pitch::{PitchDetector, PitchMethod},
+    snapshot::save_snapshot,
+    theme::{ChartMarker, Theme, ThemeName, ThemeOptions},
+    tuning::{NOTES, NoteReading, Tuning, TuningDrift, TuningOptions, note_from_midi_note_number},
+};
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum AppScreen {
     Debug,
     Tutor,
-    Help,
+    DeviceSelect,
+    Errors,
+    InstrumentSelect,
+    /// Where the sung vowel sits between the others, by its formants
+    Vowels,
+    /// A tuner for the six strings of a guitar in standard tuning
+    GuitarTuner,
+    /// The recent spectra as a heatmap scrolling to the left
+    Spectrogram,
+    /// The note being played in big letters with a needle and a strobe
+    Tuner,
+    /// The pitch of the last few seconds as a line over the notes
+    PitchContour,
+    /// The analysis settings, changed while listening
+    Settings,
+    /// The latest log lines
+    Logs,
+    /// Left against right, to see how wide the stereo is and if it's in phase
+    PhaseScope,
+    /// One of the layouts from the config, by its index
+    Layout(usize),
+    /// The first start's guide: pick an input, check its level, meet the
+    /// screens
+    Welcome,
+}
+
+impl AppScreen {
+    /// The keys that do something on this screen, for the status bar
+    fn key_hints(self) -> &'static str {
+        match self {
+            AppScreen::Debug => "1/2/3 panels, F full screen, z/Z ←/→ zoom, ? help, q quit",
+            AppScreen::Spectrogram => "↑/↓ range, ←/→ colors, F full screen, ? help, q quit",
+            AppScreen::DeviceSelect => "↑/↓ move, enter switch, l loopback, ? help, q quit",
+            AppScreen::InstrumentSelect => "↑/↓ move, enter listen for it, ? help, q quit",
+            AppScreen::Settings => "↑/↓ setting, ←/→ change it, ? help, q quit",
+            AppScreen::Logs => "↑/↓ scroll, ←/→ fewer or more, ? help, q quit",
+            AppScreen::Welcome => "↑/↓ move, enter switch, esc done, ? help, q quit",
+            _ => "? help, q quit",
+        }
+    }
+    /// The keys of this screen in full, for the top of the help
+    fn help(self) -> &'static [&'static str] {
+        match self {
+            AppScreen::Debug => &[
+                "1/2/3: hide or show the readings, fft and time domain",
+                "z/Z, ←/→ or the mouse wheel: zoom and pan the fft chart",
+                "mouse on the fft chart: click to read a frequency, drag to zoom",
+                "F: the fft chart, then the time domain, full screen",
+                "O: trigger the time domain on a rising edge, or on whole periods of the pitch",
+                "</>: lower or raise the trigger level",
+                "x: bars per third of an octave instead of the fft chart's line",
+                "f: all spectral features",
+            ],
+            AppScreen::Spectrogram => &[
+                "↑/↓: halve or double the frequency range",
+                "←/→: narrow or widen the dB range of the colors",
+                "F: full screen",
+            ],
+            AppScreen::DeviceSelect => &[
+                "↑/↓: move",
+                "enter: switch to the device",
+                "l: loopback on or off",
+            ],
+            AppScreen::InstrumentSelect => &["↑/↓: move", "enter: listen for the instrument"],
+            AppScreen::Settings => &["↑/↓: pick a setting", "←/→: change it"],
+            AppScreen::Logs => &["↑/↓: scroll", "←/→: fewer or more lines"],
+            AppScreen::Welcome => &[
+                "↑/↓: move",
+                "enter: listen on the device",
+                "l: loopback on or off",
+                "esc: done, saves a config so this doesn't show again",
+            ],
+            _ => &[],
+        }
+    }
+}
+
+/// What the frequency chart shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SpectrumView {
+    /// The fft bins
+    #[default]
+    Linear,
+    Mel,
+    /// A bar per semitone
+    ConstantQ,
+    /// A bar per pitch class
+    Chroma,
+    /// The mel-frequency cepstral coefficients over time
+    Mfcc,
+}
+
+impl SpectrumView {
+    fn next(self) -> Self {
+        match self {
+            SpectrumView::Linear => SpectrumView::Mel,
+            SpectrumView::Mel => SpectrumView::ConstantQ,
+            SpectrumView::ConstantQ => SpectrumView::Chroma,
+            SpectrumView::Chroma => SpectrumView::Mfcc,
+            SpectrumView::Mfcc => SpectrumView::Linear,
+        }
+    }
+}
+
+/// Where the time domain chart starts its window, like an oscilloscope's
+/// trigger
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Trigger {
+    /// The whole window as it came, jumping around from one to the next
+    #[default]
+    Free,
+    /// The first time the signal rises through the trigger level
+    RisingEdge,
+    /// Like `RisingEdge`, showing a whole number of periods of the pitch
+    Pitch,
+}
+
+impl Trigger {
+    fn next(self) -> Self {
+        match self {
+            Trigger::Free => Trigger::RisingEdge,
+            Trigger::RisingEdge => Trigger::Pitch,
+            Trigger::Pitch => Trigger::Free,
+        }
+    }
+}
+
+/// What the fft chart's line is drawn from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SpectrumPersistence {
+    /// Only the newest spectrum
+    #[default]
+    Latest,
+    /// An exponential average of the spectra, the noise evens out and the
+    /// quiet harmonics that stay put show through
+    Average,
+    /// The loudest each bin has been since it was turned on
+    MaxHold,
+}
+
+impl SpectrumPersistence {
+    fn next(self) -> Self {
+        match self {
+            SpectrumPersistence::Latest => SpectrumPersistence::Average,
+            SpectrumPersistence::Average => SpectrumPersistence::MaxHold,
+            SpectrumPersistence::MaxHold => SpectrumPersistence::Latest,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SpectrumPersistence::Latest => "latest",
+            SpectrumPersistence::Average => "average",
+            SpectrumPersistence::MaxHold => "max hold",
+        }
+    }
+}
+
+/// How the fft and mel charts plot magnitudes
+#[derive(Clone, Copy, Default, PartialEq)]
+enum MagnitudeScale {
+    /// The loudest partial towers over everything else
+    #[default]
+    Linear,
+    /// Decibels below full scale, quiet partials show up too
+    Db,
+}
+
+impl MagnitudeScale {
+    fn toggle(self) -> Self {
+        match self {
+            MagnitudeScale::Linear => MagnitudeScale::Db,
+            MagnitudeScale::Db => MagnitudeScale::Linear,
+        }
+    }
+
+    /// Top of the y axis, `linear_top` for linear magnitudes. The bottom is
+    /// always 0
+    fn top(self, linear_top: f64) -> f64 {
+        match self {
+            MagnitudeScale::Linear => linear_top,
+            MagnitudeScale::Db => SPECTRUM_RANGE_DB,
+        }
+    }
+
+    /// Where a magnitude of `freq_data` goes on the y axis. dB are shifted up
+    /// by the range so the bottom of the chart is 0.
+    fn plot(self, magnitude: f64, freq_data: &FreqData) -> f64 {
+        match self {
+            MagnitudeScale::Linear => magnitude,
+            MagnitudeScale::Db => {
+                freq_data.to_dbfs(magnitude).max(-SPECTRUM_RANGE_DB) + SPECTRUM_RANGE_DB
+            }
+        }
+    }
+
+    /// (x, magnitude) points of `freq_data` ready for the chart
+    fn points<'a>(self, data: &'a [(f64, f64)], freq_data: &FreqData) -> Cow<'a, [(f64, f64)]> {
+        match self {
+            MagnitudeScale::Linear => Cow::Borrowed(data),
+            MagnitudeScale::Db => data
+                .iter()
+                .map(|(x, magnitude)| (*x, self.plot(*magnitude, freq_data)))
+                .collect(),
+        }
+    }
+
+    /// `count` labels of the y axis, from the bottom to the top
+    fn labels(self, linear_top: f64, count: usize) -> Vec<String> {
+        axis_steps(0.0, self.top(linear_top), count)
+            .map(|y| match self {
+                MagnitudeScale::Linear => axis_number(y),
+                MagnitudeScale::Db => format!("{:.0}", y - SPECTRUM_RANGE_DB),
+            })
+            .collect()
+    }
+
+    fn axis(self, linear_top: f64, count: usize, theme: &Theme) -> Axis<'static> {
+        let title = match self {
+            MagnitudeScale::Linear => "Magnitude",
+            MagnitudeScale::Db => "dBFS",
+        };
+        Axis::default()
+            .title(title)
+            .style(Style::default().fg(theme.muted))
+            .labels(
+                self.labels(linear_top, count)
+                    .into_iter()
+                    .map(|label| Span::styled(label, Style::default()))
+                    .collect::<Vec<_>>(),
+            )
+            .bounds([0.0, self.top(linear_top)])
+    }
+}
+
+/// Top of a chart's y axis that follows the loudest of the recent values: it
+/// jumps up to anything louder straight away and falls back slowly after
+#[derive(Clone, Copy)]
+struct AutoScale {
+    top: f64,
+    /// Where the axis is pinned when it doesn't scale
+    fixed: f64,
+}
+
+impl AutoScale {
+    fn new(fixed: f64) -> Self {
+        Self { top: fixed, fixed }
+    }
+
+    /// Follows `loudest`, the highest value charted now, `elapsed` seconds
+    /// after the last update
+    fn update(&mut self, loudest: f64, elapsed: f64) {
+        let wanted = (loudest * AUTO_SCALE_HEADROOM).max(self.fixed * AUTO_SCALE_MIN_FRACTION);
+        self.top = if wanted >= self.top {
+            wanted
+        } else {
+            (self.top * AUTO_SCALE_FALL_PER_S.powf(elapsed)).max(wanted)
+        };
+    }
+
+    /// The top to draw, the followed one or the pinned one
+    fn top(self, auto: bool) -> f64 {
+        if auto { self.top } else { self.fixed }
+    }
+}
+
+/// The title of a screen's tab, its key first
+fn tab_title(key: char, name: &str) -> String {
+    format!("{key} {name}")
+}
+
+/// Where a bordered chart in `area` plots its data, left of which go
+/// `y_labels` and below which `x_labels`. Works it out the way ratatui does,
+/// which doesn't say; this is `Chart::layout` as of ratatui 0.29, check it again
+/// when ratatui is updated.
+fn chart_plot_area(area: Rect, y_labels: &[String], x_labels: &[Span]) -> Rect {
+    let inner = Block::bordered().inner(area);
+    let y_labels_width = y_labels
+        .iter()
+        .map(|label| Span::raw(label.as_str()).width())
+        .max()
+        .unwrap_or(0) as u16;
+    // the first x label hangs left of the y axis but for its last character
+    let first_x_label_width = x_labels.first().map_or(0, Span::width) as u16;
+    let left = y_labels_width
+        .max(first_x_label_width.saturating_sub(1))
+        .min(inner.width / 3)
+        + 1;
+    Rect::new(
+        inner.x + left,
+        inner.y,
+        inner.width.saturating_sub(left),
+        // the x labels and the axis
+        inner.height.saturating_sub(2),
+    )
+}
+
+/// Index of the first sample in the first half of `samples` where they rise
+/// through `level`, so what comes after it lines up from window to window
+fn rising_edge(samples: &[f32], level: f32) -> Option<usize> {
+    samples[..samples.len() / 2]
+        .windows(2)
+        .position(|pair| pair[0] < level && pair[1] >= level)
+        .map(|i| i + 1)
+}
+
+/// A point below the bottom of any chart. Ratatui (as of 0.29) leaves out a
+/// line's segments to a point out of its bounds, so this breaks a line in two
+const LINE_BREAK: (f64, f64) = (0.0, f64::NEG_INFINITY);
+
+/// `points` as a line per color they get, the step of `SPECTRUM_COLOR_STEPS`
+/// from the bottom of the chart to `top` they're at. Each stretch of points
+/// takes the first point of the next one so the line stays whole, and
+/// `LINE_BREAK`s keep the stretches of a color apart. A dataset per color
+/// rather than per stretch, however jagged the spectrum.
+fn spectrum_runs(points: &[(f64, f64)], top: f64) -> [Vec<(f64, f64)>; SPECTRUM_COLOR_STEPS] {
+    let step = |(_, y): (f64, f64)| {
+        ((y / top * SPECTRUM_COLOR_STEPS as f64) as usize).min(SPECTRUM_COLOR_STEPS - 1)
+    };
+    let mut runs: [Vec<(f64, f64)>; SPECTRUM_COLOR_STEPS] = Default::default();
+    let mut start = 0;
+    for i in 1..=points.len() {
+        if i == points.len() || step(points[i]) != step(points[start]) {
+            let run = &mut runs[step(points[start])];
+            if !run.is_empty() {
+                run.push(LINE_BREAK);
+            }
+            run.extend_from_slice(&points[start..(i + 1).min(points.len())]);
+            start = i;
+        }
+    }
+    runs
+}
+
+/// How many labels an axis `length` cells long gets, one every `spacing`
+/// cells, so small charts don't crowd and big ones aren't bare
+fn axis_label_count(length: u16, spacing: u16) -> usize {
+    (length / spacing).clamp(2, MAX_AXIS_LABELS) as usize
+}
+
+/// `count` values spread evenly from `low` to `high`, both included
+fn axis_steps(low: f64, high: f64, count: usize) -> impl Iterator<Item = f64> {
+    let gaps = count.max(2) - 1;
+    (0..=gaps).map(move |i| low + (high - low) * i as f64 / gaps as f64)
+}
+
+/// A line across the chart from `x_bounds.0` to `x_bounds.1` at each of the
+/// labels between the bottom and the top
+fn gridlines(x_bounds: (f64, f64), low: f64, high: f64, count: usize) -> Vec<[(f64, f64); 2]> {
+    axis_steps(low, high, count)
+        .skip(1)
+        .take(count.saturating_sub(2))
+        .map(|y| [(x_bounds.0, y), (x_bounds.1, y)])
+        .collect()
+}
+
+/// `value` with as many decimals as an axis label needs
+fn axis_number(value: f64) -> String {
+    if value >= 100.0 {
+        format!("{value:.0}")
+    } else if value >= 1.0 {
+        format!("{value:.1}")
+    } else {
+        format!("{value:.3}")
+    }
+}
+
+/// (octaves above `LOG_AXIS_LOWEST_HZ`, magnitude) of each `1 /
+/// bands_per_octave` of an octave up to `highest_hz`, from the fft bins in
+/// `data`. A band gets its loudest bin so partials don't get averaged away,
+/// the low bands narrower than a bin take the spectrum at their center.
+fn octave_bands(data: &[(f64, f64)], highest_hz: f64, bands_per_octave: f64) -> Vec<(f64, f64)> {
+    if data.len() < 2 {
+        return vec![];
+    }
+    let bin_hz = data[1].0 - data[0].0;
+    let edge = |band: usize| LOG_AXIS_LOWEST_HZ * 2f64.powf(band as f64 / bands_per_octave);
+    let bands = ((highest_hz / LOG_AXIS_LOWEST_HZ).log2() * bands_per_octave).max(0.0);
+    (0..bands as usize)
+        .map(|band| {
+            let octaves = (band as f64 + 0.5) / bands_per_octave;
+            let first = ((edge(band) / bin_hz).ceil() as usize).min(data.len());
+            let last = ((edge(band + 1) / bin_hz).ceil() as usize).min(data.len());
+            let magnitude = if first < last {
+                data[first..last]
+                    .iter()
+                    .map(|(_, magnitude)| *magnitude)
+                    .fold(0.0, f64::max)
+            } else {
+                let bin = LOG_AXIS_LOWEST_HZ * 2f64.powf(octaves) / bin_hz;
+                let i = (bin.floor() as usize).min(data.len() - 2);
+                let t = (bin - i as f64).min(1.0);
+                data[i].1 + t * (data[i + 1].1 - data[i].1)
+            };
+            (octaves, magnitude)
+        })
+        .collect()
+}
+
+/// What a channel's vu meter shows, in dBFS
+#[derive(Clone, Copy, Debug)]
+struct MeterLevel {
+    /// The rms level, falling back slowly like a needle
+    level: f64,
+    /// The highest sample level lately
+    peak: f64,
+    /// When `peak` was reached
+    peak_time: Instant,
+    /// When the channel last clipped
+    last_clip: Option<Instant>,
+    /// When `level` and `peak` were last updated
+    time: Instant,
+}
+
+impl MeterLevel {
+    fn new() -> Self {
+        Self {
+            level: -VU_RANGE_DB,
+            peak: -VU_RANGE_DB,
+            peak_time: Instant::now(),
+            last_clip: None,
+            time: Instant::now(),
+        }
+    }
+
+    /// Follows the `level` of the window that just came in
+    fn update(&mut self, level: &InputLevel) {
+        let now = Instant::now();
+        let elapsed = (now - self.time).as_secs_f64();
+        self.time = now;
+        let rms = (level.rms_db as f64).max(-VU_RANGE_DB);
+        self.level = rms.max(self.level - VU_FALL_DB_PER_S * elapsed);
+        let peak = (level.peak_db as f64).max(-VU_RANGE_DB);
+        if peak >= self.peak {
+            self.peak = peak;
+            self.peak_time = now;
+        } else if now - self.peak_time > VU_PEAK_HOLD {
+            self.peak = (self.peak - VU_PEAK_FALL_DB_PER_S * elapsed).max(peak);
+        }
+        if level.clipped {
+            self.last_clip = Some(now);
+        }
+    }
+}
+
+/// The panels of the debug screen that can be hidden, the others get their
+/// room
+#[derive(Clone, Copy, Debug)]
+struct DebugPanels {
+    /// The boxes of readings along the top
+    stats: bool,
+    freqs: bool,
+    time_domain: bool,
+}
+
+/// A chart taking up the whole terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FullScreenChart {
+    Freqs,
+    TimeDomain,
+    Spectrogram,
+}
+
+/// A row of the settings screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Setting {
+    WindowSize,
+    Window,
+    PitchDetector,
+    ZeroPadding,
+    HighPass,
+    NoiseGateThreshold,
+    Reference,
+    Device,
+}
+
+impl Setting {
+    const ALL: [Setting; 8] = [
+        Setting::WindowSize,
+        Setting::Window,
+        Setting::PitchDetector,
+        Setting::ZeroPadding,
+        Setting::HighPass,
+        Setting::NoiseGateThreshold,
+        Setting::Reference,
+        Setting::Device,
+    ];
+
+    /// What changing it does, under its value
+    fn description(self) -> &'static str {
+        match self {
+            Setting::WindowSize => {
+                "longer tells closer notes apart, shorter follows the music faster"
+            }
+            Setting::Window => "tapers each window so peaks don't leak into their neighbours",
+            Setting::PitchDetector => {
+                "yin has fewer octave errors on low notes, cepstrum copes with voices"
+            }
+            Setting::ZeroPadding => "a smoother spectrum, but no closer notes told apart",
+            Setting::HighPass => "removes the dc offset and rumble, off at 0",
+            Setting::NoiseGateThreshold => "input below this is silenced while the gate is on (g)",
+            Setting::Reference => "the frequency of A4 every note is tuned from",
+            Setting::Device => "the input listened to",
+        }
+    }
+}
+
+impl Display for Setting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Setting::WindowSize => "FFT size",
+            Setting::Window => "Window function",
+            Setting::PitchDetector => "Pitch detector",
+            Setting::ZeroPadding => "Zero padding",
+            Setting::HighPass => "High-pass",
+            Setting::NoiseGateThreshold => "Noise gate threshold",
+            Setting::Reference => "A4 reference",
+            Setting::Device => "Input device",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The value `step` places after `value` in `values`, stopping at either end
+fn step_through<T: Clone + PartialEq>(values: &[T], value: &T, step: isize) -> T {
+    let i = values.iter().position(|v| v == value).unwrap_or(0);
+    values[i.saturating_add_signed(step).min(values.len() - 1)].clone()
+}
+
+/// Waits for an audio listener to stop, its panic is an error like any other
+fn join_listener(thread: JoinHandle<Result<()>>) -> Result<()> {
+    thread
+        .join()
+        .map_err(|_| eyre!("The audio thread panicked"))?
+}
+
+/// How `value` is written on the command line
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+/// Level of one band of the bar spectrum, in dB above the bottom of the chart
+#[derive(Clone, Copy, Debug)]
+struct BarLevel {
+    level: f64,
+    /// The loudest the band has been lately
+    peak: f64,
+    /// When `peak` was reached
+    peak_time: Instant,
+}
+
+/// The loudest one bin of the fft chart has been lately, for its peak-hold
+/// line
+#[derive(Clone, Copy, Debug)]
+struct HeldPeak {
+    magnitude: f64,
+    /// When `magnitude` was reached
+    time: Instant,
+}
+
+/// A horizontal needle from -50 to +50 cents, `width` characters wide, with
+/// the in-tune zone in green around the middle
+fn cents_gauge(cents: f32, width: usize, theme: &Theme) -> Vec<Span<'static>> {
+    let last = width.saturating_sub(1) as f32;
+    let position = |cents: f32| {
+        ((cents.clamp(-GAUGE_RANGE_CENTS, GAUGE_RANGE_CENTS) + GAUGE_RANGE_CENTS)
+            / (2.0 * GAUGE_RANGE_CENTS)
+            * last)
+            .round() as usize
+    };
+    let needle = position(cents);
+    let (in_tune_from, in_tune_to) = (position(-IN_TUNE_CENTS), position(IN_TUNE_CENTS));
+    (0..width)
+        .map(|i| {
+            let symbol = if i == needle {
+                "●"
+            } else if i == width / 2 {
+                "┼"
+            } else {
+                "─"
+            };
+            let color = if i == needle {
+                if cents.abs() <= IN_TUNE_CENTS {
+                    theme.good
+                } else {
+                    theme.warning
+                }
+            } else if (in_tune_from..=in_tune_to).contains(&i) {
+                theme.good
+            } else {
+                theme.faint
+            };
+            Span::styled(symbol, Style::default().fg(color))
+        })
+        .collect()
+}
+
+/// A piano keyboard three rows tall from the C at `lowest_c`, `octaves`
+/// octaves wide, the keys `color_of` gives a color lit up in it. The Cs are
+/// labelled.
+fn piano(
+    lowest_c: i32,
+    octaves: usize,
+    theme: &Theme,
+    color_of: impl Fn(i32) -> Option<Color>,
+) -> Vec<Line<'static>> {
+    let white_keys = 7 * octaves;
+    // (symbol, fg, bg) of every cell, the top row first
+    let mut rows =
+        vec![vec![(' ', theme.black_keys, theme.white_keys); white_keys * PIANO_KEY_WIDTH]; 3];
+    for key in 0..white_keys {
+        let midi = lowest_c + 12 * (key / 7) as i32 + WHITE_KEYS[key % 7];
+        let x = key * PIANO_KEY_WIDTH;
+        let color = color_of(midi).unwrap_or(theme.white_keys);
+        for row in rows.iter_mut() {
+            row[x..x + PIANO_KEY_WIDTH - 1].fill((' ', theme.black_keys, color));
+            row[x + PIANO_KEY_WIDTH - 1] = ('│', theme.faint, theme.white_keys);
+        }
+        if key % 7 == 0 {
+            let label = note_from_midi_note_number(midi);
+            for (i, c) in label.chars().take(PIANO_KEY_WIDTH - 1).enumerate() {
+                rows[2][x + i] = (c, theme.faint, color);
+            }
+        }
+    }
+    for key in 0..white_keys {
+        // E and B have no black key after them
+        if matches!(key % 7, 2 | 6) {
+            continue;
+        }
+        let midi = lowest_c + 12 * (key / 7) as i32 + WHITE_KEYS[key % 7] + 1;
+        let color = color_of(midi).unwrap_or(theme.black_keys);
+        // over the gap between the two white keys
+        let x = (key + 1) * PIANO_KEY_WIDTH - 2;
+        for row in rows.iter_mut().take(2) {
+            for cell in row.iter_mut().skip(x).take(3) {
+                *cell = (' ', theme.black_keys, color);
+            }
+        }
+    }
+    rows.into_iter()
+        .map(|row| {
+            Line::from(
+                row.into_iter()
+                    .map(|(symbol, fg, bg)| {
+                        Span::styled(symbol.to_string(), Style::default().fg(fg).bg(bg))
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// A guitar neck in standard tuning up to fret `frets`, a row per string with
+/// the high E on top like tabs. Every fret `color_of` gives a color for gets
+/// a dot in it, the open strings left of the nut. The inlay frets are numbered
+/// underneath.
+fn fretboard(
+    frets: usize,
+    theme: &Theme,
+    color_of: impl Fn(i32) -> Option<Color>,
+) -> Vec<Line<'static>> {
+    let mut lines = STANDARD_TUNING
+        .iter()
+        .rev()
+        .map(|open| {
+            let mut spans = vec![Span::from(format!(
+                "{:<width$}",
+                note_from_midi_note_number(*open),
+                width = FRETBOARD_LABEL_WIDTH
+            ))];
+            spans.push(match color_of(*open) {
+                Some(color) => "●".fg(color),
+                None => Span::from(" "),
+            });
+            spans.push(Span::from("║"));
+            let half = (FRETBOARD_FRET_WIDTH - 2) / 2;
+            for fret in 1..=frets {
+                let string = Span::from("─".repeat(half)).fg(theme.faint);
+                spans.push(string.clone());
+                spans.push(match color_of(open + fret as i32) {
+                    Some(color) => "●".fg(color),
+                    None => "─".fg(theme.faint),
+                });
+                spans.push(Span::from("─".repeat(FRETBOARD_FRET_WIDTH - 2 - half)).fg(theme.faint));
+                spans.push(Span::from("│"));
+            }
+            Line::from(spans)
+        })
+        .collect::<Vec<_>>();
+    let numbers = (1..=frets)
+        .map(|fret| {
+            let label = if FRETBOARD_INLAYS.contains(&fret) {
+                fret.to_string()
+            } else {
+                String::new()
+            };
+            // under the middle of the fret, where its dots go
+            format!("{label:^width$}", width = FRETBOARD_FRET_WIDTH)
+        })
+        .collect::<String>();
+    lines.push(
+        Line::from(format!(
+            "{}{numbers}",
+            " ".repeat(FRETBOARD_LABEL_WIDTH + 2)
+        ))
+        .fg(theme.faint),
+    );
+    lines
+}
+
+/// A character five rows tall drawn with blocks, for the tuner's note
+fn big_glyph(c: char) -> [&'static str; 5] {
+    match c {
+        'A' => [" ██ ", "█  █", "████", "█  █", "█  █"],
+        'B' => ["███ ", "█  █", "███ ", "█  █", "███ "],
+        'C' => [" ███", "█   ", "█   ", "█   ", " ███"],
+        'D' => ["███ ", "█  █", "█  █", "█  █", "███ "],
+        'E' => ["████", "█   ", "███ ", "█   ", "████"],
+        'F' => ["████", "█   ", "███ ", "█   ", "█   "],
+        'G' => [" ███", "█   ", "█ ██", "█  █", " ███"],
+        '#' => [" █ █", "████", " █ █", "████", " █ █"],
+        '0' => [" ██ ", "█  █", "█  █", "█  █", " ██ "],
+        '1' => [" ██ ", "  █ ", "  █ ", "  █ ", " ███"],
+        '2' => ["███ ", "   █", " ██ ", "█   ", "████"],
+        '3' => ["███ ", "   █", " ██ ", "   █", "███ "],
+        '4' => ["█  █", "█  █", "████", "   █", "   █"],
+        '5' => ["████", "█   ", "███ ", "   █", "███ "],
+        '6' => [" ██ ", "█   ", "███ ", "█  █", " ██ "],
+        '7' => ["████", "   █", "  █ ", " █  ", " █  "],
+        '8' => [" ██ ", "█  █", " ██ ", "█  █", " ██ "],
+        '9' => [" ██ ", "█  █", " ███", "   █", " ██ "],
+        '-' => ["    ", "    ", "████", "    ", "    "],
+        _ => ["    "; 5],
+    }
+}
+
+/// `text` in five rows of big block characters, a column apart
+fn big_text(text: &str) -> Vec<String> {
+    (0..5)
+        .map(|row| text.chars().map(|c| big_glyph(c)[row]).join(" "))
+        .collect()
 }
 
 pub enum TerminalMessage {
     Quit,
+    SwitchDevice(String),
+    SetLoopback(bool),
+    /// Software gain in dB
+    SetGain(f32),
+    SetAgc(bool),
+    SetNoiseGate(bool),
+    SetInstrument(Instrument),
+    /// Top of the spectrum sent to the ui, in Hz
+    SetMaxFrequency(f32),
+    /// Work out the mfcc of each window, for the mfcc view
+    SetMfcc(bool),
+    /// New analysis settings, the input is restarted with them
+    SetAnalysis(Box<AnalysisOptions>),
+    /// Level below which the noise gate closes, in dBFS
+    SetNoiseGateThreshold(f32),
+    StartRecording(PathBuf),
+    StopRecording,
 }
 
 type Frequency = f32;
 
+/// How much `+`/`-` change the gain, in dB
+const GAIN_STEP_DB: f32 = 3.0;
+const GAIN_RANGE_DB: (f32, f32) = (-24.0, 42.0);
+
+/// How long status messages stay on screen
+const STATUS_DURATION: Duration = Duration::from_secs(5);
+/// Smallest terminal anything is drawn in, as (columns, rows)
+const MIN_TERMINAL_SIZE: (u16, u16) = (40, 12);
+/// Terminals narrower or shorter than this get the compact debug screen, as
+/// (columns, rows)
+const COMPACT_TERMINAL_SIZE: (u16, u16) = (100, 30);
+/// Peaks quieter than this are too quiet on the welcome screen's level check
+const WELCOME_QUIET_DB: f32 = -50.0;
+/// How long notifications stay in the corner
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+/// Most notifications in the corner at once, the oldest go first
+const MAX_TOASTS: usize = 4;
+
 // should i make it enum ? idk
 type Note = String;
 
+/// Sounds with a spectral flatness above this are too noisy to advance the tutor
+const TUTOR_MAX_FLATNESS: f32 = 0.3;
+/// Pitches the detector is less sure of than this are ignored, so noise
+/// doesn't flicker through the notes
+const MIN_PITCH_CONFIDENCE: f32 = 0.5;
+/// Lowest level the constant-q chart shows, in dB below full scale
+const CQT_RANGE_DB: f32 = 80.0;
+/// Lowest level the fft and mel charts show on the dB scale, in dB below full
+/// scale
+const SPECTRUM_RANGE_DB: f64 = 100.0;
+/// Where the log frequency axis starts, the lowest note of a piano
+const LOG_AXIS_LOWEST_HZ: f64 = 27.5;
+/// Bands the log frequency axis splits each octave into, quarter tones
+const LOG_AXIS_BANDS_PER_OCTAVE: f64 = 24.0;
+/// Lowest the top of the spectrum can be brought down to with `[`
+const MAX_FREQUENCY_LOWEST_HZ: f32 = 250.0;
+/// Narrowest the fft chart can be zoomed into, as a fraction of its range
+const FREQ_ZOOM_MIN_SPAN: f64 = 1.0 / 64.0;
+/// How much of the fft chart's view a pan moves it by
+const FREQ_PAN_STEP: f64 = 0.25;
+/// Columns a drag over the fft chart has to cover to zoom, anything less is a
+/// click
+const FREQ_DRAG_MIN_COLUMNS: u16 = 2;
+/// How much ←/→ move the high-pass on the settings screen, in Hz
+const HIGH_PASS_STEP_HZ: f32 = 10.0;
+const HIGH_PASS_MAX_HZ: f32 = 300.0;
+/// How much ←/→ move the noise gate's threshold on the settings screen, in dB
+const NOISE_GATE_STEP_DB: f32 = 3.0;
+const NOISE_GATE_RANGE_DB: (f32, f32) = (-90.0, -12.0);
+/// How much ←/→ move the A4 reference on the settings screen, in Hz
+const REFERENCE_STEP_HZ: f32 = 1.0;
+/// From baroque pitch to the sharpest orchestras
+const REFERENCE_RANGE_HZ: (f32, f32) = (415.0, 466.0);
+/// Columns between the x labels of the charts
+const X_LABEL_SPACING: u16 = 12;
+/// Rows between the y labels of the charts
+const Y_LABEL_SPACING: u16 = 3;
+/// Most labels an axis gets however big the chart
+const MAX_AXIS_LABELS: u16 = 11;
+/// Midi notes the note grid can mark, C0 to G9
+const NOTE_GRID_MIDI: (i32, i32) = (12, 127);
+/// Columns a semitone has to take on the fft chart for the note grid to mark
+/// every note, narrower and it only marks the Cs
+const NOTE_GRID_MIN_COLUMNS: f64 = 2.0;
+/// Widest the cents gauge under the debug screen's note gets
+const DEBUG_GAUGE_WIDTH: usize = 25;
+/// Room the cents next to the debug screen's gauge take
+const DEBUG_GAUGE_LABEL_WIDTH: usize = 11;
+/// Colors the fft chart's line takes from quiet to loud
+const SPECTRUM_COLOR_STEPS: usize = 8;
+/// Between the notes of the note ticker
+const NOTE_TICKER_SEPARATOR: &str = " | ";
+/// Quieter stereo isn't blown up to fill the phase scope
+const PHASE_SCOPE_MIN_PEAK: f32 = 0.01;
+/// How far `<` and `>` move the trigger level, of the time domain chart's top
+const TRIGGER_LEVEL_STEP: f32 = 0.05;
+/// Highest trigger level either way, of the time domain chart's top
+const TRIGGER_LEVEL_MAX: f32 = 0.95;
+/// The levels the logs screen can be filtered to, from the fewest lines to
+/// the most
+const LOG_LEVELS: [Level; 4] = [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG];
+/// The screens on the tab bar, with the key that opens each
+const TABS: [(AppScreen, char, &str); 13] = [
+    (AppScreen::Debug, 'd', "Debug"),
+    (AppScreen::Tutor, 't', "Tutor"),
+    (AppScreen::Tuner, 'u', "Tuner"),
+    (AppScreen::GuitarTuner, 's', "Guitar"),
+    (AppScreen::PitchContour, 'p', "Contour"),
+    (AppScreen::Spectrogram, 'w', "Spectrogram"),
+    (AppScreen::Vowels, 'v', "Vowels"),
+    (AppScreen::InstrumentSelect, 'n', "Instrument"),
+    (AppScreen::DeviceSelect, 'i', "Input"),
+    (AppScreen::Settings, 'k', "Settings"),
+    (AppScreen::PhaseScope, 'G', "Phase"),
+    (AppScreen::Errors, 'e', "Errors"),
+    (AppScreen::Logs, 'L', "Logs"),
+];
+/// Bars of the bar spectrum per octave, the third-octave bands of hardware
+/// analyzers
+const BAR_BANDS_PER_OCTAVE: f64 = 3.0;
+/// How fast a bar falls back once its band gets quieter, in dB per second
+const BAR_FALL_DB_PER_S: f64 = 60.0;
+/// How long a bar's peak marker stays put before it falls too
+const BAR_PEAK_HOLD: Duration = Duration::from_millis(1000);
+/// How fast a peak marker falls after its hold, in dB per second
+const BAR_PEAK_FALL_DB_PER_S: f64 = 20.0;
+/// How long the fft chart's peak-hold line stays put at a peak
+const SPECTRUM_PEAK_HOLD: Duration = Duration::from_millis(1500);
+/// How fast the peak-hold line falls after its hold, in dB per second
+const SPECTRUM_PEAK_FALL_DB_PER_S: f64 = 20.0;
+/// Time constant of the fft chart's averaged spectrum, in seconds
+const SPECTRUM_AVERAGE_SECS: f64 = 1.0;
+/// How far above the loudest recent value an auto-scaled y axis goes
+const AUTO_SCALE_HEADROOM: f64 = 1.2;
+/// Fraction of an auto-scaled y axis' top kept after a second while it falls
+const AUTO_SCALE_FALL_PER_S: f64 = 0.5;
+/// Lowest an auto-scaled y axis goes, as a fraction of its pinned top, so
+/// silence isn't blown up into a full chart of noise
+const AUTO_SCALE_MIN_FRACTION: f64 = 0.01;
+/// Semitones above C of the white keys of a piano
+const WHITE_KEYS: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+/// Columns of a white key on the piano, its right edge included
+const PIANO_KEY_WIDTH: usize = 4;
+/// Fewest and most octaves the piano shows, as many as fit in between
+const PIANO_OCTAVES: (usize, usize) = (2, 4);
+/// Columns of the string names left of the fretboard
+const FRETBOARD_LABEL_WIDTH: usize = 3;
+/// Columns of a fret of the fretboard, the fret wire included
+const FRETBOARD_FRET_WIDTH: usize = 6;
+/// Rows of the fretboard: the strings, the fret numbers and the border
+const FRETBOARD_HEIGHT: u16 = 9;
+/// Frets with an inlay dot, which get numbered
+const FRETBOARD_INLAYS: [usize; 6] = [3, 5, 7, 9, 12, 15];
+/// Cells the tuner's strobe pattern moves per second for every cent off
+const STROBE_CELLS_PER_CENT_S: f32 = 0.5;
+/// Width of a stripe of the strobe pattern, the gaps between are as wide
+const STROBE_STRIPE: usize = 3;
+/// Cents either side of the note a tuning gauge covers
+const GAUGE_RANGE_CENTS: f32 = 50.0;
+/// Lowest top frequency the spectrogram can be narrowed down to
+const SPECTROGRAM_MIN_HZ: f32 = 250.0;
+/// Steps the spectrogram's color range can be changed by, and its bounds
+const SPECTROGRAM_RANGE_STEP_DB: f32 = 10.0;
+const SPECTROGRAM_RANGE_DB: (f32, f32) = (30.0, 120.0);
+/// Windows of formants the vowel chart trails behind the current one
+const FORMANT_TRAIL: usize = 20;
+/// F2 range of the vowel chart, front vowels on the left like the ipa chart
+const VOWEL_F2_RANGE: (f64, f64) = (600.0, 2600.0);
+/// F1 range of the vowel chart, open vowels at the bottom
+const VOWEL_F1_RANGE: (f64, f64) = (200.0, 1000.0);
+/// (vowel, F1, F2) averages of adult men from Peterson and Barney (1952).
+/// Women and children sit 15 to 30% higher
+const REFERENCE_VOWELS: [(&str, f64, f64); 10] = [
+    ("i", 270.0, 2290.0),
+    ("ɪ", 390.0, 1990.0),
+    ("ɛ", 530.0, 1840.0),
+    ("æ", 660.0, 1720.0),
+    ("ɑ", 730.0, 1090.0),
+    ("ɔ", 570.0, 840.0),
+    ("ʊ", 440.0, 1020.0),
+    ("u", 300.0, 870.0),
+    ("ʌ", 640.0, 1190.0),
+    ("ɝ", 490.0, 1350.0),
+];
+/// How long the note box lights up after a note attack
+const ONSET_FLASH: Duration = Duration::from_millis(150);
+/// Windows of mfcc the heatmap remembers, more than fit on most terminals
+const MFCC_HISTORY: usize = 512;
+/// How far back the pitch contour goes
+const PITCH_CONTOUR_SPAN: Duration = Duration::from_secs(10);
+/// Fewest semitones the pitch contour spans, it widens to fit what's played
+const PITCH_CONTOUR_MIN_SEMITONES: f64 = 12.0;
+/// Coefficients this far from 0 get the strongest color in the heatmap
+const MFCC_COLOR_RANGE: f32 = 20.0;
+/// How long the clipping warning stays up after the input clipped
+const CLIP_HOLD: Duration = Duration::from_secs(2);
+/// dB below full scale the vu meters go down to
+const VU_RANGE_DB: f64 = 60.0;
+/// Levels the vu meters are marked at
+const VU_TICKS_DB: [f64; 8] = [0.0, -6.0, -12.0, -18.0, -24.0, -36.0, -48.0, -60.0];
+/// Above this a vu meter is yellow, and red above `VU_HOT_DB`
+const VU_WARM_DB: f64 = -18.0;
+const VU_HOT_DB: f64 = -6.0;
+/// How fast a vu meter falls back when it gets quieter, in dB per second
+const VU_FALL_DB_PER_S: f64 = 24.0;
+/// How long a vu meter's peak marker stays put before it falls
+const VU_PEAK_HOLD: Duration = Duration::from_millis(1500);
+/// How fast the peak marker falls after its hold, in dB per second
+const VU_PEAK_FALL_DB_PER_S: f64 = 12.0;
+/// Width of a vu meter's bar, the meters are a column apart
+const VU_BAR_WIDTH: u16 = 2;
+/// Most stream errors kept around for the errors screen
+const MAX_STREAM_ERRORS: usize = 100;
+
+/// A notification in the corner, like a lost device or a finished recording
+struct Toast {
+    message: String,
+    /// Shown in red
+    error: bool,
+    at: Instant,
+}
+
+struct StreamErrorItem {
+    message: String,
+    /// How many times in a row this error came in
+    count: usize,
+    last_seen: Instant,
+}
+
 struct NoteHistoryItem {
     note: Note,
     frequency: Frequency,
+    /// When the note started
+    time: Instant,
+}
+
+/// How often the ui redraws and takes in new analysis, from the config file
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RefreshOptions {
+    /// Redraws per second
+    pub fps: f32,
+    /// Times per second the latest analysis is taken in
+    pub data_rate: f32,
+}
+
+impl Default for RefreshOptions {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            data_rate: 20.0,
+        }
+    }
 }
 
 pub struct App {
@@ -46,10 +1077,284 @@ pub struct App {
     input_file_path: Option<PathBuf>,
     tutor: Option<Tutor>,
     note_history: Vec<NoteHistoryItem>,
+    /// How far off the steady notes have been over the session
+    drift: TuningDrift,
+    /// The guitar string being played, low E being 0
+    guitar_string: Option<usize>,
+    /// Cents off of the last reading of each guitar string
+    guitar_cents: [Option<f32>; 6],
+    /// The last note the tuner heard clearly and its frequency
+    tuner_reading: Option<(NoteReading, Frequency)>,
+    /// The note is sounding in the latest window, not just the last heard
+    tuner_live: bool,
+    /// How far the strobe pattern has moved, in cells
+    strobe_phase: f32,
+    /// When `strobe_phase` was last moved
+    strobe_time: Instant,
+    listener_options: ListenerOptions,
+    tuning: Tuning,
+    /// What `tuning` was made from, for changing its reference
+    tuning_options: TuningOptions,
+    refresh: RefreshOptions,
+    /// Colors of everything
+    theme: Theme,
+    /// The built-in theme `theme` is
+    theme_name: ThemeName,
+    /// What the fft and time domain charts' lines are drawn with
+    marker: ChartMarker,
+    /// Colors from the config file, which stay when the theme is changed
+    theme_options: ThemeOptions,
+    /// Recent spectra of the main input, filled by its analysis thread
+    spectrum_history: Option<SharedSpectrumHistory>,
+    /// The charts and readings stay as they were while the audio goes on
+    frozen: bool,
+    /// Copy of `spectrum_history` from when the display was frozen
+    frozen_spectrum_history: Option<SharedSpectrumHistory>,
+    /// What the frequency chart shows
+    spectrum_view: SpectrumView,
+    /// Scale of the fft chart
+    freqs_scale: MagnitudeScale,
+    /// Scale of the mel chart
+    mel_scale: MagnitudeScale,
+    /// The fft chart's frequency axis is in octaves, every interval as wide
+    /// as it sounds
+    log_frequency: bool,
+    /// Faint lines up the fft chart at the notes, with their names along the top
+    note_grid: bool,
+    /// The debug screen lists all the spectral features, not just the flatness
+    show_features: bool,
+    /// The fft chart is drawn as a bar per third of an octave instead of a line
+    spectrum_bars: bool,
+    /// The y axes of the time domain, fft and mel charts follow what's
+    /// charted instead of staying pinned
+    auto_scale: bool,
+    /// Top of the time domain chart, which also goes as far below 0
+    time_domain_top: AutoScale,
+    /// Top of the fft chart's linear magnitudes
+    freqs_top: AutoScale,
+    /// Top of the mel chart's linear magnitudes
+    mel_top: AutoScale,
+    /// When the y axes were last scaled
+    auto_scale_time: Instant,
+    /// The part of the fft chart's frequency axis in view, as fractions of
+    /// its full width
+    freq_view: (f64, f64),
+    /// Which parts of the debug screen are showing
+    debug_panels: DebugPanels,
+    /// The help is drawn over the screen
+    help_shown: bool,
+    /// Lines of the help scrolled past
+    help_scroll: u16,
+    /// Most lines of the help that can be scrolled past, and how many show at
+    /// once, as of the last frame
+    help_max_scroll: Cell<u16>,
+    help_page: Cell<u16>,
+    /// Frame rate, analysis time and dropped updates are drawn over the screen
+    perf_shown: bool,
+    /// When the screen was drawn over the last second
+    frame_times: VecDeque<Instant>,
+    /// When analysis of the main input last came in
+    last_data_time: Option<Instant>,
+    /// Analysis of the main input that a newer one replaced before it was shown
+    stale_updates: usize,
+    /// Where the time domain chart starts its window
+    trigger: Trigger,
+    /// Level the trigger fires at, as a fraction of the time domain chart's top
+    trigger_level: f32,
+    /// The chart drawn over the whole terminal instead of the screen
+    full_screen: Option<FullScreenChart>,
+    /// Where the fft chart last plotted its line, for the mouse
+    freqs_plot: Cell<Rect>,
+    /// The x axis' bounds the fft chart was last drawn with, in Hz or octaves
+    freqs_bounds: Cell<(f64, f64)>,
+    /// Columns where a drag over the fft chart started and is now
+    freqs_drag: Option<(u16, u16)>,
+    /// The frequency clicked on the fft chart, marked with its note
+    inspected_hz: Option<f64>,
+    /// The user's own screens from the config, opened with F1, F2 and so on
+    layouts: Vec<LayoutOptions>,
+    /// Where the tab bar was last drawn, for clicks on it
+    tabs_area: Cell<Rect>,
+    /// Falling levels and peaks of the bars, lowest band first
+    bar_levels: Vec<BarLevel>,
+    /// When `bar_levels` were last updated, they fall by the time since
+    bar_levels_time: Instant,
+    /// Peaks of each bin of the fft chart, held for a moment so transients
+    /// stay visible
+    held_peaks: Vec<HeldPeak>,
+    /// When `held_peaks` were last updated
+    held_peaks_time: Instant,
+    /// What the fft chart's line is drawn from
+    persistence: SpectrumPersistence,
+    /// The averaged or max-held spectrum, empty when showing the latest
+    accumulated_spectrum: Vec<(f64, f64)>,
+    /// When `accumulated_spectrum` was last updated
+    accumulated_spectrum_time: Instant,
+    /// Mfcc of every window of the selected channel, oldest first
+    mfcc_history: VecDeque<Vec<f32>>,
+    /// Formants of the last few windows of the selected channel, oldest first
+    formant_history: VecDeque<Option<Formants>>,
+    /// Pitch of every window of the selected channel over the last
+    /// `PITCH_CONTOUR_SPAN`, as a fractional midi note, oldest first. `None`
+    /// where there wasn't a clear one.
+    pitch_contour: VecDeque<(Instant, Option<f32>)>,
+    /// Top of the spectrogram's frequency range, the instrument's display
+    /// range until it's changed
+    spectrogram_max_hz: Option<f32>,
+    /// dB below the loudest color the spectrogram turns black at
+    spectrogram_range_db: f32,
+    input_devices: Vec<InputDeviceInfo>,
+    selected_device_index: usize,
+    /// Row of the settings screen being changed, in `Setting::ALL`
+    selected_setting: usize,
+    selected_channel: usize,
+    /// Latest fundamental frequency and max magnitude of every analyzed channel
+    channel_fundamentals: BTreeMap<usize, (Frequency, f32)>,
+    /// Latest status message, like the setting just changed, and when it came
+    status: Option<(String, Instant)>,
+    /// Notifications in the corner, oldest first
+    toasts: VecDeque<Toast>,
+    stream_errors: Vec<StreamErrorItem>,
+    /// The least important log lines the logs screen shows
+    log_level: Level,
+    /// Log lines scrolled back from the newest, 0 follows new ones
+    logs_scroll: usize,
+    /// File given with `--record`, used for the first recording
+    record_path: Option<PathBuf>,
+    recordings_started: usize,
+    /// The file being recorded to right now
+    recording: Option<PathBuf>,
+    /// Device analyzed alongside the main input (`--second-device`)
+    second_device: Option<String>,
+    second_freq_data: Option<FreqData>,
+    selected_instrument_index: usize,
+    /// When the input last clipped, to keep the warning up for a bit
+    last_clip: Option<Instant>,
+    /// The vu meter of each channel being analyzed
+    channel_levels: BTreeMap<usize, MeterLevel>,
+    /// When the selected channel last had a note attack, for the flash
+    last_onset: Option<Instant>,
+    /// The input has been silent for a while and isn't being analyzed
+    idle: bool,
+    /// (left, right) samples of the main input's latest window, empty when
+    /// it's mono
+    stereo_samples: Vec<(f32, f32)>,
 }
 impl App {
-    pub fn new(input_file_path: Option<String>) -> Result<Self> {
-        let input_file_path = input_file_path.map(PathBuf::from);
+    pub fn new(cli: Cli, config: Config) -> Result<Self> {
+        let mut noise_gate = config.noise_gate;
+        let mut analysis = config.analysis;
+        if let Some(high_pass_hz) = cli.high_pass {
+            analysis.high_pass_hz = high_pass_hz;
+        }
+        if let Some(window) = cli.window {
+            analysis.window = window;
+        }
+        if let Some(pitch_detector) = cli.pitch_detector {
+            analysis.pitch_detector = pitch_detector;
+        }
+        if cli.goertzel {
+            analysis.mode = AnalysisMode::Goertzel;
+        }
+        if let Some(zero_padding) = cli.zero_padding {
+            analysis.zero_padding = zero_padding;
+        }
+        if let Some(max_frequency) = cli.max_frequency {
+            analysis.max_frequency = Some(max_frequency);
+        }
+        if !ZERO_PADDING_FACTORS.contains(&analysis.zero_padding) {
+            return Err(eyre!(
+                "zero_padding is {}, it has to be 1, 2 or 4",
+                analysis.zero_padding
+            ));
+        }
+        if !WINDOW_SIZES.contains(&analysis.window_size) {
+            return Err(eyre!(
+                "window_size is {}, it has to be one of {}",
+                analysis.window_size,
+                WINDOW_SIZES.iter().join(", ")
+            ));
+        }
+        let mut refresh = config.refresh;
+        if let Some(fps) = cli.fps {
+            refresh.fps = fps;
+        }
+        if let Some(data_rate) = cli.data_rate {
+            refresh.data_rate = data_rate;
+        }
+        if !(refresh.fps > 0.0 && refresh.data_rate > 0.0) {
+            return Err(eyre!(
+                "fps is {} and data_rate {}, they have to be above 0",
+                refresh.fps,
+                refresh.data_rate
+            ));
+        }
+        if let Some(threshold_db) = cli.noise_gate {
+            noise_gate.enabled = true;
+            noise_gate.threshold_db = threshold_db;
+        }
+        let device_from_config = cli.device.is_none() && config.device.is_some();
+        let mut listener_options = ListenerOptions {
+            host: cli.host,
+            device: cli.device.or(config.device),
+            sample_rate: cli.sample_rate,
+            loopback: cli.loopback,
+            audio_file: cli.audio_file,
+            speed: cli.speed,
+            channel_mode: if cli.per_channel {
+                ChannelMode::PerChannel
+            } else if cli.downmix {
+                ChannelMode::Downmix
+            } else if let Some(channel) = cli.channel {
+                ChannelMode::Single(channel as usize - 1)
+            } else {
+                ChannelMode::default()
+            },
+            gain_db: 0.0,
+            agc: cli.agc,
+            noise_gate,
+            source: 0,
+            monitor: cli.monitor.map(|device| MonitorOptions {
+                device: Some(device).filter(|device| !device.is_empty()),
+                latency: Duration::from_millis(cli.monitor_latency),
+            }),
+            analysis,
+            instrument: cli.instrument.unwrap_or(config.instrument),
+        };
+        // fail early (before the tui takes over the terminal) if the input doesn't exist
+        let mut second_device = None;
+        if let Some(audio_file) = &listener_options.audio_file {
+            let reader = hound::WavReader::open(audio_file)?;
+            check_channel(
+                listener_options.channel_mode,
+                reader.spec().channels as usize,
+            )?;
+        } else {
+            let host = select_host(listener_options.host.as_deref())?;
+            let device = match find_capture_device(&host, &listener_options) {
+                // the config's device may have been unplugged since, that's no reason not to start
+                Err(err) if device_from_config => {
+                    tracing::warn!("{err:#}, listening on the default device instead");
+                    listener_options.device = None;
+                    find_capture_device(&host, &listener_options)?
+                }
+                device => device?,
+            };
+            let channels = capture_config(&device, &listener_options)?.channels();
+            check_channel(listener_options.channel_mode, channels as usize)?;
+            if !listener_options.loopback {
+                listener_options.device = Some(device.name()?);
+            }
+            if let Some(monitor) = &listener_options.monitor {
+                find_output_device(&host, monitor.device.as_deref())?;
+            }
+            if let Some(selector) = cli.second_device.as_deref() {
+                second_device = Some(find_input_device(&host, Some(selector))?.name()?);
+            }
+        }
+        let tuning = Tuning::new(&config.tuning)?;
+        let theme_name = cli.theme.unwrap_or(config.theme.name);
+        let input_file_path = cli.file.map(PathBuf::from);
         let tutor = if let Some(input_file_path) = &input_file_path {
             Some(Self::set_tutor(input_file_path)?)
         } else {
@@ -59,11 +1364,38 @@ impl App {
             freq_data: FreqData {
                 data: vec![],
                 max_magnitude: 0.0,
+                full_scale: 0.0,
                 peak_frequency: 0.0,
                 fundamental_frequency: 0.0,
                 samples_n: 0,
                 sample_rate: 0,
                 time_domain_samples: vec![],
+                channel: 0,
+                channels: 0,
+                gated: false,
+                source: 0,
+                level: InputLevel::default(),
+                features: SpectralFeatures::default(),
+                pitch_confidence: 0.0,
+                pitch_method: PitchMethod::default(),
+                octave_correction: None,
+                mel_data: vec![],
+                mfcc: vec![],
+                pitches: vec![],
+                harmonics: HarmonicSeries::default(),
+                peaks: vec![],
+                formants: None,
+                envelope: None,
+                cqt: vec![],
+                cqt_constant_q_from: 0,
+                chroma: [0.0; 12],
+                chord: None,
+                key: None,
+                onset: false,
+                bpm: None,
+                vibrato: None,
+                analysis_time: Duration::ZERO,
+                dropped_windows: 0,
             },
             screen: if input_file_path.is_some() {
                 AppScreen::Tutor
@@ -73,103 +1405,1310 @@ impl App {
             input_file_path,
             tutor,
             note_history: vec![],
+            drift: TuningDrift::default(),
+            guitar_string: None,
+            guitar_cents: [None; 6],
+            tuner_reading: None,
+            tuner_live: false,
+            strobe_phase: 0.0,
+            strobe_time: Instant::now(),
+            listener_options,
+            tuning,
+            tuning_options: config.tuning,
+            refresh,
+            theme: config.theme.theme(theme_name),
+            theme_name,
+            marker: cli.marker.unwrap_or(config.theme.marker),
+            theme_options: config.theme,
+            spectrum_history: None,
+            frozen: false,
+            frozen_spectrum_history: None,
+            spectrum_view: SpectrumView::default(),
+            freqs_scale: MagnitudeScale::default(),
+            mel_scale: MagnitudeScale::default(),
+            log_frequency: false,
+            note_grid: false,
+            show_features: false,
+            spectrum_bars: false,
+            auto_scale: true,
+            time_domain_top: AutoScale::new(0.05),
+            freqs_top: AutoScale::new(40.0),
+            mel_top: AutoScale::new(40.0),
+            auto_scale_time: Instant::now(),
+            freq_view: (0.0, 1.0),
+            debug_panels: DebugPanels {
+                stats: true,
+                freqs: true,
+                time_domain: true,
+            },
+            help_shown: false,
+            help_scroll: 0,
+            help_max_scroll: Cell::new(0),
+            help_page: Cell::new(1),
+            perf_shown: false,
+            frame_times: VecDeque::new(),
+            last_data_time: None,
+            stale_updates: 0,
+            trigger: Trigger::default(),
+            trigger_level: 0.0,
+            full_screen: None,
+            freqs_plot: Cell::new(Rect::default()),
+            freqs_bounds: Cell::new((0.0, 1.0)),
+            freqs_drag: None,
+            inspected_hz: None,
+            layouts: config.layouts,
+            tabs_area: Cell::new(Rect::default()),
+            bar_levels: vec![],
+            bar_levels_time: Instant::now(),
+            held_peaks: vec![],
+            held_peaks_time: Instant::now(),
+            persistence: SpectrumPersistence::default(),
+            accumulated_spectrum: vec![],
+            accumulated_spectrum_time: Instant::now(),
+            mfcc_history: VecDeque::new(),
+            formant_history: VecDeque::new(),
+            pitch_contour: VecDeque::new(),
+            spectrogram_max_hz: None,
+            spectrogram_range_db: SPECTRUM_RANGE_DB as f32,
+            input_devices: vec![],
+            selected_device_index: 0,
+            selected_setting: 0,
+            selected_channel: 0,
+            channel_fundamentals: BTreeMap::new(),
+            status: None,
+            toasts: VecDeque::new(),
+            stream_errors: vec![],
+            log_level: Level::INFO,
+            logs_scroll: 0,
+            record_path: cli.record,
+            recordings_started: 0,
+            recording: None,
+            second_device,
+            second_freq_data: None,
+            last_clip: None,
+            channel_levels: BTreeMap::new(),
+            last_onset: None,
+            selected_instrument_index: 0,
+            idle: false,
+            stereo_samples: vec![],
         })
     }
 
-    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
-        let tick_rate = Duration::from_millis(250);
+    /// Runs until the user quits, then gives a summary of the session to print
+    /// once the terminal is back to normal
+    pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<Option<String>> {
+        let frame_interval = Duration::from_secs_f32(1.0 / self.refresh.fps);
+        let data_interval = Duration::from_secs_f32(1.0 / self.refresh.data_rate);
         let mut last_tick = Instant::now();
+        let mut last_frame = Instant::now();
+        let mut redraw = true;
         let (tx, rx) = mpsc::channel();
         let (tx_to_audio, rx_from_ui) = mpsc::channel();
-        let audio_thread = std::thread::spawn(move || {
-            AudioListener::new(tx, rx_from_ui).run().unwrap();
+        let listener_options = self.listener_options.clone();
+        let mut second_listener = self.second_device.clone().map(|device| {
+            let (tx_to_second, rx_from_ui) = mpsc::channel();
+            let options = ListenerOptions {
+                device: Some(device),
+                loopback: false,
+                channel_mode: ChannelMode::default(),
+                source: 1,
+                monitor: None,
+                ..listener_options.clone()
+            };
+            let tx = tx.clone();
+            let thread =
+                std::thread::spawn(move || AudioListener::new(tx, rx_from_ui, options).run());
+            (tx_to_second, thread)
         });
+        let audio_thread =
+            std::thread::spawn(move || AudioListener::new(tx, rx_from_ui, listener_options).run());
+        if self.record_path.is_some() {
+            self.toggle_recording(&tx_to_audio);
+        }
         loop {
-            terminal.draw(|frame| self.draw(frame))?;
-
-            let timeout = tick_rate.saturating_sub(last_tick.elapsed());
-            if event::poll(timeout)? {
-                if let Event::Key(key) = event::read()? {
-                    if key.code == KeyCode::Char('q') {
-                        tx_to_audio.send(TerminalMessage::Quit).unwrap();
-                        break;
-                    } else if key.code == KeyCode::Char('d') {
-                        self.set_screen(AppScreen::Debug)?;
-                    } else if key.code == KeyCode::Char('t') {
-                        self.set_screen(AppScreen::Tutor)?;
-                    } else if key.code == KeyCode::Char('h') {
-                        self.set_screen(AppScreen::Help)?;
+            // there's nothing left to show without the main input, its error is returned below
+            if audio_thread.is_finished() {
+                break;
+            }
+            // the second input going away still leaves the first one to look at
+            if second_listener
+                .as_ref()
+                .is_some_and(|(_, thread)| thread.is_finished())
+                && let Some((_, thread)) = second_listener.take()
+            {
+                let err = join_listener(thread).err();
+                let message = err.map_or("The second input stopped".to_string(), |err| {
+                    format!("The second input stopped: {err}")
+                });
+                tracing::error!("{message}");
+                self.second_freq_data = None;
+                self.notify(message, true);
+                redraw = true;
+            }
+            // a key shows its effect right away, otherwise it waits for the next frame
+            if redraw || last_frame.elapsed() >= frame_interval {
+                terminal.draw(|frame| self.draw(frame))?;
+                last_frame = Instant::now();
+                while self
+                    .frame_times
+                    .front()
+                    .is_some_and(|time| last_frame - *time > Duration::from_secs(1))
+                {
+                    self.frame_times.pop_front();
+                }
+                self.frame_times.push_back(last_frame);
+            }
+
+            let timeout = frame_interval
+                .saturating_sub(last_frame.elapsed())
+                .min(data_interval.saturating_sub(last_tick.elapsed()));
+            let event = if event::poll(timeout)? {
+                Some(event::read()?)
+            } else {
+                None
+            };
+            // moving the mouse over the screen changes nothing, it can wait for the next frame
+            redraw = match &event {
+                Some(Event::Key(key)) => key.kind == KeyEventKind::Press,
+                Some(Event::Mouse(mouse)) => !matches!(mouse.kind, MouseEventKind::Moved),
+                Some(Event::Resize(..)) => true,
+                _ => false,
+            };
+            if let Some(Event::Mouse(mouse)) = event {
+                self.on_mouse(mouse)?;
+            }
+            if let Some(Event::Key(key)) = event
+                && key.kind == KeyEventKind::Press
+            {
+                if key.code == KeyCode::Char('q') {
+                    let _ = tx_to_audio.send(TerminalMessage::Quit);
+                    break;
+                } else if key.code == KeyCode::Char('d') {
+                    self.set_screen(AppScreen::Debug)?;
+                } else if key.code == KeyCode::Char('t') {
+                    self.set_screen(AppScreen::Tutor)?;
+                } else if matches!(key.code, KeyCode::Char('?' | 'h')) {
+                    self.help_shown = !self.help_shown;
+                    self.help_scroll = 0;
+                } else if key.code == KeyCode::Esc && self.help_shown {
+                    self.help_shown = false;
+                } else if self.help_shown
+                    && matches!(
+                        key.code,
+                        KeyCode::Up | KeyCode::Down | KeyCode::PageUp | KeyCode::PageDown
+                    )
+                {
+                    let page = self.help_page.get();
+                    self.help_scroll = match key.code {
+                        KeyCode::Up => self.help_scroll.saturating_sub(1),
+                        KeyCode::PageUp => self.help_scroll.saturating_sub(page),
+                        KeyCode::PageDown => self.help_scroll.saturating_add(page),
+                        _ => self.help_scroll.saturating_add(1),
+                    }
+                    // stop at the last line instead of scrolling past it
+                    .min(self.help_max_scroll.get());
+                } else if key.code == KeyCode::Char('i') {
+                    self.set_screen(AppScreen::DeviceSelect)?;
+                } else if key.code == KeyCode::Char('e') {
+                    self.set_screen(AppScreen::Errors)?;
+                } else if key.code == KeyCode::Char('L') {
+                    self.set_screen(AppScreen::Logs)?;
+                } else if key.code == KeyCode::Char('G') {
+                    self.set_screen(AppScreen::PhaseScope)?;
+                } else if let KeyCode::F(n) = key.code
+                    && (1..=self.layouts.len()).contains(&(n as usize))
+                {
+                    self.set_screen(AppScreen::Layout(n as usize - 1))?;
+                } else if key.code == KeyCode::Char('n') {
+                    self.set_screen(AppScreen::InstrumentSelect)?;
+                } else if key.code == KeyCode::Char('v') {
+                    self.set_screen(AppScreen::Vowels)?;
+                } else if key.code == KeyCode::Char('s') {
+                    self.set_screen(AppScreen::GuitarTuner)?;
+                } else if key.code == KeyCode::Char('w') {
+                    self.set_screen(AppScreen::Spectrogram)?;
+                } else if key.code == KeyCode::Char('u') {
+                    self.set_screen(AppScreen::Tuner)?;
+                } else if key.code == KeyCode::Char('p') {
+                    self.set_screen(AppScreen::PitchContour)?;
+                } else if key.code == KeyCode::Char('k') {
+                    self.set_screen(AppScreen::Settings)?;
+                } else if key.code == KeyCode::Char('c') {
+                    self.select_next_channel();
+                } else if matches!(key.code, KeyCode::Char('+') | KeyCode::Char('=')) {
+                    self.change_gain(GAIN_STEP_DB, &tx_to_audio);
+                } else if key.code == KeyCode::Char('-') {
+                    self.change_gain(-GAIN_STEP_DB, &tx_to_audio);
+                } else if key.code == KeyCode::Char('g') {
+                    self.listener_options.noise_gate.enabled =
+                        !self.listener_options.noise_gate.enabled;
+                    let _ = tx_to_audio.send(TerminalMessage::SetNoiseGate(
+                        self.listener_options.noise_gate.enabled,
+                    ));
+                } else if key.code == KeyCode::Char(' ') {
+                    self.toggle_freeze();
+                } else if key.code == KeyCode::Char('S') {
+                    self.save_snapshot();
+                } else if key.code == KeyCode::Char('F') {
+                    self.toggle_full_screen();
+                } else if key.code == KeyCode::Esc && self.full_screen.is_some() {
+                    self.full_screen = None;
+                } else if key.code == KeyCode::Char('m') {
+                    let was_mfcc = self.spectrum_view == SpectrumView::Mfcc;
+                    self.spectrum_view = self.spectrum_view.next();
+                    let mfcc = self.spectrum_view == SpectrumView::Mfcc;
+                    if mfcc != was_mfcc {
+                        // the heatmap starts over rather than jump from the last time
+                        self.mfcc_history.clear();
+                        let _ = tx_to_audio.send(TerminalMessage::SetMfcc(mfcc));
+                    }
+                } else if key.code == KeyCode::Char('b') {
+                    match self.spectrum_view {
+                        SpectrumView::Linear => self.freqs_scale = self.freqs_scale.toggle(),
+                        SpectrumView::Mel => self.mel_scale = self.mel_scale.toggle(),
+                        // the constant-q is in dB already, the rest aren't magnitudes
+                        _ => {}
                     }
+                } else if key.code == KeyCode::Char('o') {
+                    self.log_frequency = !self.log_frequency;
+                } else if key.code == KeyCode::Char('N') {
+                    self.note_grid = !self.note_grid;
+                } else if key.code == KeyCode::Char('A') {
+                    self.persistence = self.persistence.next();
+                    // starts over from the next spectrum
+                    self.accumulated_spectrum.clear();
+                    self.status = Some((
+                        format!("Spectrum: {}", self.persistence.name()),
+                        Instant::now(),
+                    ));
+                } else if key.code == KeyCode::Char('f') {
+                    self.show_features = !self.show_features;
+                } else if key.code == KeyCode::Char('x') {
+                    self.spectrum_bars = !self.spectrum_bars;
+                } else if key.code == KeyCode::Char('y') {
+                    self.auto_scale = !self.auto_scale;
+                } else if key.code == KeyCode::Char('T') {
+                    self.theme_name = self.theme_name.next();
+                    self.theme = self.theme_options.theme(self.theme_name);
+                    self.status = Some((format!("Theme: {}", self.theme_name), Instant::now()));
+                } else if key.code == KeyCode::Char('P') {
+                    self.perf_shown = !self.perf_shown;
+                } else if key.code == KeyCode::Char('M') {
+                    self.marker = self.marker.next();
+                    self.status = Some((format!("Chart marker: {}", self.marker), Instant::now()));
+                } else if key.code == KeyCode::Char('r') {
+                    self.toggle_recording(&tx_to_audio);
+                } else if matches!(key.code, KeyCode::Char('[' | ']')) {
+                    self.change_max_frequency(key.code == KeyCode::Char(']'), &tx_to_audio);
+                } else if key.code == KeyCode::Char('a') {
+                    self.listener_options.agc = !self.listener_options.agc;
+                    let _ = tx_to_audio.send(TerminalMessage::SetAgc(self.listener_options.agc));
+                } else if let AppScreen::DeviceSelect = self.screen {
+                    self.on_device_select_key(key.code, &tx_to_audio);
+                } else if let AppScreen::Welcome = self.screen {
+                    self.on_welcome_key(key.code, &tx_to_audio)?;
+                } else if let AppScreen::InstrumentSelect = self.screen {
+                    self.on_instrument_select_key(key.code, &tx_to_audio);
+                } else if let AppScreen::Spectrogram = self.screen {
+                    self.on_spectrogram_key(key.code);
+                } else if self.shows_debug_panel() {
+                    self.on_debug_key(key.code);
+                } else if let AppScreen::Logs = self.screen {
+                    self.on_logs_key(key.code);
+                } else if let AppScreen::Settings = self.screen {
+                    let tx_to_second = second_listener.as_ref().map(|(tx, _)| tx);
+                    self.on_settings_key(key.code, &tx_to_audio, tx_to_second)?;
+                }
+                if let Some((tx_to_second, _)) = &second_listener
+                    && (matches!(
+                        key.code,
+                        KeyCode::Char('+' | '=' | '-' | 'g' | 'a' | '[' | ']')
+                    ) || matches!(self.screen, AppScreen::InstrumentSelect)
+                        && key.code == KeyCode::Enter)
+                {
+                    // both inputs get the same processing
+                    self.send_input_settings(tx_to_second);
                 }
             }
-            if last_tick.elapsed() >= tick_rate {
-                if let Ok(data) = rx.try_recv() {
-                    let mut latest_data = data;
-                    while let Ok(data) = rx.try_recv() {
-                        latest_data = data;
+            if last_tick.elapsed() >= data_interval {
+                // with per-channel analysis we get one data per channel, keep the latest of each
+                let mut latest_data = BTreeMap::new();
+                while let Ok(message) = rx.try_recv() {
+                    match message {
+                        AudioMessage::FreqData(_) if self.frozen => {}
+                        AudioMessage::FreqData(data) if data.source != 0 => {
+                            self.second_freq_data = Some(*data);
+                        }
+                        AudioMessage::FreqData(mut data) => {
+                            self.idle = false;
+                            // the heatmap wants every window, not just the ones that get shown
+                            if data.channel == self.selected_channel {
+                                // empty while the mfcc view is off
+                                if !data.mfcc.is_empty() {
+                                    if self.mfcc_history.len() == MFCC_HISTORY {
+                                        self.mfcc_history.pop_front();
+                                    }
+                                    self.mfcc_history.push_back(data.mfcc.clone());
+                                }
+                                if self.formant_history.len() == FORMANT_TRAIL {
+                                    self.formant_history.pop_front();
+                                }
+                                self.formant_history.push_back(data.formants);
+                                let now = Instant::now();
+                                while self
+                                    .pitch_contour
+                                    .front()
+                                    .is_some_and(|(time, _)| now - *time > PITCH_CONTOUR_SPAN)
+                                {
+                                    self.pitch_contour.pop_front();
+                                }
+                                let clear = data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+                                    && data.features.flatness < TUTOR_MAX_FLATNESS
+                                    && data.max_magnitude > self.preset().min_magnitude;
+                                let pitch = self
+                                    .tuning
+                                    .note(data.fundamental_frequency)
+                                    .filter(|_| clear)
+                                    .map(|reading| {
+                                        reading.midi_note_number as f32 + reading.cents / 100.0
+                                    });
+                                self.pitch_contour.push_back((now, pitch));
+                            }
+                            // only the latest data gets shown, but an attack in between still counts
+                            if latest_data
+                                .get(&data.channel)
+                                .is_some_and(|previous: &FreqData| previous.onset)
+                            {
+                                data.onset = true;
+                            }
+                            self.last_data_time = Some(Instant::now());
+                            if latest_data.insert(data.channel, *data).is_some() {
+                                self.stale_updates += 1;
+                            }
+                        }
+                        AudioMessage::Idle(0) => self.idle = true,
+                        AudioMessage::SpectrumHistory(0, history) => {
+                            self.spectrum_history = Some(history);
+                        }
+                        AudioMessage::SpectrumHistory(..) => {}
+                        AudioMessage::Stereo(0, samples) if !self.frozen => {
+                            self.stereo_samples = samples;
+                        }
+                        AudioMessage::Stereo(..) => {}
+                        AudioMessage::Idle(_) => self.second_freq_data = None,
+                        AudioMessage::Status(status) => self.notify(status, false),
+                        AudioMessage::Error(error) => self.notify(error, true),
+                        AudioMessage::StreamError(message) => {
+                            self.on_stream_error(message);
+                        }
+                        AudioMessage::RecordingStarted(path) => {
+                            self.notify(format!("Recording to {}", path.display()), false);
+                            self.recording = Some(path);
+                        }
+                        AudioMessage::RecordingStopped(path) => {
+                            // a newer recording may have started already
+                            if self.recording.as_ref() == Some(&path) {
+                                self.recording = None;
+                            }
+                        }
                     }
-                    self.on_tick(latest_data);
+                }
+                for data in latest_data.into_values() {
+                    self.on_tick(data);
                 }
                 last_tick = Instant::now();
             }
         }
-        audio_thread.join().unwrap();
-        Ok(())
+        if let Some((tx_to_second, thread)) = second_listener {
+            let _ = tx_to_second.send(TerminalMessage::Quit);
+            join_listener(thread)?;
+        }
+        join_listener(audio_thread)?;
+        Ok(self.drift.summary().map(|summary| {
+            let notes = self
+                .drift
+                .note_averages()
+                .map(|(note, cents, readings)| {
+                    format!("{note}: {cents:+.0} cents on average ({readings} readings)")
+                })
+                .join("\n");
+            format!("{summary}\n{notes}")
+        }))
     }
     fn on_tick(&mut self, data: FreqData) {
+        if data.level.clipped {
+            self.last_clip = Some(Instant::now());
+        }
+        self.channel_levels
+            .entry(data.channel)
+            .or_insert_with(MeterLevel::new)
+            .update(&data.level);
+        if data.onset && data.channel == self.selected_channel {
+            self.last_onset = Some(Instant::now());
+            if let Some(tutor) = self.tutor.as_mut() {
+                tutor.attacked = true;
+            }
+        }
+        self.channel_fundamentals.insert(
+            data.channel,
+            (data.fundamental_frequency, data.max_magnitude),
+        );
+        if self.channel_fundamentals.len() == 1 {
+            // only one channel is analyzed, so that's the one to show
+            self.selected_channel = data.channel;
+        }
+        if data.channel != self.selected_channel {
+            return;
+        }
         self.freq_data = data;
-        if let Some(note) = get_note_from_frequency(self.freq_data.fundamental_frequency) {
+        if self.spectrum_bars {
+            self.update_bar_levels();
+        }
+        self.update_auto_scale();
+        self.update_held_peaks();
+        self.update_accumulated_spectrum();
+        if let Some(note) = self.tuning.note_name(self.freq_data.fundamental_frequency) {
             let f = self.freq_data.fundamental_frequency;
-            if self.freq_data.max_magnitude > 100.0
-                && self.note_history.last().is_none_or(|n| {
-                    get_note_from_frequency(f) != get_note_from_frequency(n.frequency)
-                })
+            if self.freq_data.max_magnitude > self.preset().min_magnitude
+                && self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+                && self
+                    .note_history
+                    .last()
+                    .is_none_or(|n| self.tuning.note_name(f) != self.tuning.note_name(n.frequency))
             {
                 self.note_history.push(NoteHistoryItem {
                     note,
                     frequency: self.freq_data.fundamental_frequency,
+                    time: Instant::now(),
                 });
             }
         }
-        if self.freq_data.max_magnitude > 10.0 {
-            // chekc if if let chain are stable at this point
-            if let Some(note) = get_note_from_frequency(self.freq_data.fundamental_frequency) {
-                if let Some(tutor) = self.tutor.as_mut() {
-                    if let Some(next_note) = tutor.notes_sequence.get(tutor.current_note_index) {
-                        if let Ok(current_note) = note.parse::<MusicalNote>() {
-                            if let MusicalSound::Note(next_note) = next_note {
-                                if current_note == *next_note
-                                    && tutor.current_note_index < tutor.notes_sequence.len()
-                                {
-                                    let mut next_idx = tutor.current_note_index + 1;
-                                    if next_idx >= tutor.notes_sequence.len() {
-                                        tutor.current_note_index = next_idx;
-                                    } else {
-                                        while next_idx < tutor.notes_sequence.len()
-                                            && matches!(
-                                                tutor.notes_sequence[next_idx],
-                                                MusicalSound::Silence
-                                            )
-                                        {
-                                            next_idx += 1;
-                                        }
-                                        tutor.current_note_index = next_idx;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            };
+        let clear_pitch = self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+            && self.freq_data.features.flatness < TUTOR_MAX_FLATNESS
+            && self.freq_data.max_magnitude > self.preset().min_magnitude;
+        let now = Instant::now();
+        let elapsed = (now - self.strobe_time).as_secs_f32();
+        self.strobe_time = now;
+        self.tuner_live = false;
+        if clear_pitch && let Some(reading) = self.tuning.note(self.freq_data.fundamental_frequency)
+        {
+            // sharp turns the strobe right, flat left, in tune it stands still
+            self.strobe_phase += reading.cents * STROBE_CELLS_PER_CENT_S * elapsed;
+            self.strobe_phase = self.strobe_phase.rem_euclid(2.0 * STROBE_STRIPE as f32);
+            self.tuner_reading = Some((reading, self.freq_data.fundamental_frequency));
+            self.tuner_live = true;
+        }
+        self.guitar_string = if clear_pitch
+            && let Some((string, cents)) =
+                nearest_string(self.freq_data.fundamental_frequency, &self.tuning)
+        {
+            self.guitar_cents[string] = Some(cents);
+            Some(string)
+        } else {
+            None
+        };
+        // the pitch wobbles during the attack and sags in the release
+        if self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+            && self.freq_data.features.flatness < TUTOR_MAX_FLATNESS
+            && self
+                .freq_data
+                .envelope
+                .is_some_and(|envelope| envelope.phase == EnvelopePhase::Sustain)
+            && let Some(reading) = self.tuning.note(self.freq_data.fundamental_frequency)
+        {
+            self.drift.add(&reading);
+        }
+        // noise can land on the right note too, only pitched sounds count
+        if self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+            && self.freq_data.features.flatness < TUTOR_MAX_FLATNESS
+            && let Some(note) = self.tuning.note_name(self.freq_data.fundamental_frequency)
+            && let Ok(current_note) = note.parse::<ScientificNote>()
+            && let Some(tutor) = self.tutor.as_mut()
+            && let Some(MusicalSound::Note(next_note)) =
+                tutor.notes_sequence.get(tutor.current_note_index)
+            && next_note.matches(&current_note)
+            // the same note twice in a row has to be played twice
+            && (tutor.attacked || tutor.last_played.as_ref() != Some(&current_note))
+        {
+            tutor.last_played = Some(current_note);
+            tutor.attacked = false;
+            // rests are skipped, there's nothing to play for them
+            let mut next_idx = tutor.current_note_index + 1;
+            while matches!(
+                tutor.notes_sequence.get(next_idx),
+                Some(MusicalSound::Silence)
+            ) {
+                next_idx += 1;
+            }
+            tutor.current_note_index = next_idx;
+        }
+    }
+    fn toggle_recording(&mut self, tx_to_audio: &Sender<TerminalMessage>) {
+        if self.recording.is_some() {
+            let _ = tx_to_audio.send(TerminalMessage::StopRecording);
+            return;
+        }
+        self.recordings_started += 1;
+        let path = match &self.record_path {
+            Some(path) if self.recordings_started == 1 => path.clone(),
+            // don't overwrite the earlier takes
+            Some(path) => path.with_file_name(format!(
+                "{}-{}.wav",
+                path.file_stem().unwrap_or_default().to_string_lossy(),
+                self.recordings_started
+            )),
+            None => get_data_dir().join(format!(
+                "recording-{}.wav",
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            )),
+        };
+        let _ = tx_to_audio.send(TerminalMessage::StartRecording(path));
+    }
+    /// Saves the spectrum on screen to the data directory, as a csv and an svg
+    fn save_snapshot(&mut self) {
+        let stem = get_data_dir().join(format!(
+            "spectrum-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        ));
+        match std::fs::create_dir_all(get_data_dir())
+            .map_err(Into::into)
+            .and_then(|_| save_snapshot(&self.freq_data, stem))
+        {
+            Ok((csv_path, svg_path)) => self.notify(
+                format!(
+                    "Saved the spectrum to {} and {}",
+                    csv_path.display(),
+                    svg_path.display()
+                ),
+                false,
+            ),
+            Err(err) => {
+                tracing::error!("saving the spectrum failed: {err}");
+                self.notify(format!("Saving the spectrum failed: {err}"), true);
+            }
+        }
+    }
+    /// Puts `message` in the corner for a while, in red if it's an `error`
+    fn notify(&mut self, message: String, error: bool) {
+        self.toasts
+            .retain(|toast| toast.at.elapsed() < TOAST_DURATION);
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            message,
+            error,
+            at: Instant::now(),
+        });
+    }
+    fn send_input_settings(&self, tx: &Sender<TerminalMessage>) {
+        let _ = tx.send(TerminalMessage::SetGain(self.listener_options.gain_db));
+        let _ = tx.send(TerminalMessage::SetAgc(self.listener_options.agc));
+        let _ = tx.send(TerminalMessage::SetNoiseGate(
+            self.listener_options.noise_gate.enabled,
+        ));
+        let _ = tx.send(TerminalMessage::SetInstrument(
+            self.listener_options.instrument,
+        ));
+        if let Some(max_frequency) = self.listener_options.analysis.max_frequency {
+            let _ = tx.send(TerminalMessage::SetMaxFrequency(max_frequency));
+        }
+    }
+    /// The instrument's preset, with the spectrum's top changed if it was
+    fn preset(&self) -> InstrumentPreset {
+        self.listener_options
+            .instrument
+            .preset()
+            .with_max_display_hz(self.listener_options.analysis.max_frequency)
+    }
+    /// Doubles or halves the top of the spectrum, `up` or not
+    fn change_max_frequency(&mut self, up: bool, tx_to_audio: &Sender<TerminalMessage>) {
+        let max_hz = self.preset().max_display_hz;
+        let mut max_hz = if up { max_hz * 2.0 } else { max_hz / 2.0 };
+        if self.freq_data.sample_rate > 0 {
+            max_hz = max_hz.min(self.nyquist_hz());
+        }
+        let max_hz = max_hz.max(MAX_FREQUENCY_LOWEST_HZ);
+        self.listener_options.analysis.max_frequency = Some(max_hz);
+        let _ = tx_to_audio.send(TerminalMessage::SetMaxFrequency(max_hz));
+        self.status = Some((format!("Spectrum up to {max_hz:.0} Hz"), Instant::now()));
+    }
+    fn change_gain(&mut self, delta_db: f32, tx_to_audio: &Sender<TerminalMessage>) {
+        let gain_db =
+            (self.listener_options.gain_db + delta_db).clamp(GAIN_RANGE_DB.0, GAIN_RANGE_DB.1);
+        self.listener_options.gain_db = gain_db;
+        let _ = tx_to_audio.send(TerminalMessage::SetGain(gain_db));
+    }
+    fn on_stream_error(&mut self, message: String) {
+        self.notify(format!("Stream error: {message} (e for details)"), true);
+        if let Some(last) = self.stream_errors.last_mut()
+            && last.message == message
+        {
+            last.count += 1;
+            last.last_seen = Instant::now();
+            return;
+        }
+        if self.stream_errors.len() >= MAX_STREAM_ERRORS {
+            self.stream_errors.remove(0);
+        }
+        self.stream_errors.push(StreamErrorItem {
+            message,
+            count: 1,
+            last_seen: Instant::now(),
+        });
+    }
+    fn select_next_channel(&mut self) {
+        let next = self
+            .channel_fundamentals
+            .range(self.selected_channel + 1..)
+            .next()
+            .or_else(|| self.channel_fundamentals.iter().next());
+        if let Some((&channel, _)) = next
+            && channel != self.selected_channel
+        {
+            self.selected_channel = channel;
+            self.mfcc_history.clear();
+            self.formant_history.clear();
+            self.pitch_contour.clear();
         }
     }
     fn set_screen(&mut self, screen: AppScreen) -> Result<()> {
-        if let AppScreen::Tutor = screen {
-            self.reset_tutor()?;
+        match screen {
+            AppScreen::Tutor => self.reset_tutor()?,
+            AppScreen::DeviceSelect | AppScreen::Welcome => self.refresh_input_devices()?,
+            AppScreen::Settings if self.listener_options.audio_file.is_none() => {
+                self.refresh_input_devices()?
+            }
+            AppScreen::InstrumentSelect => {
+                self.selected_instrument_index = Instrument::ALL
+                    .iter()
+                    .position(|i| *i == self.listener_options.instrument)
+                    .unwrap_or(0);
+            }
+            _ => {}
         }
         self.screen = screen;
+        self.full_screen = None;
+        Ok(())
+    }
+    /// Opens the first-run guide instead of the debug screen
+    pub fn welcome(&mut self) -> Result<()> {
+        self.set_screen(AppScreen::Welcome)
+    }
+    /// Gives the whole terminal to the debug screen's fft chart, then its time
+    /// domain chart, then neither. The spectrogram screen just goes in and out.
+    fn toggle_full_screen(&mut self) {
+        self.full_screen = match (self.screen, self.full_screen) {
+            (AppScreen::Debug, None) => Some(FullScreenChart::Freqs),
+            (AppScreen::Debug, Some(FullScreenChart::Freqs)) => Some(FullScreenChart::TimeDomain),
+            (AppScreen::Spectrogram, None) => Some(FullScreenChart::Spectrogram),
+            _ => None,
+        };
+    }
+    fn refresh_input_devices(&mut self) -> Result<()> {
+        let host = select_host(self.listener_options.host.as_deref())?;
+        self.input_devices = list_input_devices(&host)?;
+        self.selected_device_index = self
+            .input_devices
+            .iter()
+            .position(|d| Some(&d.name) == self.listener_options.device.as_ref())
+            .unwrap_or(0);
+        Ok(())
+    }
+    fn on_device_select_key(&mut self, key_code: KeyCode, tx_to_audio: &Sender<TerminalMessage>) {
+        match key_code {
+            KeyCode::Up => {
+                self.selected_device_index = self.selected_device_index.saturating_sub(1);
+            }
+            KeyCode::Down if self.selected_device_index + 1 < self.input_devices.len() => {
+                self.selected_device_index += 1;
+            }
+            KeyCode::Enter => {
+                if let Some(device) = self.input_devices.get(self.selected_device_index) {
+                    let _ = tx_to_audio.send(TerminalMessage::SwitchDevice(device.name.clone()));
+                    self.listener_options.device = Some(device.name.clone());
+                    self.listener_options.loopback = false;
+                    self.channel_fundamentals.clear();
+                    self.channel_levels.clear();
+                }
+            }
+            KeyCode::Char('l') => {
+                self.listener_options.loopback = !self.listener_options.loopback;
+                self.channel_fundamentals.clear();
+                self.channel_levels.clear();
+                let _ =
+                    tx_to_audio.send(TerminalMessage::SetLoopback(self.listener_options.loopback));
+            }
+            _ => {}
+        }
+    }
+    /// The welcome screen picks the input like the input screen, esc leaves
+    /// it for the debug screen for good
+    fn on_welcome_key(
+        &mut self,
+        key_code: KeyCode,
+        tx_to_audio: &Sender<TerminalMessage>,
+    ) -> Result<()> {
+        if key_code != KeyCode::Esc {
+            self.on_device_select_key(key_code, tx_to_audio);
+            return Ok(());
+        }
+        let device = if self.listener_options.loopback {
+            None
+        } else {
+            self.listener_options.device.as_deref()
+        };
+        match write_starter_config(&value_name(&self.listener_options.instrument), device) {
+            Ok(path) => self.notify(format!("Saved a config to {}", path.display()), false),
+            Err(err) => {
+                tracing::error!("{err:#}");
+                self.notify(format!("Couldn't save a config: {err}"), true);
+            }
+        }
+        self.set_screen(AppScreen::Debug)
+    }
+    fn on_instrument_select_key(
+        &mut self,
+        key_code: KeyCode,
+        tx_to_audio: &Sender<TerminalMessage>,
+    ) {
+        match key_code {
+            KeyCode::Up => {
+                self.selected_instrument_index = self.selected_instrument_index.saturating_sub(1);
+            }
+            KeyCode::Down if self.selected_instrument_index + 1 < Instrument::ALL.len() => {
+                self.selected_instrument_index += 1;
+            }
+            KeyCode::Enter => {
+                let instrument = Instrument::ALL[self.selected_instrument_index];
+                self.listener_options.instrument = instrument;
+                let _ = tx_to_audio.send(TerminalMessage::SetInstrument(instrument));
+                self.status = Some((format!("Listening for {instrument}"), Instant::now()));
+            }
+            _ => {}
+        }
+    }
+    fn on_settings_key(
+        &mut self,
+        key_code: KeyCode,
+        tx_to_audio: &Sender<TerminalMessage>,
+        tx_to_second: Option<&Sender<TerminalMessage>>,
+    ) -> Result<()> {
+        match key_code {
+            KeyCode::Up => {
+                self.selected_setting = self.selected_setting.saturating_sub(1);
+            }
+            KeyCode::Down if self.selected_setting + 1 < Setting::ALL.len() => {
+                self.selected_setting += 1;
+            }
+            KeyCode::Left => self.change_setting(-1, tx_to_audio, tx_to_second)?,
+            KeyCode::Right => self.change_setting(1, tx_to_audio, tx_to_second)?,
+            _ => {}
+        }
+        Ok(())
+    }
+    /// Moves the selected setting `step` values along and restarts the inputs'
+    /// analysis with it. The second input follows all but the device.
+    fn change_setting(
+        &mut self,
+        step: isize,
+        tx_to_audio: &Sender<TerminalMessage>,
+        tx_to_second: Option<&Sender<TerminalMessage>>,
+    ) -> Result<()> {
+        let setting = Setting::ALL[self.selected_setting];
+        let previous = self.setting_value(setting);
+        let analysis = &mut self.listener_options.analysis;
+        match setting {
+            Setting::WindowSize => {
+                analysis.window_size = step_through(&WINDOW_SIZES, &analysis.window_size, step);
+            }
+            Setting::Window => {
+                analysis.window =
+                    step_through(WindowFunction::value_variants(), &analysis.window, step);
+            }
+            Setting::PitchDetector => {
+                analysis.pitch_detector = step_through(
+                    PitchDetector::value_variants(),
+                    &analysis.pitch_detector,
+                    step,
+                );
+            }
+            Setting::ZeroPadding => {
+                analysis.zero_padding =
+                    step_through(&ZERO_PADDING_FACTORS, &analysis.zero_padding, step);
+            }
+            Setting::HighPass => {
+                analysis.high_pass_hz = (analysis.high_pass_hz + step as f32 * HIGH_PASS_STEP_HZ)
+                    .clamp(0.0, HIGH_PASS_MAX_HZ);
+            }
+            Setting::NoiseGateThreshold => {
+                let noise_gate = &mut self.listener_options.noise_gate;
+                noise_gate.threshold_db = (noise_gate.threshold_db
+                    + step as f32 * NOISE_GATE_STEP_DB)
+                    .clamp(NOISE_GATE_RANGE_DB.0, NOISE_GATE_RANGE_DB.1);
+            }
+            Setting::Reference => {
+                self.tuning_options.reference_hz = (self.tuning_options.reference_hz
+                    + step as f32 * REFERENCE_STEP_HZ)
+                    .clamp(REFERENCE_RANGE_HZ.0, REFERENCE_RANGE_HZ.1);
+                self.tuning = Tuning::new(&self.tuning_options)?;
+            }
+            Setting::Device => {
+                if self.listener_options.audio_file.is_some() || self.input_devices.is_empty() {
+                    self.status =
+                        Some(("No input device to switch to".to_string(), Instant::now()));
+                    return Ok(());
+                }
+                let names = self
+                    .input_devices
+                    .iter()
+                    .map(|device| device.name.clone())
+                    .collect::<Vec<_>>();
+                let current = self
+                    .listener_options
+                    .device
+                    .clone()
+                    .filter(|_| !self.listener_options.loopback)
+                    .unwrap_or_default();
+                let name = step_through(&names, &current, step);
+                if name != current {
+                    let _ = tx_to_audio.send(TerminalMessage::SwitchDevice(name.clone()));
+                    self.listener_options.device = Some(name);
+                    self.listener_options.loopback = false;
+                    self.channel_fundamentals.clear();
+                    self.channel_levels.clear();
+                }
+            }
+        }
+        let value = self.setting_value(setting);
+        if value == previous {
+            return Ok(());
+        }
+        for tx in std::iter::once(tx_to_audio).chain(tx_to_second) {
+            let _ = match setting {
+                Setting::NoiseGateThreshold => tx.send(TerminalMessage::SetNoiseGateThreshold(
+                    self.listener_options.noise_gate.threshold_db,
+                )),
+                Setting::Reference | Setting::Device => Ok(()),
+                _ => tx.send(TerminalMessage::SetAnalysis(Box::new(
+                    self.listener_options.analysis.clone(),
+                ))),
+            };
+        }
+        self.status = Some((format!("{setting}: {value}"), Instant::now()));
+        Ok(())
+    }
+    /// How `setting` is set right now
+    fn setting_value(&self, setting: Setting) -> String {
+        let analysis = &self.listener_options.analysis;
+        match setting {
+            Setting::WindowSize => format!("{} samples", analysis.window_size),
+            Setting::Window => value_name(&analysis.window),
+            Setting::PitchDetector => value_name(&analysis.pitch_detector),
+            Setting::ZeroPadding if analysis.zero_padding == 1 => "off".to_string(),
+            Setting::ZeroPadding => format!("{}x", analysis.zero_padding),
+            Setting::HighPass if analysis.high_pass_hz == 0.0 => "off".to_string(),
+            Setting::HighPass => format!("{:.0} Hz", analysis.high_pass_hz),
+            Setting::NoiseGateThreshold => {
+                format!("{:.0} dBFS", self.listener_options.noise_gate.threshold_db)
+            }
+            Setting::Reference => format!("{:.0} Hz", self.tuning_options.reference_hz),
+            Setting::Device => {
+                if let Some(audio_file) = &self.listener_options.audio_file {
+                    audio_file.display().to_string()
+                } else if self.listener_options.loopback {
+                    "loopback".to_string()
+                } else {
+                    self.listener_options
+                        .device
+                        .clone()
+                        .unwrap_or_else(|| "default".to_string())
+                }
+            }
+        }
+    }
+    /// Lets the charts' y axes follow the window that just came in
+    fn update_auto_scale(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.auto_scale_time).as_secs_f64();
+        self.auto_scale_time = now;
+        let loudest = |values: &mut dyn Iterator<Item = f64>| values.fold(0.0, f64::max);
+        self.time_domain_top.update(
+            loudest(
+                &mut self
+                    .freq_data
+                    .time_domain_samples
+                    .iter()
+                    .map(|sample| sample.abs() as f64),
+            ),
+            elapsed,
+        );
+        self.freqs_top.update(
+            loudest(&mut self.freq_data.data.iter().map(|(_, magnitude)| *magnitude)),
+            elapsed,
+        );
+        self.mel_top.update(
+            loudest(
+                &mut self
+                    .freq_data
+                    .mel_data
+                    .iter()
+                    .map(|(_, magnitude)| *magnitude),
+            ),
+            elapsed,
+        );
+    }
+    /// Raises each bin's held peak to its magnitude now, or lets it fall once
+    /// it's been held long enough
+    fn update_held_peaks(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.held_peaks_time).as_secs_f64();
+        self.held_peaks_time = now;
+        if self.held_peaks.len() != self.freq_data.data.len() {
+            self.held_peaks = vec![
+                HeldPeak {
+                    magnitude: 0.0,
+                    time: now,
+                };
+                self.freq_data.data.len()
+            ];
+        }
+        let fall = 10f64.powf(-SPECTRUM_PEAK_FALL_DB_PER_S * elapsed / 20.0);
+        for (held, (_, magnitude)) in self.held_peaks.iter_mut().zip(&self.freq_data.data) {
+            if *magnitude >= held.magnitude {
+                held.magnitude = *magnitude;
+                held.time = now;
+            } else if now - held.time > SPECTRUM_PEAK_HOLD {
+                held.magnitude = (held.magnitude * fall).max(*magnitude);
+            }
+        }
+    }
+    /// Folds the newest spectrum into the averaged or max-held one, starting
+    /// over from it when the bins changed
+    fn update_accumulated_spectrum(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.accumulated_spectrum_time).as_secs_f64();
+        self.accumulated_spectrum_time = now;
+        let data = &self.freq_data.data;
+        if self.persistence == SpectrumPersistence::Latest {
+            self.accumulated_spectrum.clear();
+            return;
+        }
+        if self.accumulated_spectrum.len() != data.len()
+            || self.accumulated_spectrum.first().map(|(hz, _)| *hz)
+                != data.first().map(|(hz, _)| *hz)
+        {
+            self.accumulated_spectrum = data.clone();
+            return;
+        }
+        let weight = 1.0 - (-elapsed / SPECTRUM_AVERAGE_SECS).exp();
+        for ((_, accumulated), (_, magnitude)) in self.accumulated_spectrum.iter_mut().zip(data) {
+            *accumulated = match self.persistence {
+                SpectrumPersistence::Average => *accumulated + (magnitude - *accumulated) * weight,
+                _ => accumulated.max(*magnitude),
+            };
+        }
+    }
+    /// Lets the bars fall towards the newest spectrum, jumping up right away
+    /// when a band gets louder
+    fn update_bar_levels(&mut self) {
+        let now = Instant::now();
+        let elapsed = (now - self.bar_levels_time).as_secs_f64();
+        self.bar_levels_time = now;
+        let highest_hz = self.preset().max_display_hz as f64;
+        let bands = if self.freq_data.pitch_method == PitchMethod::Goertzel {
+            // a point per note, the loudest of each band's notes
+            let band_count =
+                ((highest_hz / LOG_AXIS_LOWEST_HZ).log2() * BAR_BANDS_PER_OCTAVE).max(0.0) as usize;
+            let mut bands = vec![0.0; band_count];
+            for (hz, magnitude) in &self.freq_data.data {
+                let band = ((hz / LOG_AXIS_LOWEST_HZ).log2() * BAR_BANDS_PER_OCTAVE).floor();
+                if band >= 0.0
+                    && let Some(level) = bands.get_mut(band as usize)
+                {
+                    *level = magnitude.max(*level);
+                }
+            }
+            bands
+        } else {
+            octave_bands(&self.freq_data.data, highest_hz, BAR_BANDS_PER_OCTAVE)
+                .into_iter()
+                .map(|(_, magnitude)| magnitude)
+                .collect()
+        };
+        if self.bar_levels.len() != bands.len() {
+            self.bar_levels = vec![
+                BarLevel {
+                    level: 0.0,
+                    peak: 0.0,
+                    peak_time: now,
+                };
+                bands.len()
+            ];
+        }
+        for (bar, magnitude) in self.bar_levels.iter_mut().zip(bands) {
+            let level =
+                self.freq_data.to_dbfs(magnitude).max(-SPECTRUM_RANGE_DB) + SPECTRUM_RANGE_DB;
+            bar.level = level.max(bar.level - BAR_FALL_DB_PER_S * elapsed);
+            if bar.level >= bar.peak {
+                bar.peak = bar.level;
+                bar.peak_time = now;
+            } else if now - bar.peak_time > BAR_PEAK_HOLD {
+                bar.peak = (bar.peak - BAR_PEAK_FALL_DB_PER_S * elapsed).max(bar.level);
+            }
+        }
+    }
+    fn on_debug_key(&mut self, key_code: KeyCode) {
+        match key_code {
+            KeyCode::Char('z') => self.zoom_freqs(0.5, 0.5),
+            KeyCode::Char('Z') => self.zoom_freqs(2.0, 0.5),
+            KeyCode::Char('1') => self.debug_panels.stats = !self.debug_panels.stats,
+            KeyCode::Char('2') => self.debug_panels.freqs = !self.debug_panels.freqs,
+            KeyCode::Char('3') => self.debug_panels.time_domain = !self.debug_panels.time_domain,
+            KeyCode::Char('O') => self.trigger = self.trigger.next(),
+            KeyCode::Char('<' | '>') => {
+                let step = if key_code == KeyCode::Char('<') {
+                    -TRIGGER_LEVEL_STEP
+                } else {
+                    TRIGGER_LEVEL_STEP
+                };
+                self.trigger_level =
+                    (self.trigger_level + step).clamp(-TRIGGER_LEVEL_MAX, TRIGGER_LEVEL_MAX);
+            }
+            KeyCode::Left => self.pan_freqs(-FREQ_PAN_STEP),
+            KeyCode::Right => self.pan_freqs(FREQ_PAN_STEP),
+            _ => {}
+        }
+    }
+    /// A click on a tab opens its screen. Over the fft chart the wheel zooms in
+    /// and out around the pointer and scrolling sideways pans, a click marks
+    /// the frequency under the pointer and a drag zooms into what it covers.
+    /// A right click takes the mark away.
+    fn on_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        let pointer = Position::new(mouse.column, mouse.row);
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+            && self.tabs_area.get().contains(pointer)
+        {
+            if let Some(screen) = self.tab_at(mouse.column) {
+                self.set_screen(screen)?;
+            }
+            return Ok(());
+        }
+        let plot = self.freqs_plot.get();
+        if !(self.shows_freqs()
+            && matches!(self.full_screen, None | Some(FullScreenChart::Freqs))
+            && self.spectrum_view == SpectrumView::Linear
+            && !self.spectrum_bars)
+        {
+            return Ok(());
+        }
+        // drags carry on outside the chart, up to its edges
+        let column = mouse
+            .column
+            .clamp(plot.x, plot.right().saturating_sub(1).max(plot.x));
+        match mouse.kind {
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some((_, to)) = self.freqs_drag.as_mut() {
+                    *to = column;
+                }
+                return Ok(());
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                if let Some((from, to)) = self.freqs_drag.take() {
+                    if from.abs_diff(to) >= FREQ_DRAG_MIN_COLUMNS {
+                        self.zoom_freqs_to(
+                            self.plot_fraction(from.min(to)),
+                            self.plot_fraction(from.max(to)),
+                        );
+                    } else {
+                        self.inspected_hz = Some(self.hz_at(self.plot_fraction(from)));
+                    }
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        if !plot.contains(pointer) {
+            return Ok(());
+        }
+        let around = self.plot_fraction(mouse.column);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.freqs_drag = Some((column, column)),
+            MouseEventKind::Down(MouseButton::Right) => self.inspected_hz = None,
+            MouseEventKind::ScrollUp => self.zoom_freqs(0.5, around),
+            MouseEventKind::ScrollDown => self.zoom_freqs(2.0, around),
+            MouseEventKind::ScrollLeft => self.pan_freqs(-FREQ_PAN_STEP),
+            MouseEventKind::ScrollRight => self.pan_freqs(FREQ_PAN_STEP),
+            _ => {}
+        }
         Ok(())
     }
+    /// Whether the fft chart is on the screen, for the mouse
+    fn shows_freqs(&self) -> bool {
+        match self.screen {
+            AppScreen::Debug => true,
+            AppScreen::Layout(i) => self.layouts[i].shows(Widget::Spectrum),
+            _ => false,
+        }
+    }
+    /// Whether the screen has one of the debug screen's charts on it, so the
+    /// keys that zoom, pan and trigger them work there too
+    fn shows_debug_panel(&self) -> bool {
+        match self.screen {
+            AppScreen::Debug => true,
+            AppScreen::Layout(i) => {
+                self.layouts[i].shows(Widget::Spectrum) || self.layouts[i].shows(Widget::TimeDomain)
+            }
+            _ => false,
+        }
+    }
+    /// The screens on the tab bar with their titles, the built-in ones then
+    /// the layouts
+    fn tabs(&self) -> Vec<(AppScreen, String)> {
+        TABS.iter()
+            .map(|(screen, key, name)| (*screen, tab_title(*key, name)))
+            .chain(
+                self.layouts.iter().enumerate().map(|(i, layout)| {
+                    (AppScreen::Layout(i), format!("F{} {}", i + 1, layout.name))
+                }),
+            )
+            .collect()
+    }
+    /// The screen whose tab is at `column` of the tab bar
+    fn tab_at(&self, column: u16) -> Option<AppScreen> {
+        let mut x = self.tabs_area.get().x;
+        for (screen, title) in self.tabs() {
+            // a space either side of the title, then the divider
+            let width = title.chars().count() as u16 + 2;
+            if (x..x + width).contains(&column) {
+                return Some(screen);
+            }
+            x += width + 1;
+        }
+        None
+    }
+    /// How far across the fft chart's plot `column` is, 0 on the left
+    fn plot_fraction(&self, column: u16) -> f64 {
+        let plot = self.freqs_plot.get();
+        (column.saturating_sub(plot.x) as f64 / plot.width.saturating_sub(1).max(1) as f64).min(1.0)
+    }
+    /// The frequency `fraction` of the way across the fft chart as last drawn
+    fn hz_at(&self, fraction: f64) -> f64 {
+        let (low, high) = self.freqs_bounds.get();
+        let x = low + (high - low) * fraction;
+        if self.log_frequency {
+            LOG_AXIS_LOWEST_HZ * 2f64.powf(x)
+        } else {
+            x
+        }
+    }
+    /// Zooms the fft chart into what's between `from` and `to` of the way
+    /// across its view
+    fn zoom_freqs_to(&mut self, from: f64, to: f64) {
+        let (low, high) = self.freq_view;
+        let span = ((high - low) * (to - from)).clamp(FREQ_ZOOM_MIN_SPAN, 1.0);
+        let low = (low + (high - low) * from).clamp(0.0, 1.0 - span);
+        self.freq_view = (low, low + span);
+    }
+    /// Scales the fft chart's view by `factor`, keeping the frequency
+    /// `around` of the way across it in place
+    fn zoom_freqs(&mut self, factor: f64, around: f64) {
+        let (low, high) = self.freq_view;
+        let span = ((high - low) * factor).clamp(FREQ_ZOOM_MIN_SPAN, 1.0);
+        let pivot = low + (high - low) * around;
+        let low = (pivot - span * around).clamp(0.0, 1.0 - span);
+        self.freq_view = (low, low + span);
+    }
+    /// Moves the fft chart's view by `by` of its width, right for positive
+    fn pan_freqs(&mut self, by: f64) {
+        let (low, high) = self.freq_view;
+        let span = high - low;
+        let low = (low + span * by).clamp(0.0, 1.0 - span);
+        self.freq_view = (low, low + span);
+    }
+    fn on_spectrogram_key(&mut self, key_code: KeyCode) {
+        let max_hz = self.spectrogram_max_hz();
+        match key_code {
+            KeyCode::Up => {
+                self.spectrogram_max_hz = Some((max_hz * 2.0).min(self.nyquist_hz()));
+            }
+            KeyCode::Down => {
+                self.spectrogram_max_hz = Some((max_hz / 2.0).max(SPECTROGRAM_MIN_HZ));
+            }
+            KeyCode::Left => {
+                self.spectrogram_range_db = (self.spectrogram_range_db - SPECTROGRAM_RANGE_STEP_DB)
+                    .max(SPECTROGRAM_RANGE_DB.0);
+            }
+            KeyCode::Right => {
+                self.spectrogram_range_db = (self.spectrogram_range_db + SPECTROGRAM_RANGE_STEP_DB)
+                    .min(SPECTROGRAM_RANGE_DB.1);
+            }
+            _ => {}
+        }
+    }
+    /// Freezes or unfreezes what's on screen. The spectrogram gets a copy of
+    /// its history, which the analysis thread keeps filling.
+    fn toggle_freeze(&mut self) {
+        self.frozen = !self.frozen;
+        self.frozen_spectrum_history = if self.frozen {
+            self.spectrum_history
+                .as_ref()
+                .map(|history| Arc::new(Mutex::new(history.lock().unwrap().clone())))
+        } else {
+            None
+        };
+        let status = if self.frozen {
+            "Frozen, space to go on"
+        } else {
+            "Live"
+        };
+        self.status = Some((status.to_string(), Instant::now()));
+    }
+    /// The spectra the spectrogram shows, the copy while frozen
+    fn shown_spectrum_history(&self) -> Option<&SharedSpectrumHistory> {
+        if self.frozen {
+            self.frozen_spectrum_history.as_ref()
+        } else {
+            self.spectrum_history.as_ref()
+        }
+    }
+    fn on_logs_key(&mut self, key_code: KeyCode) {
+        match key_code {
+            KeyCode::Up => {
+                self.logs_scroll = (self.logs_scroll + 1).min(LOG_BUFFER.lock().unwrap().len());
+            }
+            KeyCode::Down => self.logs_scroll = self.logs_scroll.saturating_sub(1),
+            KeyCode::Left | KeyCode::Right => {
+                self.log_level = step_through(
+                    &LOG_LEVELS,
+                    &self.log_level,
+                    if key_code == KeyCode::Left { -1 } else { 1 },
+                );
+                self.logs_scroll = 0;
+            }
+            _ => {}
+        }
+    }
+    /// Highest frequency in the spectra of the analyzed input
+    fn nyquist_hz(&self) -> f32 {
+        self.freq_data.sample_rate as f32 / 2.0
+    }
+    fn spectrogram_max_hz(&self) -> f32 {
+        let max_hz = self
+            .spectrogram_max_hz
+            .unwrap_or(self.preset().max_display_hz);
+        if self.freq_data.sample_rate > 0 {
+            max_hz.min(self.nyquist_hz())
+        } else {
+            max_hz
+        }
+    }
     fn reset_tutor(&mut self) -> Result<()> {
         if let Some(input_file_path) = &self.input_file_path {
             self.tutor = Some(Self::set_tutor(input_file_path)?);
@@ -192,7 +2731,7 @@ impl App {
             .lines()
             .map(|line| {
                 line.split(",")
-                    .map(|n| n.parse::<MusicalNote>().map(MusicalSound::Note))
+                    .map(|n| n.parse::<ScientificNote>().map(MusicalSound::Note))
                     .collect::<Vec<_>>()
                     .into_iter()
                     .collect::<Result<Vec<_>, Error>>()
@@ -204,9 +2743,79 @@ impl App {
         Ok(sounds)
     }
     fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        if area.width < MIN_TERMINAL_SIZE.0 || area.height < MIN_TERMINAL_SIZE.1 {
+            // nothing to click
+            self.tabs_area.set(Rect::default());
+            self.freqs_plot.set(Rect::default());
+            let [_, message_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+            frame.render_widget(
+                Paragraph::new(format!(
+                    "Terminal too small: {}x{}, needs {}x{}",
+                    area.width, area.height, MIN_TERMINAL_SIZE.0, MIN_TERMINAL_SIZE.1
+                ))
+                .centered()
+                .wrap(Wrap { trim: true })
+                .fg(self.theme.warning),
+                message_area,
+            );
+            return;
+        }
+        self.draw_screen(frame);
+        self.render_toasts(frame, frame.area());
+        if self.perf_shown {
+            self.render_perf(frame, frame.area());
+        }
+        if self.help_shown {
+            self.render_help(frame, frame.area());
+        }
+    }
+    fn draw_screen(&self, frame: &mut Frame) {
+        if let Some(chart) = self.full_screen {
+            // no tabs to click
+            self.tabs_area.set(Rect::default());
+            let area = frame.area();
+            match chart {
+                FullScreenChart::Freqs => self.render_freqs(frame, area),
+                FullScreenChart::TimeDomain => self.render_time_domain(frame, area),
+                FullScreenChart::Spectrogram => self.render_spectrogram(frame, area),
+            }
+            return;
+        }
+        let [tabs_area, area, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        self.render_tabs(frame, tabs_area);
+        self.render_status_bar(frame, status_area);
         match self.screen {
             AppScreen::Tutor => {
-                let layout = frame.area();
+                // guitarists get the neck too
+                let guitar = self.listener_options.instrument == Instrument::Guitar;
+                let [area, fretboard_area, piano_area, vibrato_area] = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Min(0),
+                        Constraint::Length(if guitar { FRETBOARD_HEIGHT } else { 0 }),
+                        Constraint::Length(3),
+                        Constraint::Length(4),
+                    ])
+                    .areas(area);
+                self.render_vibrato(frame, vibrato_area);
+                let target = self.tutor.as_ref().and_then(|tutor| {
+                    match tutor.notes_sequence.get(tutor.current_note_index) {
+                        Some(MusicalSound::Note(note)) => Some(note),
+                        _ => None,
+                    }
+                });
+                if guitar {
+                    self.render_fretboard(frame, fretboard_area, target);
+                }
+                self.render_piano(frame, piano_area, target);
+                let layout = area;
                 if let Some(tutor) = &self.tutor {
                     let note = self.note_history.last();
                     let mut lines = vec![];
@@ -221,17 +2830,23 @@ impl App {
                         ))
                         .centered(),
                     );
+                    if let Some(chord) = self.freq_data.chord {
+                        lines.push(Line::from(format!("Current chord: {chord}")).centered());
+                    }
                     let mut spans = vec![];
                     for (i, sound) in tutor.notes_sequence.iter().enumerate() {
                         match sound {
                             MusicalSound::Note(n) => {
+                                if !spans.is_empty() {
+                                    spans.push(Span::raw(" "));
+                                }
                                 let content = n.to_string();
                                 let span = Span::styled(
                                     content,
                                     if tutor.current_note_index == i {
                                         Style::default().add_modifier(Modifier::BOLD)
                                     } else if tutor.current_note_index < i {
-                                        Style::default().fg(Color::Gray)
+                                        Style::default().fg(self.theme.muted)
                                     } else {
                                         Style::default()
                                     },
@@ -256,266 +2871,2448 @@ impl App {
 
                     frame.render_widget(text, layout);
                 } else {
-                    let layout = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                        .split(frame.area());
-                    self.show_help(frame, layout[0]);
-                    frame.render_widget(
-                        Line::from("You need to pass a file as an argument to see the notes here."),
-                        layout[1],
-                    );
+                    let layout = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .split(area);
+                    frame.render_widget(Text::from(self.help_lines()).centered(), layout[0]);
+                    frame.render_widget(
+                        Line::from("You need to pass a file as an argument to see the notes here."),
+                        layout[1],
+                    );
+                }
+            }
+            AppScreen::Debug
+                if frame.area().width < COMPACT_TERMINAL_SIZE.0
+                    || frame.area().height < COMPACT_TERMINAL_SIZE.1 =>
+            {
+                self.render_compact_debug(frame, area);
+            }
+            AppScreen::Debug => {
+                let panels = self.debug_panels;
+                // the charts share what's left, or one takes all of it
+                let chart = |shown: bool, other_shown: bool| match (shown, other_shown) {
+                    (false, _) => Constraint::Length(0),
+                    (true, true) => Constraint::Ratio(1, 2),
+                    (true, false) => Constraint::Min(0),
+                };
+                let layout = Layout::default()
+                    .direction(ratatui::layout::Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(1),
+                        Constraint::Length(match (panels.stats, self.show_features) {
+                            (false, _) => 0,
+                            (true, true) => 13,
+                            (true, false) => 8,
+                        }),
+                        Constraint::Length(3),
+                        chart(panels.freqs, panels.time_domain),
+                        chart(panels.time_domain, panels.freqs),
+                    ])
+                    .split(area);
+                let ticker_area = layout[0];
+                let top = layout[1];
+                let piano_area = layout[2];
+                // the vu meters run down the right of both charts
+                let [charts, meters_area] = Layout::horizontal([
+                    Constraint::Min(0),
+                    Constraint::Length(self.vu_meters_width()),
+                ])
+                .areas(layout[3].union(layout[4]));
+                let middle = Rect {
+                    width: charts.width,
+                    ..layout[3]
+                };
+                let bottom = Rect {
+                    width: charts.width,
+                    ..layout[4]
+                };
+                self.render_vu_meters(frame, meters_area);
+                if self.debug_panels.stats {
+                    self.render_debug_stats(frame, top);
+                }
+                self.render_note_ticker(frame, ticker_area);
+
+                self.render_piano(frame, piano_area, None);
+                if panels.freqs {
+                    self.render_freqs(frame, middle);
+                } else {
+                    // nothing for the mouse to hit
+                    self.freqs_plot.set(Rect::default());
+                }
+                if panels.time_domain {
+                    self.render_time_domain(frame, bottom);
+                }
+                if !(panels.stats || panels.freqs || panels.time_domain) {
+                    frame.render_widget(
+                        Paragraph::new("all panels hidden, 1, 2 and 3 show them again")
+                            .centered()
+                            .fg(self.theme.faint),
+                        middle.union(bottom),
+                    );
+                }
+                if self.idle {
+                    // the charts show the last sound heard, dim them while it's quiet
+                    for area in [middle, bottom] {
+                        frame
+                            .buffer_mut()
+                            .set_style(area, Style::default().add_modifier(Modifier::DIM));
+                    }
+                }
+            }
+            AppScreen::DeviceSelect => {
+                self.render_device_select(frame, area);
+            }
+            AppScreen::Errors => {
+                self.render_stream_errors(frame, area);
+            }
+            AppScreen::InstrumentSelect => {
+                self.render_instrument_select(frame, area);
+            }
+            AppScreen::Vowels => {
+                self.render_vowels(frame, area);
+            }
+            AppScreen::GuitarTuner => {
+                self.render_guitar_tuner(frame, area);
+            }
+            AppScreen::Spectrogram => {
+                self.render_spectrogram(frame, area);
+            }
+            AppScreen::Tuner => {
+                let [ticker_area, area] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+                self.render_note_ticker(frame, ticker_area);
+                self.render_tuner(frame, area);
+            }
+            AppScreen::PitchContour => {
+                self.render_pitch_contour(frame, area);
+            }
+            AppScreen::Settings => {
+                self.render_settings(frame, area);
+            }
+            AppScreen::Logs => {
+                self.render_logs(frame, area);
+            }
+            AppScreen::PhaseScope => {
+                self.render_phase_scope(frame, area);
+            }
+            AppScreen::Layout(i) => {
+                self.render_layout(frame, area, &self.layouts[i]);
+            }
+            AppScreen::Welcome => {
+                self.render_welcome(frame, area);
+            }
+        }
+    }
+    /// The readings along the top of the debug screen: the pitch and the
+    /// input on the left, the note in the middle, the session on the right
+    fn render_debug_stats(&self, frame: &mut Frame, top: Rect) {
+        let note = self
+            .note_history
+            .last()
+            .map_or(" ".to_string(), |n| n.note.clone());
+        let peak_freq_text = format!("Peak frequency: {}", self.freq_data.peak_frequency);
+        let max_magnitude_text = format!("Max Magnitude: {}", self.freq_data.max_magnitude);
+        let features = self.freq_data.features;
+        let mut text_left = Text::from(vec![
+            Line::from(peak_freq_text),
+            Line::from(format!(
+                "Fundamental ({}): {:.1} Hz, {:.0}% sure",
+                self.freq_data.pitch_method.name(),
+                self.freq_data.fundamental_frequency,
+                self.freq_data.pitch_confidence * 100.0
+            )),
+            Line::from(format!(
+                "Octave: {}",
+                match (
+                    self.freq_data.pitch_method,
+                    self.freq_data.octave_correction
+                ) {
+                    (PitchMethod::Hps, Some(correction)) => correction.describe(),
+                    (PitchMethod::Hps, None) => "kept".to_string(),
+                    _ => "hps only".to_string(),
+                }
+            )),
+            Line::from(format!(
+                "Spectral flatness: {:.2} ({})",
+                features.flatness,
+                if features.flatness < TUTOR_MAX_FLATNESS {
+                    "pitched"
+                } else {
+                    "noisy"
+                }
+            )),
+            Line::from(format!(
+                "Noise gate: {} (g)",
+                if !self.listener_options.noise_gate.enabled {
+                    "off"
+                } else if self.freq_data.gated {
+                    "closed"
+                } else {
+                    "open"
+                }
+            )),
+            if self
+                .last_clip
+                .is_some_and(|clip| clip.elapsed() < CLIP_HOLD)
+            {
+                Line::from("CLIPPING, turn the input down").fg(self.theme.bad)
+            } else {
+                Line::from(format!(
+                    "Input: {:.1} dBFS RMS, {:.1} dBFS peak",
+                    self.freq_data.level.rms_db, self.freq_data.level.peak_db
+                ))
+            },
+        ])
+        .centered();
+        if self.show_features {
+            text_left.push_line(format!(
+                "Centroid: {:.0} Hz, rolloff: {:.0} Hz",
+                features.centroid, features.rolloff
+            ));
+            text_left.push_line(format!("Spectral flux: {:.3}", features.flux));
+            let harmonics = &self.freq_data.harmonics;
+            let strongest = harmonics
+                .harmonics
+                .iter()
+                .map(|harmonic| harmonic.magnitude)
+                .fold(0.0, f32::max);
+            let per_harmonic = |value: &dyn Fn(&Harmonic) -> String| {
+                (1..=harmonics.harmonics.last().map_or(0, |last| last.number))
+                    .map(|number| {
+                        harmonics
+                            .harmonics
+                            .iter()
+                            .find(|harmonic| harmonic.number == number)
+                            .map_or("-".to_string(), value)
+                    })
+                    .join(" ")
+            };
+            text_left.push_line(format!(
+                "Harmonics (dB): {}",
+                per_harmonic(&|harmonic| format!(
+                    "{:.0}",
+                    linear_to_db(harmonic.magnitude / strongest)
+                ))
+            ));
+            text_left.push_line(format!(
+                "Off by (cents): {}",
+                per_harmonic(&|harmonic| format!("{:+.0}", harmonic.deviation_cents))
+            ));
+            text_left.push_line(
+                harmonics
+                    .inharmonicity
+                    .map_or("Inharmonicity: -".to_string(), |b| {
+                        format!("Inharmonicity: B = {b:.1e}")
+                    }),
+            );
+        }
+        let top_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Ratio(1, 3),
+                Constraint::Ratio(1, 3),
+                Constraint::Ratio(1, 3),
+            ])
+            .split(top);
+        let text_right = Text::from(vec![
+            Line::from(format!(
+                "Sample rate: {} ({:.1} s of history)",
+                self.freq_data.sample_rate,
+                self.shown_spectrum_history().map_or(0.0, |history| {
+                    history
+                        .lock()
+                        .unwrap()
+                        .duration(self.selected_channel)
+                        .as_secs_f32()
+                })
+            )),
+            Line::from(max_magnitude_text),
+            Line::from(self.drift.drift().map_or(
+                "Tuning drift: play for a minute".to_string(),
+                |(cents, span)| {
+                    format!(
+                        "Tuning drift: {cents:+.0} cents over {:.0} min",
+                        span.as_secs_f32() / 60.0
+                    )
+                },
+            )),
+            Line::from(format!(
+                "Gain: {:+} dB (+/-), AGC {} (a)",
+                self.listener_options.gain_db,
+                if self.listener_options.agc {
+                    "on"
+                } else {
+                    "off"
+                }
+            )),
+            Line::from(format!(
+                "Loudness: {:.1} LUFS (M), {:.1} LUFS (S)",
+                self.freq_data.level.momentary_lufs, self.freq_data.level.short_term_lufs
+            )),
+            Line::from(
+                self.recording
+                    .as_ref()
+                    .map_or("Not recording (r)".to_string(), |path| {
+                        format!("REC {} (r)", path.display())
+                    }),
+            ),
+        ]);
+        frame.render_widget(
+            Paragraph::new(text_left).block(Block::bordered()),
+            top_layout[0],
+        );
+
+        let mut note_lines = vec![if self.idle {
+            Line::from("listening…").centered().fg(self.theme.faint)
+        } else if self.freq_data.pitch_confidence < MIN_PITCH_CONFIDENCE {
+            // not sure this window has a pitch, the note is just the last one
+            Line::from(note.as_str()).centered().fg(self.theme.faint)
+        } else {
+            Line::from(note.as_str()).centered()
+        }];
+        if !self.idle
+            && self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+            && let Some(reading) = self.tuning.note(self.freq_data.fundamental_frequency)
+            && reading.name == note
+        {
+            // a rough tuner, as wide as the note box has room for
+            let mut box_width = top_layout[1].width.saturating_sub(2) as usize;
+            if self.second_device.is_some() {
+                box_width /= 2;
+            }
+            let gauge_width = box_width
+                .saturating_sub(DEBUG_GAUGE_LABEL_WIDTH)
+                .min(DEBUG_GAUGE_WIDTH);
+            // odd, so the middle falls on a character
+            let gauge_width = gauge_width.saturating_sub(1) | 1;
+            let mut spans = cents_gauge(reading.cents, gauge_width, &self.theme);
+            spans.push(Span::from(format!(" {:+.0} cents", reading.cents)).fg(self.theme.muted));
+            note_lines.push(Line::from(spans).centered());
+        }
+        if !self.idle
+            && let Some(envelope) = self.freq_data.envelope
+        {
+            note_lines.push(
+                Line::from(format!(
+                    "{}, attack {} ms, held {:.1} s",
+                    envelope.phase.name(),
+                    envelope.attack.as_millis(),
+                    envelope.held().as_secs_f32()
+                ))
+                .centered()
+                .fg(self.theme.muted),
+            );
+        }
+        if !self.idle && self.freq_data.pitches.len() > 1 {
+            let notes = self
+                .freq_data
+                .pitches
+                .iter()
+                .filter_map(|pitch| self.tuning.note_name(*pitch))
+                .join(" ");
+            note_lines.push(Line::from(format!("notes {notes}")).centered());
+        }
+        if !self.idle
+            && let Some(chord) = self.freq_data.chord
+        {
+            note_lines.push(Line::from(format!("chord {chord}")).centered());
+        }
+        let mut note_block = Block::bordered();
+        if self
+            .last_onset
+            .is_some_and(|onset| onset.elapsed() < ONSET_FLASH)
+        {
+            note_block = note_block.border_style(Style::default().fg(self.theme.highlight));
+        }
+        let key_and_tempo = [
+            self.freq_data.key.map(|key| format!("key: {key}")),
+            self.freq_data.bpm.map(|bpm| format!("{bpm:.0} BPM")),
+        ]
+        .into_iter()
+        .flatten()
+        .join(", ");
+        if !key_and_tempo.is_empty() {
+            note_block =
+                note_block.title_bottom(Line::from(key_and_tempo).centered().fg(self.theme.muted));
+        }
+        if self.channel_fundamentals.len() > 1 {
+            note_block = note_block
+                .title(format!(
+                    "Channel {} of {} (c to switch)",
+                    self.selected_channel + 1,
+                    self.freq_data.channels
+                ))
+                .title_alignment(ratatui::layout::Alignment::Center);
+            let other_channels = self
+                .channel_fundamentals
+                .iter()
+                .filter(|(channel, _)| **channel != self.selected_channel)
+                .map(|(channel, (frequency, _))| {
+                    format!(
+                        "ch{}: {}",
+                        channel + 1,
+                        self.tuning
+                            .note_name(*frequency)
+                            .unwrap_or_else(|| "-".to_string())
+                    )
+                })
+                .join(" | ");
+            note_lines.push(Line::from(other_channels).centered().fg(self.theme.muted));
+        }
+        if let Some(second_device) = &self.second_device {
+            // duet: the second device's pitch goes next to the main one
+            let note_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
+                .split(top_layout[1]);
+            frame.render_widget(
+                Paragraph::new(Text::from(note_lines)).block(note_block),
+                note_layout[0],
+            );
+            let second_note = self
+                .second_freq_data
+                .as_ref()
+                .filter(|data| {
+                    data.max_magnitude > self.preset().min_magnitude
+                        && data.pitch_confidence >= MIN_PITCH_CONFIDENCE
+                })
+                .and_then(|data| self.tuning.note_name(data.fundamental_frequency))
+                .unwrap_or_else(|| " ".to_string());
+            frame.render_widget(
+                Paragraph::new(Line::from(second_note).centered()).block(
+                    Block::bordered()
+                        .title(second_device.as_str())
+                        .title_alignment(ratatui::layout::Alignment::Center),
+                ),
+                note_layout[1],
+            );
+        } else {
+            frame.render_widget(
+                Paragraph::new(Text::from(note_lines)).block(note_block),
+                top_layout[1],
+            );
+        }
+        frame.render_widget(
+            Paragraph::new(text_right).block(Block::bordered()),
+            top_layout[2],
+        );
+    }
+    /// The debug screen on a small terminal: the note in big letters over the
+    /// fft chart, or the time domain when that's the one shown
+    fn render_compact_debug(&self, frame: &mut Frame, area: Rect) {
+        let [note_area, chart_area] =
+            Layout::vertical([Constraint::Length(7), Constraint::Min(0)]).areas(area);
+        let block = Block::bordered();
+        let inner = block.inner(note_area);
+        frame.render_widget(block, note_area);
+        let confident = !self.idle && self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE;
+        let reading = self
+            .tuning
+            .note(self.freq_data.fundamental_frequency)
+            .filter(|_| confident);
+        let note = self
+            .note_history
+            .last()
+            .map_or(String::new(), |item| item.note.clone());
+        let big_note = big_text(&note);
+        let note_width = big_note
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let [big_area, details_area] = Layout::horizontal([
+            Constraint::Length(note_width as u16 + 4),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+        let color = match &reading {
+            Some(reading) if reading.cents.abs() <= IN_TUNE_CENTS => self.theme.good,
+            Some(_) => self.theme.warning,
+            // the last note heard, or nothing
+            None => self.theme.faint,
+        };
+        frame.render_widget(
+            Text::from(
+                big_note
+                    .into_iter()
+                    .map(|row| Line::from(row).centered().fg(color))
+                    .collect::<Vec<_>>(),
+            ),
+            big_area,
+        );
+        let mut details = vec![Line::default()];
+        match &reading {
+            Some(reading) => {
+                details.push(Line::from(format!(
+                    "{:.1} Hz",
+                    self.freq_data.fundamental_frequency
+                )));
+                let gauge_width = (details_area.width as usize)
+                    .saturating_sub(DEBUG_GAUGE_LABEL_WIDTH)
+                    .min(DEBUG_GAUGE_WIDTH);
+                // odd, so the middle falls on a character
+                let gauge_width = gauge_width.saturating_sub(1) | 1;
+                let mut spans = cents_gauge(reading.cents, gauge_width, &self.theme);
+                spans
+                    .push(Span::from(format!(" {:+.0} cents", reading.cents)).fg(self.theme.muted));
+                details.push(Line::from(spans));
+            }
+            None if self.idle => details.push(Line::from("listening…").fg(self.theme.faint)),
+            None => details.push(Line::from("no clear pitch").fg(self.theme.faint)),
+        }
+        if let Some(chord) = self.freq_data.chord.filter(|_| !self.idle) {
+            details.push(Line::from(format!("chord {chord}")).fg(self.theme.muted));
+        }
+        frame.render_widget(Text::from(details), details_area);
+
+        if self.debug_panels.freqs {
+            self.render_freqs(frame, chart_area);
+        } else {
+            // nothing for the mouse to hit
+            self.freqs_plot.set(Rect::default());
+            self.render_time_domain(frame, chart_area);
+        }
+        if self.idle {
+            // the chart shows the last sound heard, dim it while it's quiet
+            frame
+                .buffer_mut()
+                .set_style(chart_area, Style::default().add_modifier(Modifier::DIM));
+        }
+    }
+    /// The first start's guide: the inputs to pick from, how loud the one
+    /// picked is, and the screens there are
+    fn render_welcome(&self, frame: &mut Frame, area: Rect) {
+        let [intro_area, devices_area, level_area, screens_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Min(5),
+            Constraint::Length(5),
+            Constraint::Length(10),
+        ])
+        .areas(area);
+        frame.render_widget(
+            Text::from(vec![
+                Line::from("Welcome! Pick your input, check it hears you, then esc to start.")
+                    .bold(),
+                Line::from("? shows every key, on any screen").fg(self.theme.muted),
+            ])
+            .centered(),
+            intro_area,
+        );
+        self.render_device_select(frame, devices_area);
+
+        let level = self.freq_data.level;
+        let (verdict, color) = if self.idle || level.peak_db < WELCOME_QUIET_DB {
+            (
+                "Too quiet: play or sing, get closer to the mic or turn the gain up with +",
+                self.theme.warning,
+            )
+        } else if level.clipped || level.peak_db > VU_HOT_DB as f32 {
+            (
+                "Too loud, it clips: turn the gain down with -",
+                self.theme.bad,
+            )
+        } else {
+            ("Good level", self.theme.good)
+        };
+        let block = Block::bordered()
+            .title("Level check")
+            .title_alignment(ratatui::layout::Alignment::Center)
+            .padding(Padding::horizontal(1));
+        let width = block.inner(level_area).width as usize;
+        let filled = (((level.peak_db as f64 + VU_RANGE_DB) / VU_RANGE_DB).clamp(0.0, 1.0)
+            * width as f64)
+            .round() as usize;
+        let note = match self.note_history.last() {
+            Some(note) if !self.idle => format!("hearing {}", note.note),
+            _ => "no note yet".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from("█".repeat(filled)).fg(color),
+                Line::from(verdict).fg(color),
+                Line::from(format!(
+                    "{:.0} dBFS peak, {note}",
+                    level.peak_db.max(-(VU_RANGE_DB as f32))
+                ))
+                .fg(self.theme.muted),
+            ])
+            .block(block),
+            level_area,
+        );
+
+        let screens = [
+            ("d", "debug: the note, the spectrum and the waveform"),
+            ("u", "tuner: the note in big letters with a needle"),
+            ("s", "guitar tuner: string by string"),
+            ("p", "pitch contour: the last ten seconds of pitch"),
+            ("w", "spectrogram: the recent spectra as a heatmap"),
+            ("t", "tutor: play along to a file of notes"),
+            ("n", "instrument: tune the analysis to what you play"),
+            ("k", "settings: fft size, pitch detector, a4 and more"),
+        ];
+        frame.render_widget(
+            Paragraph::new(
+                screens
+                    .map(|(key, screen)| {
+                        Line::from(vec![
+                            Span::from(key).bold(),
+                            Span::from(format!("  {screen}")),
+                        ])
+                    })
+                    .to_vec(),
+            )
+            .block(
+                Block::bordered()
+                    .title("The screens")
+                    .title_alignment(ratatui::layout::Alignment::Center)
+                    .padding(Padding::horizontal(1)),
+            ),
+            screens_area,
+        );
+    }
+    /// One of the user's layouts, each of its widgets in its cell
+    fn render_layout(&self, frame: &mut Frame, area: Rect, layout: &LayoutOptions) {
+        // nothing for the mouse to hit unless the fft chart is drawn
+        self.freqs_plot.set(Rect::default());
+        if layout.rows.is_empty() {
+            frame.render_widget(
+                Paragraph::new(format!("the layout {} has no rows", layout.name))
+                    .centered()
+                    .fg(self.theme.faint),
+                area,
+            );
+        }
+        for (widget, area) in layout.areas(area) {
+            match widget {
+                Widget::Spectrum => self.render_freqs(frame, area),
+                Widget::TimeDomain => self.render_time_domain(frame, area),
+                Widget::Spectrogram => self.render_spectrogram(frame, area),
+                Widget::Tuner => self.render_tuner(frame, area),
+                Widget::PitchContour => self.render_pitch_contour(frame, area),
+                Widget::NoteTicker => self.render_note_ticker(frame, area),
+                Widget::Piano => self.render_piano(frame, area, None),
+                Widget::VuMeters => self.render_vu_meters(frame, area),
+                Widget::Vibrato => self.render_vibrato(frame, area),
+                Widget::PhaseScope => self.render_phase_scope(frame, area),
+                Widget::Vowels => self.render_vowels(frame, area),
+                Widget::GuitarTuner => self.render_guitar_tuner(frame, area),
+            }
+        }
+    }
+
+    fn render_instrument_select(&self, frame: &mut Frame, area: Rect) {
+        let items = Instrument::ALL
+            .iter()
+            .map(|instrument| {
+                let preset = instrument.preset();
+                let mut name = Line::from(instrument.to_string()).bold();
+                if *instrument == self.listener_options.instrument {
+                    name.push_span(Span::from(" (active)").fg(self.theme.good));
+                }
+                let range = if preset.pitch_range.1.is_finite() {
+                    format!(
+                        "    pitch {:.0}-{:.0} Hz, spectrum up to {:.0} Hz",
+                        preset.pitch_range.0, preset.pitch_range.1, preset.max_display_hz
+                    )
+                } else {
+                    format!(
+                        "    any pitch, spectrum up to {:.0} Hz",
+                        preset.max_display_hz
+                    )
+                };
+                ListItem::new(Text::from(vec![
+                    name,
+                    Line::from(range).fg(self.theme.muted),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title("Instrument (up/down to move, enter to pick)")
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            )
+            .highlight_symbol("> ")
+            .highlight_style(Style::default().fg(self.theme.highlight));
+        let mut state = ListState::default().with_selected(Some(self.selected_instrument_index));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_settings(&self, frame: &mut Frame, area: Rect) {
+        let width = Setting::ALL
+            .iter()
+            .map(|setting| setting.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let items = Setting::ALL
+            .iter()
+            .map(|setting| {
+                ListItem::new(Text::from(vec![
+                    Line::from(vec![
+                        Span::from(format!("{:width$}  ", setting.to_string())).bold(),
+                        Span::from(format!("< {} >", self.setting_value(*setting))),
+                    ]),
+                    Line::from(format!("    {}", setting.description())).fg(self.theme.muted),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title("Settings (up/down to move, left/right to change)")
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            )
+            .highlight_symbol("> ")
+            .highlight_style(Style::default().fg(self.theme.highlight));
+        let mut state = ListState::default().with_selected(Some(self.selected_setting));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_stream_errors(&self, frame: &mut Frame, area: Rect) {
+        let lines = if self.stream_errors.is_empty() {
+            vec![Line::from("No stream errors so far").centered()]
+        } else {
+            self.stream_errors
+                .iter()
+                .rev()
+                .map(|e| {
+                    let mut line = Line::from(vec![
+                        Span::from(format!("{:>5}s ago  ", e.last_seen.elapsed().as_secs()))
+                            .fg(self.theme.muted),
+                        Span::from(e.message.clone()),
+                    ]);
+                    if e.count > 1 {
+                        line.push_span(Span::from(format!(" (x{})", e.count)).fg(self.theme.muted));
+                    }
+                    line
+                })
+                .collect()
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title("Stream errors")
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            ),
+            area,
+        );
+    }
+
+    fn render_logs(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title(format!(
+                "Logs down to {} (up/down to scroll, left/right for fewer or more)",
+                self.log_level.as_str().to_lowercase()
+            ))
+            .title_bottom(Line::from(log_path().display().to_string()).fg(self.theme.muted))
+            .title_alignment(ratatui::layout::Alignment::Center);
+        let rows = block.inner(area).height as usize;
+        let buffer = LOG_BUFFER.lock().unwrap();
+        let shown = buffer
+            .iter()
+            .filter(|line| line.level <= self.log_level)
+            .collect::<Vec<_>>();
+        // stop at the oldest line instead of scrolling past it
+        let end = shown
+            .len()
+            .saturating_sub(self.logs_scroll)
+            .max(rows.min(shown.len()));
+        let lines = if shown.is_empty() {
+            vec![Line::from("Nothing logged yet").centered()]
+        } else {
+            shown[end.saturating_sub(rows)..end]
+                .iter()
+                .map(|line| {
+                    let color = match line.level {
+                        Level::ERROR => self.theme.bad,
+                        Level::WARN => self.theme.warning,
+                        Level::INFO => self.theme.signal,
+                        _ => self.theme.muted,
+                    };
+                    Line::from(vec![
+                        Span::from(format!("{:>8.1}s ", line.elapsed.as_secs_f32()))
+                            .fg(self.theme.muted),
+                        Span::from(format!("{:<5} ", line.level.as_str())).fg(color),
+                        Span::from(line.message.clone()),
+                    ])
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn render_device_select(&self, frame: &mut Frame, area: Rect) {
+        let items = self
+            .input_devices
+            .iter()
+            .map(|d| {
+                let mut name = Line::from(d.name.clone()).bold();
+                if !self.listener_options.loopback
+                    && Some(&d.name) == self.listener_options.device.as_ref()
+                {
+                    name.push_span(Span::from(" (active)").fg(self.theme.good));
+                }
+                let mut lines = vec![name];
+                lines.extend(
+                    d.configs
+                        .iter()
+                        .map(|c| Line::from(format!("    {c}")).fg(self.theme.muted)),
+                );
+                ListItem::new(Text::from(lines))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(
+                Block::bordered()
+                    .title(format!(
+                        "Input devices (up/down to move, enter to switch, l: loopback {})",
+                        if self.listener_options.loopback {
+                            "on"
+                        } else {
+                            "off"
+                        }
+                    ))
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            )
+            .highlight_symbol("> ")
+            .highlight_style(Style::default().fg(self.theme.highlight));
+        let mut state = ListState::default().with_selected(Some(self.selected_device_index));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    /// The keys of the open screen, then the ones that work everywhere
+    fn help_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![];
+        let screen_help = self.screen.help();
+        if !screen_help.is_empty() {
+            lines.push(Line::from("On this screen").bold());
+            lines.extend(screen_help.iter().map(|line| Line::from(*line)));
+            lines.push(Line::default());
+            lines.push(Line::from("Everywhere").bold());
+        }
+        lines.extend([
+            Line::from("?/h: help, ↑/↓ to scroll it, esc to close it"),
+            Line::from("d: debug and visualization"),
+            Line::from("t: tutor"),
+            Line::from("i: input device"),
+            Line::from("n: instrument"),
+            Line::from("k: settings, ←/→ change fft size, window, detector, a4 and more live"),
+            Line::from("c: next channel (with --per-channel)"),
+            Line::from("e: stream errors"),
+            Line::from("L: logs, ↑/↓ to scroll and ←/→ for fewer or more"),
+            Line::from("v: vowel chart"),
+            Line::from("G: stereo phase scope (left against right)"),
+            Line::from("s: guitar string tuner"),
+            Line::from("F1, F2, ...: your own layouts, from [[layouts]] in the config"),
+            Line::from("u: tuner"),
+            Line::from("p: pitch over the last ten seconds"),
+            Line::from("w: spectrogram, ↑/↓ for its frequency range and ←/→ for its colors"),
+            Line::from("+/-: input gain"),
+            Line::from("a: automatic gain control"),
+            Line::from("g: noise gate"),
+            Line::from("m: frequency chart (linear, mel, constant-q, chroma, mfcc)"),
+            Line::from("b: dB or linear magnitudes on the fft and mel charts"),
+            Line::from("o: octaves (log frequency) on the fft chart"),
+            Line::from("N: the notes on the fft chart"),
+            Line::from("A: the fft chart's latest spectrum, an average or the max held"),
+            Line::from("[/]: halve or double the highest frequency of the spectrum"),
+            Line::from("F: the fft chart, then the time domain, or the spectrogram full screen"),
+            Line::from("y: y axes follow the signal or stay pinned"),
+            Line::from("T: next color theme"),
+            Line::from("P: frame rate, analysis time and dropped updates"),
+            Line::from("M: next chart marker (braille, half block, block, dot)"),
+            Line::from("r: start/stop recording"),
+            Line::from("space: freeze the charts and readings, the audio goes on"),
+            Line::from("S: save the spectrum to a csv and an svg"),
+            Line::from("q: quit"),
+        ]);
+        lines
+    }
+    /// How fast the ui keeps up, in a box in the top right corner
+    fn render_perf(&self, frame: &mut Frame, area: Rect) {
+        let since_data = self.last_data_time.map_or_else(
+            || "none yet".to_string(),
+            |time| format!("{:.0} ms ago", time.elapsed().as_secs_f32() * 1000.0),
+        );
+        let lines = vec![
+            Line::from(format!("draw: {} fps", self.frame_times.len())),
+            Line::from(format!("data: {since_data}")),
+            Line::from(format!(
+                "analysis: {:.1} ms per window",
+                self.freq_data.analysis_time.as_secs_f32() * 1000.0
+            )),
+            Line::from(format!(
+                "dropped: {} windows",
+                self.freq_data.dropped_windows
+            )),
+            Line::from(format!("stale: {} updates", self.stale_updates)),
+        ];
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+        let [_, area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(width)]).areas(area);
+        let [_, area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(lines.len() as u16 + 2),
+        ])
+        .areas(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title("Performance (P)")
+                    .padding(Padding::horizontal(1))
+                    .border_style(Style::default().fg(self.theme.muted)),
+            ),
+            area,
+        );
+    }
+    /// The recent notifications in a box in the bottom right corner, above the
+    /// status bar, newest at the bottom
+    fn render_toasts(&self, frame: &mut Frame, area: Rect) {
+        let lines = self
+            .toasts
+            .iter()
+            .filter(|toast| toast.at.elapsed() < TOAST_DURATION)
+            .map(|toast| {
+                Line::from(toast.message.as_str()).fg(if toast.error {
+                    self.theme.bad
+                } else {
+                    self.theme.highlight
+                })
+            })
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            return;
+        }
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+        let [_, area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(width)]).areas(area);
+        let [_, area, _] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(lines.len() as u16 + 2),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .padding(Padding::horizontal(1))
+                    .border_style(Style::default().fg(self.theme.muted)),
+            ),
+            area,
+        );
+    }
+    /// The help in a box over the middle of the screen
+    fn render_help(&self, frame: &mut Frame, area: Rect) {
+        let lines = self.help_lines();
+        let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+        let height = lines.len() as u16 + 2;
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(height)])
+            .flex(Flex::Center)
+            .areas(area);
+        let page = area.height.saturating_sub(2);
+        self.help_page.set(page.max(1));
+        self.help_max_scroll
+            .set((lines.len() as u16).saturating_sub(page));
+        // the terminal may have grown since the last key
+        let scroll = self.help_scroll.min(self.help_max_scroll.get());
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines).scroll((scroll, 0)).block(
+                Block::bordered()
+                    .title("Help (? or esc to close)")
+                    .title_alignment(ratatui::layout::Alignment::Center)
+                    .padding(Padding::horizontal(1))
+                    .border_style(Style::default().fg(self.theme.highlight)),
+            ),
+            area,
+        );
+    }
+
+    fn render_time_domain(&self, frame: &mut Frame, area: Rect) {
+        if self.freq_data.time_domain_samples.is_empty() {
+            return;
+        }
+        let samples = &self.freq_data.time_domain_samples;
+        let top = self.time_domain_top.top(self.auto_scale);
+        let level = self.trigger_level * top as f32;
+        // half a window is shown while triggered, so every start has as many
+        // samples after it
+        let (start, len) = match self.trigger {
+            Trigger::Free => (0, samples.len()),
+            Trigger::RisingEdge => (rising_edge(samples, level).unwrap_or(0), samples.len() / 2),
+            Trigger::Pitch => {
+                let period =
+                    self.freq_data.sample_rate as f32 / self.freq_data.fundamental_frequency;
+                let half = samples.len() / 2;
+                let len = if period.is_finite() && period >= 1.0 && (period as usize) <= half {
+                    (half as f32 / period).floor() * period
+                } else {
+                    half as f32
+                };
+                (
+                    rising_edge(samples, level).unwrap_or(0),
+                    len.round() as usize,
+                )
+            }
+        };
+        let data = &samples[start..start + len];
+        let x_bounds = (0, data.len());
+        let x_count = axis_label_count(area.width, X_LABEL_SPACING);
+        let y_count = axis_label_count(area.height.saturating_sub(3), Y_LABEL_SPACING);
+        let x_labels = axis_steps(0.0, x_bounds.1 as f64, x_count)
+            .map(|sample| {
+                Span::styled(
+                    format!("{sample:.0}"),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            })
+            .collect::<Vec<_>>();
+        let data = data
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (i as f64, *d as f64))
+            .collect::<Vec<_>>();
+        let trigger_line = [(0.0, level as f64), (x_bounds.1 as f64, level as f64)];
+        let grid = gridlines((0.0, x_bounds.1 as f64), -top, top, y_count);
+        let mut datasets = grid
+            .iter()
+            .map(|line| {
+                Dataset::default()
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(self.theme.faint))
+                    .graph_type(ratatui::widgets::GraphType::Line)
+                    .data(line)
+            })
+            .collect::<Vec<_>>();
+        datasets.push(
+            Dataset::default()
+                .marker(self.marker.marker())
+                .style(Style::default().fg(self.theme.signal))
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .data(&data),
+        );
+        if self.trigger != Trigger::Free {
+            datasets.insert(
+                grid.len(),
+                Dataset::default()
+                    .marker(self.marker.marker())
+                    .style(Style::default().fg(self.theme.faint))
+                    .graph_type(ratatui::widgets::GraphType::Line)
+                    .data(&trigger_line),
+            );
+        }
+        let y_bounds = (-top, top);
+        let title = match self.trigger {
+            Trigger::Free => "Time domain (O: trigger)".to_string(),
+            Trigger::RisingEdge => format!(
+                "Time domain, rising edge at {:+.2} (O, </>)",
+                self.trigger_level
+            ),
+            Trigger::Pitch => format!(
+                "Time domain, periods of the pitch from {:+.2} (O, </>)",
+                self.trigger_level
+            ),
+        };
+
+        let chart = Chart::new(datasets)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_alignment(ratatui::layout::Alignment::Center),
+            )
+            .x_axis(
+                Axis::default()
+                    .title("Time".to_string())
+                    .style(Style::default().fg(self.theme.muted))
+                    .labels(x_labels)
+                    .bounds([x_bounds.0 as f64, x_bounds.1 as f64]),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("Magnitude")
+                    .style(Style::default().fg(self.theme.muted))
+                    .labels(
+                        axis_steps(-top, top, y_count)
+                            .map(|y| {
+                                // the middle one can come out a hair below 0
+                                let sign = if y < -top * 1e-9 { "-" } else { "" };
+                                Span::styled(
+                                    format!("{sign}{}", axis_number(y.abs())),
+                                    Style::default(),
+                                )
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .bounds([y_bounds.0, y_bounds.1]),
+            );
+
+        frame.render_widget(chart, area);
+    }
+    fn render_freqs(&self, frame: &mut Frame, area: Rect) {
+        if self.freq_data.data.is_empty() {
+            return;
+        }
+        match self.spectrum_view {
+            SpectrumView::Linear if self.spectrum_bars => {
+                return self.render_spectrum_bars(frame, area);
+            }
+            SpectrumView::Linear => {}
+            SpectrumView::Mel => return self.render_mel(frame, area),
+            SpectrumView::ConstantQ => return self.render_cqt(frame, area),
+            SpectrumView::Chroma => return self.render_chroma(frame, area),
+            SpectrumView::Mfcc => return self.render_mfcc(frame, area),
+        }
+        // let n = self.freq_data.data.len() / 4;
+        // let x_bounds = (self.freq_data.data[0].0, self.freq_data.data[n].0);
+        let n = self.preset().max_display_hz as f64;
+        // on the log axis x is in octaves above LOG_AXIS_LOWEST_HZ
+        let octaves = |hz: f64| (hz / LOG_AXIS_LOWEST_HZ).log2();
+        // the zoomed in part of the full axis
+        let view = |(low, high): (f64, f64)| {
+            let span = high - low;
+            (low + span * self.freq_view.0, low + span * self.freq_view.1)
+        };
+        let x_count = axis_label_count(area.width, X_LABEL_SPACING);
+        let y_count = axis_label_count(area.height.saturating_sub(3), Y_LABEL_SPACING);
+        let spectrum = if self.accumulated_spectrum.is_empty() {
+            &self.freq_data.data
+        } else {
+            &self.accumulated_spectrum
+        };
+        let (x_bounds, x_labels, data) = if self.log_frequency {
+            let x_bounds = view((0.0, octaves(n)));
+            let x_labels = axis_steps(x_bounds.0, x_bounds.1, x_count)
+                .map(|octave| {
+                    Span::styled(
+                        format!("{:.0}", LOG_AXIS_LOWEST_HZ * 2f64.powf(octave)),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let data = if self.freq_data.pitch_method == PitchMethod::Goertzel {
+                // a point per note is spaced evenly already
+                spectrum
+                    .iter()
+                    .map(|(hz, magnitude)| (octaves(*hz), *magnitude))
+                    .collect()
+            } else {
+                octave_bands(spectrum, n, LOG_AXIS_BANDS_PER_OCTAVE)
+            };
+            (x_bounds, x_labels, Cow::Owned(data))
+        } else {
+            let x_bounds = view((self.freq_data.data[0].0, n));
+            let x_labels = axis_steps(x_bounds.0, x_bounds.1, x_count)
+                .map(|hz| {
+                    Span::styled(
+                        format!("{hz:.0}"),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )
+                })
+                .collect::<Vec<_>>();
+            (x_bounds, x_labels, Cow::Borrowed(&spectrum[..]))
+        };
+        let points = self.freqs_scale.points(&data, &self.freq_data);
+        let linear_top = self.freqs_top.top(self.auto_scale);
+        self.freqs_plot.set(chart_plot_area(
+            area,
+            &self.freqs_scale.labels(linear_top, y_count),
+            &x_labels,
+        ));
+        self.freqs_bounds.set(x_bounds);
+        let held = self
+            .freq_data
+            .data
+            .iter()
+            .zip(&self.held_peaks)
+            .map(|((hz, _), held)| (*hz, held.magnitude))
+            .collect::<Vec<_>>();
+        let held = if !self.log_frequency {
+            held
+        } else if self.freq_data.pitch_method == PitchMethod::Goertzel {
+            held.into_iter()
+                .map(|(hz, magnitude)| (octaves(hz), magnitude))
+                .collect()
+        } else {
+            octave_bands(&held, n, LOG_AXIS_BANDS_PER_OCTAVE)
+        };
+        let held_points = self.freqs_scale.points(&held, &self.freq_data);
+        let top = self.freqs_scale.top(linear_top);
+        let runs = spectrum_runs(&points, top);
+        // under everything else
+        let grid = gridlines(x_bounds, 0.0, top, y_count);
+        let notes = if self.note_grid {
+            self.note_grid_notes(x_bounds, &octaves)
+        } else {
+            vec![]
+        };
+        let note_lines = notes
+            .iter()
+            .map(|(_, x)| [(*x, 0.0), (*x, top)])
+            .collect::<Vec<_>>();
+        let mut datasets = grid
+            .iter()
+            .chain(&note_lines)
+            .map(|line| {
+                Dataset::default()
+                    .marker(symbols::Marker::Braille)
+                    .style(Style::default().fg(self.theme.faint))
+                    .graph_type(ratatui::widgets::GraphType::Line)
+                    .data(line)
+            })
+            .collect::<Vec<_>>();
+        let under_spectrum = datasets.len();
+        datasets.extend(
+            runs.iter()
+                .enumerate()
+                .filter(|(_, run)| !run.is_empty())
+                .map(|(step, run)| {
+                    Dataset::default()
+                        .marker(self.marker.marker())
+                        .style(
+                            Style::default().fg(self
+                                .theme
+                                .spectrum(step as f32 / (SPECTRUM_COLOR_STEPS - 1) as f32)),
+                        )
+                        .graph_type(ratatui::widgets::GraphType::Line)
+                        .data(run)
+                }),
+        );
+        // under the live spectrum, so it only shows where that has fallen away
+        datasets.insert(
+            under_spectrum,
+            Dataset::default()
+                .name("held")
+                .marker(self.marker.marker())
+                .style(Style::default().fg(self.theme.faint))
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .data(&held_points),
+        );
+        let harmonic_markers = self
+            .freq_data
+            .harmonics
+            .harmonics
+            .iter()
+            // ones off the top of the chart still get a marker along it
+            .map(|harmonic| {
+                (
+                    if self.log_frequency {
+                        octaves(harmonic.frequency as f64)
+                    } else {
+                        harmonic.frequency as f64
+                    },
+                    self.freqs_scale
+                        .plot(harmonic.magnitude as f64, &self.freq_data)
+                        .min(top),
+                )
+            })
+            .collect::<Vec<_>>();
+        let peak_markers = self
+            .freq_data
+            .peaks
+            .iter()
+            .map(|peak| {
+                (
+                    if self.log_frequency {
+                        octaves(peak.frequency as f64)
+                    } else {
+                        peak.frequency as f64
+                    },
+                    self.freqs_scale
+                        .plot(peak.magnitude as f64, &self.freq_data)
+                        .min(top),
+                )
+            })
+            .collect::<Vec<_>>();
+        datasets.push(
+            Dataset::default()
+                .name("peaks")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(self.theme.peak))
+                .graph_type(ratatui::widgets::GraphType::Scatter)
+                .data(&peak_markers),
+        );
+        // the chart has no room for text next to the markers, so they're
+        // listed along the bottom
+        let peak_labels = self
+            .freq_data
+            .peaks
+            .iter()
+            .map(|peak| match self.tuning.note_name(peak.frequency) {
+                Some(note) => format!("{:.1} Hz {note}", peak.frequency),
+                None => format!("{:.1} Hz", peak.frequency),
+            })
+            .join("  ");
+        if self.freq_data.pitch_confidence >= MIN_PITCH_CONFIDENCE {
+            datasets.push(
+                Dataset::default()
+                    .name("harmonics")
+                    .marker(symbols::Marker::Dot)
+                    .style(Style::default().fg(self.theme.highlight))
+                    .graph_type(ratatui::widgets::GraphType::Scatter)
+                    .data(&harmonic_markers),
+            );
+        }
+
+        // a line up the chart at the clicked frequency, labelled in the corner
+        let inspected = self.inspected_hz.map(|hz| {
+            let x = if self.log_frequency { octaves(hz) } else { hz };
+            [(x, 0.0), (x, top)]
+        });
+        let mut inspected_label = Line::default();
+        if let Some(hz) = self.inspected_hz
+            && let Some(inspected) = &inspected
+        {
+            datasets.push(
+                Dataset::default()
+                    .name("inspected")
+                    .marker(self.marker.marker())
+                    .style(Style::default().fg(self.theme.highlight))
+                    .graph_type(ratatui::widgets::GraphType::Line)
+                    .data(inspected),
+            );
+            let magnitude = self
+                .freq_data
+                .data
+                .iter()
+                .min_by(|a, b| (a.0 - hz).abs().total_cmp(&(b.0 - hz).abs()))
+                .map_or(0.0, |(_, magnitude)| *magnitude);
+            let mut label = format!("{hz:.1} Hz");
+            if let Some(reading) = self.tuning.note(hz as f32) {
+                label += &format!(" {} {:+.0} cents", reading.name, reading.cents);
+            }
+            label += &format!(", {:.0} dBFS ", self.freq_data.to_dbfs(magnitude));
+            inspected_label = Line::from(label).right_aligned().fg(self.theme.highlight);
+        }
+
+        let chart = Chart::new(datasets)
+            .block(
+                Block::bordered()
+                    .title(match self.persistence {
+                        SpectrumPersistence::Latest => "Frequencies".to_string(),
+                        persistence => format!("Frequencies ({})", persistence.name()),
+                    })
+                    .title_alignment(ratatui::layout::Alignment::Center)
+                    .title(inspected_label)
+                    .title_bottom(Line::from(peak_labels).centered().fg(self.theme.peak)),
+            )
+            .x_axis(
+                Axis::default()
+                    .title(if self.log_frequency {
+                        "Frequency (octaves)"
+                    } else {
+                        "Frequency"
+                    })
+                    .style(Style::default().fg(self.theme.muted))
+                    .labels(x_labels)
+                    .bounds([x_bounds.0, x_bounds.1]),
+            )
+            .y_axis(self.freqs_scale.axis(linear_top, y_count, &self.theme));
+
+        frame.render_widget(chart, area);
+        self.render_note_names(frame, &notes, x_bounds);
+        // shade what a drag would zoom into
+        if let Some((from, to)) = self.freqs_drag {
+            let buffer = frame.buffer_mut();
+            // the drag started on the last frame's chart, which may have been bigger
+            let plot = self.freqs_plot.get().intersection(buffer.area);
+            for x in from.min(to).max(plot.left())..(from.max(to) + 1).min(plot.right()) {
+                for y in plot.top()..plot.bottom() {
+                    buffer[(x, y)].set_bg(self.theme.faint);
+                }
+            }
+        }
+    }
+    /// The notes the note grid marks within `x_bounds` of the fft chart, as
+    /// their midi note number and where they are along the x axis. Every
+    /// note where there's room for it, only the Cs where there isn't
+    fn note_grid_notes(
+        &self,
+        x_bounds: (f64, f64),
+        octaves: &impl Fn(f64) -> f64,
+    ) -> Vec<(i32, f64)> {
+        let x = |midi: i32| {
+            let hz = self.tuning.equal_tempered_hz(midi) as f64;
+            if self.log_frequency { octaves(hz) } else { hz }
+        };
+        let columns_per_x = self.freqs_plot.get().width as f64 / (x_bounds.1 - x_bounds.0);
+        (NOTE_GRID_MIDI.0..=NOTE_GRID_MIDI.1)
+            .filter(|midi| {
+                let semitone_columns = (x(midi + 1) - x(*midi)) * columns_per_x;
+                semitone_columns >= NOTE_GRID_MIN_COLUMNS
+                    || midi % 12 == 0 && semitone_columns * 12.0 >= NOTE_GRID_MIN_COLUMNS
+            })
+            .map(|midi| (midi, x(midi)))
+            .filter(|(_, x)| (x_bounds.0..=x_bounds.1).contains(x))
+            .collect()
+    }
+    /// The names of the natural ones of `notes` along the top of the fft
+    /// chart, left to right as long as they don't run into each other
+    fn render_note_names(&self, frame: &mut Frame, notes: &[(i32, f64)], x_bounds: (f64, f64)) {
+        let plot = self.freqs_plot.get();
+        let columns_per_x = plot.width as f64 / (x_bounds.1 - x_bounds.0);
+        let buffer = frame.buffer_mut();
+        let mut free_from = plot.left();
+        for (midi, x) in notes {
+            let name = note_from_midi_note_number(*midi);
+            if name.contains('#') {
+                continue;
+            }
+            let column = plot.left() + ((x - x_bounds.0) * columns_per_x) as u16;
+            if column < free_from || column + name.len() as u16 > plot.right() {
+                continue;
+            }
+            buffer.set_string(
+                column,
+                plot.top(),
+                &name,
+                Style::default().fg(self.theme.muted),
+            );
+            free_from = column + name.len() as u16 + 1;
+        }
+    }
+    /// Room the vu meters take: the tick labels and a bar per channel
+    fn vu_meters_width(&self) -> u16 {
+        let channels = self.channel_levels.len().max(1) as u16;
+        // the borders, the labels and a gap before every bar
+        2 + 3 + channels * (VU_BAR_WIDTH + 1)
+    }
+    /// A vertical meter per channel from -60 dBFS up, green to red as it gets
+    /// louder, with a marker at the recent peak. The segment on top lights up
+    /// red for a while after the channel clipped.
+    fn render_vu_meters(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title("dB")
+            .title_alignment(ratatui::layout::Alignment::Center);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let labelled = self.channel_levels.len() > 1;
+        // the clip segment, the scale, and the channel numbers under it
+        let scale_height = inner.height.saturating_sub(1 + u16::from(labelled));
+        if scale_height < 2 {
+            return;
+        }
+        let scale_top = inner.y + 1;
+        // row 0 is 0 dBFS, the last row the bottom of the range
+        let row_of = |db: f64| {
+            ((-db / VU_RANGE_DB) * (scale_height - 1) as f64)
+                .round()
+                .clamp(0.0, (scale_height - 1) as f64) as u16
+        };
+        let db_of = |row: u16| -VU_RANGE_DB * row as f64 / (scale_height - 1) as f64;
+        let buffer = frame.buffer_mut();
+        let mut labelled_rows = vec![];
+        for tick in VU_TICKS_DB {
+            let row = row_of(tick);
+            if labelled_rows.contains(&row) {
+                continue;
+            }
+            labelled_rows.push(row);
+            buffer.set_string(
+                inner.x,
+                scale_top + row,
+                format!("{tick:>3.0}"),
+                Style::default().fg(self.theme.muted),
+            );
+        }
+        for (i, (channel, meter)) in self.channel_levels.iter().enumerate() {
+            let x = inner.x + 3 + 1 + i as u16 * (VU_BAR_WIDTH + 1);
+            if x + VU_BAR_WIDTH > inner.right() {
+                break;
+            }
+            let clipped = meter
+                .last_clip
+                .is_some_and(|clip| clip.elapsed() < CLIP_HOLD);
+            let clip_color = if clipped {
+                self.theme.bad
+            } else {
+                self.theme.faint
+            };
+            let peak_row = row_of(meter.peak);
+            for column in x..x + VU_BAR_WIDTH {
+                buffer[(column, inner.y)].set_symbol("▀").set_fg(clip_color);
+                for row in 0..scale_height {
+                    let db = db_of(row);
+                    let color = if db > VU_HOT_DB {
+                        self.theme.bad
+                    } else if db > VU_WARM_DB {
+                        self.theme.warning
+                    } else {
+                        self.theme.good
+                    };
+                    let cell = &mut buffer[(column, scale_top + row)];
+                    if meter.level > -VU_RANGE_DB && meter.level >= db {
+                        cell.set_symbol("█").set_fg(color);
+                    } else if row == peak_row && meter.peak > -VU_RANGE_DB {
+                        cell.set_symbol("▔").set_fg(self.theme.bright);
+                    } else {
+                        cell.set_symbol("│").set_fg(self.theme.faint);
+                    }
+                }
+                if labelled {
+                    buffer.set_string(
+                        x,
+                        scale_top + scale_height,
+                        format!("{:^width$}", channel + 1, width = VU_BAR_WIDTH as usize),
+                        Style::default().fg(self.theme.muted),
+                    );
+                }
+            }
+        }
+    }
+    /// A tab per screen, the open one highlighted. They can be clicked.
+    fn render_tabs(&self, frame: &mut Frame, area: Rect) {
+        self.tabs_area.set(area);
+        let tabs = self.tabs();
+        let selected = tabs.iter().position(|(screen, _)| *screen == self.screen);
+        let tabs = Tabs::new(tabs.into_iter().map(|(_, title)| title))
+            .style(Style::default().fg(self.theme.muted))
+            .highlight_style(Style::default().fg(self.theme.highlight))
+            .select(selected);
+        frame.render_widget(tabs, area);
+    }
+    /// What's being listened to and how, on the left, and the screen's keys on
+    /// the right, or the latest status message for a few seconds
+    fn render_status_bar(&self, frame: &mut Frame, area: Rect) {
+        let analysis = &self.listener_options.analysis;
+        let sample_rate = if self.freq_data.sample_rate > 0 {
+            format!("{} Hz", self.freq_data.sample_rate)
+        } else {
+            "? Hz".to_string()
+        };
+        let method = if self.freq_data.sample_rate > 0 {
+            self.freq_data.pitch_method.name().to_string()
+        } else {
+            value_name(&analysis.pitch_detector)
+        };
+        let setup = format!(
+            " {}{} | {sample_rate} | {} samples | {method}",
+            if self.frozen { "frozen | " } else { "" },
+            self.setting_value(Setting::Device),
+            analysis.window_size,
+        );
+        let right = match &self.status {
+            Some((status, at)) if at.elapsed() < STATUS_DURATION => {
+                Span::from(format!("{status} ")).fg(self.theme.highlight)
+            }
+            _ => Span::from(format!("{} ", self.screen.key_hints())).fg(self.theme.muted),
+        };
+        let [setup_area, right_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(right.width() as u16)])
+                .areas(area);
+        frame.render_widget(Line::from(setup), setup_area);
+        frame.render_widget(Line::from(right).right_aligned(), right_area);
+    }
+    /// The spectrum as a bar per third of an octave, green to red as they get
+    /// louder, each with a marker at its recent peak
+    fn render_spectrum_bars(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title("Spectrum bars (x)")
+            .title_alignment(ratatui::layout::Alignment::Center);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        if inner.is_empty() {
+            return;
+        }
+        let [axis_area, bars_area] =
+            Layout::horizontal([Constraint::Length(5), Constraint::Min(0)]).areas(inner);
+        let [bars_area, labels_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(bars_area);
+        let bands = self.bar_levels.len();
+        let height = bars_area.height as usize;
+        if bands == 0 || height == 0 {
+            return;
+        }
+        for (row, label) in [(0, "0"), (height / 2, "-50"), (height - 1, "-100")] {
+            frame.render_widget(
+                Line::from(label).right_aligned().fg(self.theme.muted),
+                Rect {
+                    y: axis_area.y + row as u16,
+                    height: 1,
+                    width: axis_area.width.saturating_sub(1),
+                    ..axis_area
+                },
+            );
+        }
+        let bar_width = (bars_area.width as usize / bands).max(1);
+        // a gap between bars once they're wide enough to spare one
+        let gap = usize::from(bar_width >= 3);
+        // in eighths of a row, the partial blocks give the bars' tops
+        let eighths =
+            |level: f64| (level / SPECTRUM_RANGE_DB * (8 * height) as f64).round() as usize;
+        const PARTIAL: [&str; 8] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇"];
+        let buffer = frame.buffer_mut();
+        for (band, bar) in self.bar_levels.iter().enumerate() {
+            let x = bars_area.x + (band * bar_width) as u16;
+            if x >= bars_area.right() {
+                break;
+            }
+            let filled = eighths(bar.level);
+            let peak_row = (eighths(bar.peak) / 8).min(height - 1);
+            for row in 0..height {
+                // row 0 is the bottom
+                let y = bars_area.bottom() - 1 - row as u16;
+                let color = match row * 10 / height {
+                    0..=5 => self.theme.good,
+                    6..=7 => self.theme.warning,
+                    _ => self.theme.bad,
+                };
+                let (symbol, color) = if filled >= 8 * (row + 1) {
+                    ("█", color)
+                } else if filled > 8 * row {
+                    (PARTIAL[filled - 8 * row], color)
+                } else if row == peak_row && bar.peak > 0.0 {
+                    ("▔", self.theme.bright)
+                } else {
+                    continue;
+                };
+                for column in 0..bar_width - gap {
+                    let x = x + column as u16;
+                    if x < bars_area.right() {
+                        buffer[(x, y)].set_symbol(symbol).set_fg(color);
+                    }
                 }
             }
-            AppScreen::Debug => {
-                let layout = Layout::default()
-                    .direction(ratatui::layout::Direction::Vertical)
-                    .constraints([
-                        Constraint::Length(4),
-                        Constraint::Length(1),
-                        Constraint::Ratio(1, 2),
-                        Constraint::Ratio(1, 2),
-                    ])
-                    .split(frame.area());
-                let top = layout[0];
-                let history_line_area = layout[1];
-                let middle = layout[2];
-                let bottom = layout[3];
-                let note = self
-                    .note_history
-                    .last()
-                    .map_or(" ".to_string(), |n| n.note.clone());
-                let peak_freq_text = format!("Peak frequency: {}", self.freq_data.peak_frequency);
-                let max_magnitude_text = format!("Max Magnitude: {}", self.freq_data.max_magnitude);
-                let text_left = Text::from(vec![
-                    Line::from(peak_freq_text),
-                    Line::from(format!(
-                        "Fundamental frequency (HPS): {}",
-                        self.freq_data.fundamental_frequency
-                    )),
-                ])
-                .centered();
-                let top_layout = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([
-                        Constraint::Ratio(1, 3),
-                        Constraint::Ratio(1, 3),
-                        Constraint::Ratio(1, 3),
-                    ])
-                    .split(top);
-                let text_right = Text::from(vec![
-                    Line::from(format!("Sample rate: {}", self.freq_data.sample_rate)),
-                    Line::from(max_magnitude_text),
-                ]);
-                frame.render_widget(
-                    Paragraph::new(text_left).block(Block::bordered()),
-                    top_layout[0],
-                );
-
-                let note_text = Line::from(note).centered();
-                frame.render_widget(
-                    Paragraph::new(note_text).block(Block::bordered()),
-                    top_layout[1],
-                );
-                frame.render_widget(
-                    Paragraph::new(text_right).block(Block::bordered()),
-                    top_layout[2],
-                );
-                let history_items_space_available = (history_line_area.width / 3) as usize;
-                frame.render_widget(
-                    Text::from(Line::from(vec![
-                        Span::default()
-                            .content(
-                                self.note_history.last().map_or_else(
-                                    || String::from("| "),
-                                    |m| format!("| {} | ", m.note),
-                                ),
-                            )
-                            .bold(),
-                        Span::default().content(
-                            self.note_history[(if self.note_history.len()
-                                > history_items_space_available
-                            {
-                                self.note_history.len() - history_items_space_available
-                            } else {
-                                0
-                            })
-                                ..self.note_history.len()]
-                                .iter()
-                                .rev()
-                                .skip(1)
-                                .map(|n| format!("{} ", n.note))
-                                .join("|"),
-                        ),
-                    ])),
-                    history_line_area,
+        }
+        // the center of every third band, an octave apart
+        for band in (0..bands).step_by(BAR_BANDS_PER_OCTAVE as usize) {
+            let hz = LOG_AXIS_LOWEST_HZ * 2f64.powf((band as f64 + 0.5) / BAR_BANDS_PER_OCTAVE);
+            let label = if hz >= 1000.0 {
+                format!("{:.1}k", hz / 1000.0)
+            } else {
+                format!("{hz:.0}")
+            };
+            let x = labels_area.x + (band * bar_width) as u16;
+            if x + label.len() as u16 <= labels_area.right() {
+                buffer.set_string(
+                    x,
+                    labels_area.y,
+                    label,
+                    Style::default().fg(self.theme.muted),
                 );
-
-                self.render_freqs(frame, middle);
-                self.render_time_domain(frame, bottom);
-            }
-            AppScreen::Help => {
-                self.show_help(frame, frame.area());
             }
         }
     }
-
-    fn show_help(&self, frame: &mut Frame, area: Rect) {
-        let lines = vec![
-            Line::from("h: help"),
-            Line::from("d: debug and visualization"),
-            Line::from("t: tutor"),
-            Line::from("q: quit"),
-        ];
-        let text = Text::from(lines).centered();
-        frame.render_widget(text, area);
+    /// The mel spectrum, the x axis in mels but labelled in Hz
+    fn render_mel(&self, frame: &mut Frame, area: Rect) {
+        let max_mel = hz_to_mel(self.preset().max_display_hz) as f64;
+        let x_labels = [0.0, 0.25, 0.5, 0.75, 1.0]
+            .map(|fraction| {
+                Span::styled(
+                    format!("{:.0}", mel_to_hz((max_mel * fraction) as f32)),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            })
+            .to_vec();
+        let points = self
+            .mel_scale
+            .points(&self.freq_data.mel_data, &self.freq_data);
+        let chart = Chart::new(vec![
+            Dataset::default()
+                .name("mel")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(self.theme.signal))
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .data(&points),
+        ])
+        .block(
+            Block::bordered()
+                .title("Mel spectrum (m)")
+                .title_alignment(ratatui::layout::Alignment::Center),
+        )
+        .x_axis(
+            Axis::default()
+                .title("Frequency".to_string())
+                .style(Style::default().fg(self.theme.muted))
+                .labels(x_labels)
+                .bounds([0.0, max_mel]),
+        )
+        .y_axis(self.mel_scale.axis(
+            self.mel_top.top(self.auto_scale),
+            axis_label_count(area.height.saturating_sub(3), Y_LABEL_SPACING),
+            &self.theme,
+        ));
+        frame.render_widget(chart, area);
     }
-
-    fn render_time_domain(&self, frame: &mut Frame, area: Rect) {
-        if self.freq_data.time_domain_samples.is_empty() {
-            return;
-        }
-        let data = self.freq_data.time_domain_samples.clone();
-        let x_bounds = (0, data.len());
-        let x_labels = vec![
-            Span::styled(
-                format!("{:.2}", x_bounds.0),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                format!("{:.2}", x_bounds.1),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-        ];
-        let data = data
+    /// The constant-q spectrum, a bar per semitone in dB. The bottom ones the
+    /// window is too short to resolve are greyed out
+    fn render_cqt(&self, frame: &mut Frame, area: Rect) {
+        let data = self
+            .freq_data
+            .cqt
             .iter()
             .enumerate()
-            .map(|(i, d)| (i as f64, *d as f64 * 1000.0))
+            .map(|(i, amplitude)| {
+                let db = linear_to_db(*amplitude).max(-CQT_RANGE_DB) + CQT_RANGE_DB;
+                (i as f64, db as f64)
+            })
+            .collect::<Vec<_>>();
+        let (smeared, resolved) = data.split_at(self.freq_data.cqt_constant_q_from.min(data.len()));
+        let x_labels = (0..=CQT_BINS / 12)
+            .map(|octave| {
+                Span::styled(
+                    format!("C{}", CQT_LOWEST_MIDI / 12 - 1 + octave as i32),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            })
             .collect::<Vec<_>>();
-        let datasets = vec![
+        let chart = Chart::new(vec![
             Dataset::default()
                 .marker(symbols::Marker::Braille)
-                .style(Style::default().fg(Color::Cyan))
-                .graph_type(ratatui::widgets::GraphType::Line)
-                .data(&data),
-        ];
-        let y_bounds = (-50.0, 50.0);
-
-        let chart = Chart::new(datasets)
+                .style(Style::default().fg(self.theme.faint))
+                .graph_type(ratatui::widgets::GraphType::Bar)
+                .data(smeared),
+            Dataset::default()
+                .name("semitones")
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(self.theme.signal))
+                .graph_type(ratatui::widgets::GraphType::Bar)
+                .data(resolved),
+        ])
+        .block(
+            Block::bordered()
+                .title("Constant-Q spectrum (m)")
+                .title_alignment(ratatui::layout::Alignment::Center),
+        )
+        .x_axis(
+            Axis::default()
+                .title("Note".to_string())
+                .style(Style::default().fg(self.theme.muted))
+                .labels(x_labels)
+                .bounds([0.0, CQT_BINS as f64]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("dBFS")
+                .style(Style::default().fg(self.theme.muted))
+                .labels(vec![
+                    Span::styled(format!("-{CQT_RANGE_DB}"), Style::default()),
+                    Span::styled("0", Style::default()),
+                ])
+                .bounds([0.0, CQT_RANGE_DB as f64]),
+        );
+        frame.render_widget(chart, area);
+    }
+    /// Rate and depth of the vibrato of the held note, for singers and string
+    /// players working on theirs
+    fn render_vibrato(&self, frame: &mut Frame, area: Rect) {
+        let lines = if self.idle {
+            vec![Line::from("listening…").fg(self.theme.faint)]
+        } else if let Some(vibrato) = self.freq_data.vibrato {
+            vec![
+                Line::from(format!(
+                    "{:.1} Hz, ±{:.0} cents",
+                    vibrato.rate_hz, vibrato.depth_cents
+                )),
+                Line::from("singers mostly sit around 5 to 7 Hz and ±20 to ±100 cents")
+                    .fg(self.theme.muted),
+            ]
+        } else {
+            vec![Line::from("none, hold a note for a second to measure it").fg(self.theme.faint)]
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .centered()
+                .block(Block::bordered().title("Vibrato")),
+            area,
+        );
+    }
+    /// How strong each pitch class is, whatever the octave
+    fn render_chroma(&self, frame: &mut Frame, area: Rect) {
+        let bars = NOTES
+            .iter()
+            .zip(self.freq_data.chroma)
+            .map(|(name, value)| {
+                Bar::default()
+                    .label(Line::from(*name))
+                    .value((value * 100.0) as u64)
+                    .text_value(String::new())
+            })
+            .collect::<Vec<_>>();
+        let inner_width = area.width.saturating_sub(2);
+        let bar_width = (inner_width / 12).saturating_sub(1).max(1);
+        let chart = BarChart::default()
             .block(
                 Block::bordered()
-                    .title("Time domain")
+                    .title("Chroma (m)")
                     .title_alignment(ratatui::layout::Alignment::Center),
             )
-            .x_axis(
-                Axis::default()
-                    .title("Time".to_string())
-                    .style(Style::default().fg(Color::Gray))
-                    .labels(x_labels)
-                    .bounds([x_bounds.0 as f64, x_bounds.1 as f64]),
-            )
-            .y_axis(
-                Axis::default()
-                    .title("Magnitude")
-                    .style(Style::default().fg(Color::Gray))
-                    .labels(vec![
-                        Span::styled(format!("{}", y_bounds.0), Style::default()),
-                        Span::styled(format!("{}", y_bounds.1), Style::default()),
-                    ])
-                    .bounds([y_bounds.0, y_bounds.1]),
-            );
-
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .max(100)
+            .bar_style(Style::default().fg(self.theme.signal));
         frame.render_widget(chart, area);
     }
-    fn render_freqs(&self, frame: &mut Frame, area: Rect) {
-        if self.freq_data.data.is_empty() {
+
+    /// Mfcc over time, a row per coefficient from the first up with the newest
+    /// window on the right. The loudness coefficient is left out, it would
+    /// drown out the rest. Red is positive, blue negative.
+    fn render_mfcc(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title("MFCC (m)")
+            .title_alignment(ratatui::layout::Alignment::Center);
+        let inner = block.inner(area);
+        let coefficients = self.mfcc_history.back().map_or(0, |mfcc| mfcc.len());
+        let rows = coefficients.saturating_sub(1).min(inner.height as usize);
+        if rows == 0 {
+            frame.render_widget(block, area);
             return;
         }
-        // let n = self.freq_data.data.len() / 4;
-        // let x_bounds = (self.freq_data.data[0].0, self.freq_data.data[n].0);
-        let n = 1500.0;
-        let x_bounds = (self.freq_data.data[0].0, n);
-        let x_labels = vec![
-            Span::styled(
-                format!("{:.2}", x_bounds.0),
-                Style::default().add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                format!("{:.2}", x_bounds.1),
-                Style::default().add_modifier(Modifier::BOLD),
+        let row_height = inner.height as usize / rows;
+        let frames = self
+            .mfcc_history
+            .iter()
+            .skip(self.mfcc_history.len().saturating_sub(inner.width as usize))
+            .collect::<Vec<_>>();
+        let color = |value: f32| self.theme.diverging(value / MFCC_COLOR_RANGE);
+        let lines = (1..=rows)
+            .flat_map(|coefficient| {
+                let line = Line::from(
+                    frames
+                        .iter()
+                        .map(|mfcc| {
+                            Span::styled(
+                                " ",
+                                Style::default()
+                                    .bg(color(mfcc.get(coefficient).copied().unwrap_or(0.0))),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+                .right_aligned();
+                std::iter::repeat_n(line, row_height)
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+    /// The spectra of the selected channel over time, the newest on the right
+    /// and the low frequencies at the bottom. Each cell is two pixels tall,
+    /// the top half's color in the foreground of a half block.
+    fn render_spectrogram(&self, frame: &mut Frame, area: Rect) {
+        let max_hz = self.spectrogram_max_hz();
+        let block = Block::bordered()
+            .title(format!(
+                "Spectrogram (w), 0-{max_hz:.0} Hz, {:.0} dB range",
+                self.spectrogram_range_db
+            ))
+            .title_alignment(ratatui::layout::Alignment::Center)
+            .title_bottom(
+                Line::from("↑/↓: frequency range, ←/→: color range")
+                    .centered()
+                    .fg(self.theme.faint),
+            );
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        if inner.is_empty() {
+            return;
+        }
+        let [axis_area, heatmap_area] =
+            Layout::horizontal([Constraint::Length(7), Constraint::Min(0)]).areas(inner);
+        let height = heatmap_area.height as usize;
+        if height == 0 {
+            return;
+        }
+        // a label every few rows, at the frequency of the top of its cell
+        for row in (0..height).step_by(5) {
+            let hz = max_hz * (1.0 - row as f32 / height as f32);
+            let label = if hz >= 1000.0 {
+                format!("{:.1}k", hz / 1000.0)
+            } else {
+                format!("{hz:.0}")
+            };
+            frame.render_widget(
+                Line::from(label).right_aligned().fg(self.theme.faint),
+                Rect {
+                    y: axis_area.y + row as u16,
+                    height: 1,
+                    width: axis_area.width.saturating_sub(1),
+                    ..axis_area
+                },
+            );
+        }
+        let Some(history) = self.shown_spectrum_history() else {
+            return;
+        };
+        let history = history.lock().unwrap();
+        let bin_hz = history.bin_hz();
+        if bin_hz <= 0.0 {
+            return;
+        }
+        let frames = history.frames(self.freq_data.channel);
+        let shown = frames.len().min(heatmap_area.width as usize);
+        let skip = frames.len() - shown;
+        let pixels = 2 * height;
+        let color = |magnitudes: &[f32], pixel: usize| {
+            // pixel 0 is the top, every one covers an equal slice of the range
+            let top_bin = ((max_hz / bin_hz) as usize).min(magnitudes.len().saturating_sub(1));
+            let low = (pixels - 1 - pixel) * top_bin / pixels;
+            let high = ((pixels - pixel) * top_bin / pixels).max(low + 1);
+            let magnitude = magnitudes
+                .get(low..high.min(magnitudes.len()))
+                .unwrap_or_default()
+                .iter()
+                .copied()
+                .fold(0.0, f32::max);
+            let db = self.freq_data.to_dbfs(magnitude as f64) as f32;
+            self.theme.heat(1.0 + db / self.spectrogram_range_db)
+        };
+        let x = heatmap_area.right() - shown as u16;
+        let buffer = frame.buffer_mut();
+        for (column, spectrum) in frames.skip(skip).enumerate() {
+            for row in 0..height {
+                buffer[(x + column as u16, heatmap_area.y + row as u16)]
+                    .set_char('▀')
+                    .set_fg(color(&spectrum.magnitudes, 2 * row))
+                    .set_bg(color(&spectrum.magnitudes, 2 * row + 1));
+            }
+        }
+    }
+    /// Each string of a guitar in standard tuning with how far off it was
+    /// last played, the one playing now highlighted
+    fn render_guitar_tuner(&self, frame: &mut Frame, area: Rect) {
+        let [area, fretboard_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(FRETBOARD_HEIGHT)])
+                .areas(area);
+        self.render_fretboard(frame, fretboard_area, None);
+        let gauge_width = 41;
+        let mut lines = vec![Line::default()];
+        for (string, midi) in STANDARD_TUNING.iter().enumerate() {
+            let playing = self.guitar_string == Some(string) && !self.idle;
+            let mut spans = vec![
+                Span::from(if playing { "▶ " } else { "  " }),
+                Span::from(format!("{:<4}", note_from_midi_note_number(*midi))),
+            ];
+            match self.guitar_cents[string] {
+                Some(cents) => {
+                    spans.extend(cents_gauge(cents, gauge_width, &self.theme));
+                    spans.push(Span::from(format!(" {cents:+4.0} cents  ")));
+                    spans.push(if cents.abs() <= IN_TUNE_CENTS {
+                        "in tune".fg(self.theme.good)
+                    } else if cents < 0.0 {
+                        "flat, tune up".fg(self.theme.warning)
+                    } else {
+                        "sharp, tune down".fg(self.theme.warning)
+                    });
+                }
+                None => {
+                    spans.push(Span::from("─".repeat(gauge_width)).fg(self.theme.faint));
+                    spans.push(" not played yet".fg(self.theme.faint));
+                }
+            }
+            let line = Line::from(spans);
+            lines.push(if playing {
+                line.add_modifier(Modifier::BOLD)
+            } else if self.guitar_string.is_some() && !self.idle {
+                line.add_modifier(Modifier::DIM)
+            } else {
+                line
+            });
+            lines.push(Line::default());
+        }
+        let title = if self.idle {
+            "Guitar strings (listening…)".to_string()
+        } else {
+            "Guitar strings, standard tuning (play one string at a time)".to_string()
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(title)
+                    .title_alignment(ratatui::layout::Alignment::Center),
             ),
+            area,
+        );
+    }
+    /// A piano with the notes sounding lit up in green, and the tutor's
+    /// `target` in yellow (in every octave when it doesn't say which). It
+    /// starts at the instrument's lowest C and moves down or up to keep the
+    /// notes sounding in view.
+    fn render_piano(&self, frame: &mut Frame, area: Rect, target: Option<&ScientificNote>) {
+        let octaves =
+            (area.width as usize / (7 * PIANO_KEY_WIDTH)).clamp(PIANO_OCTAVES.0, PIANO_OCTAVES.1);
+        let sounding = self.sounding_notes();
+        let lowest_hz = self.preset().pitch_range.0;
+        let mut lowest_c = self
+            .tuning
+            .note(lowest_hz)
+            .map_or(48, |reading| reading.midi_note_number.div_euclid(12) * 12);
+        let span = 12 * octaves as i32;
+        if let Some(lowest) = sounding.iter().min()
+            && *lowest < lowest_c
+        {
+            lowest_c = lowest.div_euclid(12) * 12;
+        } else if let Some(highest) = sounding.iter().max()
+            && *highest >= lowest_c + span
+        {
+            lowest_c = (highest.div_euclid(12) + 1) * 12 - span;
+        }
+        let lines = piano(lowest_c, octaves, &self.theme, |midi| {
+            if sounding.contains(&midi) {
+                Some(self.theme.good)
+            } else if target.is_some_and(|target| target.is_midi_note(midi)) {
+                Some(self.theme.highlight)
+            } else {
+                None
+            }
+        });
+        frame.render_widget(Paragraph::new(lines).centered(), area);
+    }
+    /// Midi notes of what's being played right now, all the notes of a chord
+    fn sounding_notes(&self) -> Vec<i32> {
+        if self.idle
+            || self.freq_data.pitch_confidence < MIN_PITCH_CONFIDENCE
+            || self.freq_data.max_magnitude <= self.preset().min_magnitude
+        {
+            return vec![];
+        }
+        let frequencies = if self.freq_data.pitches.len() > 1 {
+            &self.freq_data.pitches[..]
+        } else {
+            std::slice::from_ref(&self.freq_data.fundamental_frequency)
+        };
+        frequencies
+            .iter()
+            .filter_map(|frequency| self.tuning.note(*frequency))
+            .map(|reading| reading.midi_note_number)
+            .collect()
+    }
+    /// A guitar neck in standard tuning with every place the notes being
+    /// played can be fretted in green, and the tutor's `target` in yellow
+    fn render_fretboard(&self, frame: &mut Frame, area: Rect, target: Option<&ScientificNote>) {
+        let block = Block::bordered()
+            .title("Fretboard")
+            .title_alignment(ratatui::layout::Alignment::Center);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let sounding = self.sounding_notes();
+        let frets =
+            (inner.width as usize).saturating_sub(FRETBOARD_LABEL_WIDTH + 2) / FRETBOARD_FRET_WIDTH;
+        let lines = fretboard(frets.min(FRETBOARD_FRETS), &self.theme, |midi| {
+            if sounding.contains(&midi) {
+                Some(self.theme.good)
+            } else if target.is_some_and(|target| target.is_midi_note(midi)) {
+                Some(self.theme.highlight)
+            } else {
+                None
+            }
+        });
+        frame.render_widget(Paragraph::new(lines).centered(), inner);
+    }
+    /// The pitch over the last ten seconds against a line per natural note, the
+    /// newest on the right. The range follows what's been played, a gap
+    /// where nothing clear was.
+    fn render_pitch_contour(&self, frame: &mut Frame, area: Rect) {
+        let now = Instant::now();
+        let seconds_ago = |time: Instant| -((now - time).as_secs_f64());
+        // a line per stretch of clear pitch
+        let segments = self
+            .pitch_contour
+            .iter()
+            .chunk_by(|(_, pitch)| pitch.is_some())
+            .into_iter()
+            .filter_map(|(voiced, points)| {
+                voiced.then(|| {
+                    points
+                        .filter_map(|(time, pitch)| Some((seconds_ago(*time), (*pitch)? as f64)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let (lowest, highest) = segments.iter().flatten().fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(low, high), (_, pitch)| (low.min(*pitch), high.max(*pitch)),
+        );
+        let (lowest, highest) = if lowest.is_finite() {
+            (lowest.floor() - 1.0, highest.ceil() + 1.0)
+        } else {
+            // middle c up an octave before anything's been played
+            (60.0, 72.0)
+        };
+        let widen = (PITCH_CONTOUR_MIN_SEMITONES - (highest - lowest)).max(0.0);
+        let y_bounds = [
+            lowest - (widen / 2.0).floor(),
+            highest + (widen / 2.0).ceil(),
         ];
-        let total_points = (n * self.freq_data.samples_n as f64 / self.freq_data.sample_rate as f64)
-            .floor() as usize
-            + 1;
-        let n_chunks = 4;
-        let chunk_size = total_points / n_chunks;
-        let chunks = self.freq_data.data.chunks(chunk_size);
-        let datasets = chunks
-            .enumerate()
-            .map(|(i, c)| {
-                let multiple = 255 / (n_chunks + 1);
+        let gridlines = (y_bounds[0] as i32..=y_bounds[1] as i32)
+            .map(|midi| {
+                let span = PITCH_CONTOUR_SPAN.as_secs_f64();
+                (midi, [(-span, midi as f64), (0.0, midi as f64)])
+            })
+            .collect::<Vec<_>>();
+        // a gridline per natural, the sharps would crowd them
+        let mut datasets = gridlines
+            .iter()
+            .filter(|(midi, _)| !NOTES[midi.rem_euclid(12) as usize].ends_with('#'))
+            .map(|(_, line)| {
                 Dataset::default()
-                    .name(format!("freq{i}"))
                     .marker(symbols::Marker::Braille)
-                    .style(Style::default().fg(
-                        // if i % 2 == 0 {
-                        //     Color::Cyan
-                        // } else {
-                        //     Color::Yellow
-                        // },
-                        if i < n_chunks + 1 {
-                            Color::Rgb(
-                                255 - i as u8 * multiple as u8,
-                                i as u8 * multiple as u8,
-                                255,
-                            )
-                        } else {
-                            Color::Cyan
-                        },
-                    ))
+                    .style(Style::default().fg(self.theme.faint))
                     .graph_type(ratatui::widgets::GraphType::Line)
-                    .data(c)
+                    .data(line)
             })
             .collect::<Vec<_>>();
-
+        datasets.extend(segments.iter().map(|segment| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(self.theme.signal))
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .data(segment)
+        }));
+        // a label per semitone so they line up with the gridlines, only the
+        // naturals written out
+        let y_labels = gridlines
+            .iter()
+            .map(|(midi, _)| {
+                let name = note_from_midi_note_number(*midi);
+                Span::from(if name.contains('#') {
+                    String::new()
+                } else {
+                    name
+                })
+            })
+            .collect::<Vec<_>>();
+        let span = PITCH_CONTOUR_SPAN.as_secs_f64();
         let chart = Chart::new(datasets)
             .block(
                 Block::bordered()
-                    .title("Frequencies")
+                    .title("Pitch over time (p)")
                     .title_alignment(ratatui::layout::Alignment::Center),
             )
             .x_axis(
                 Axis::default()
-                    .title("Frequency".to_string())
-                    .style(Style::default().fg(Color::Gray))
-                    .labels(x_labels)
-                    .bounds([x_bounds.0, x_bounds.1]),
+                    .title("Seconds ago")
+                    .style(Style::default().fg(self.theme.muted))
+                    .labels(vec![
+                        Span::from(format!("{span:.0}")),
+                        Span::from(format!("{:.0}", span / 2.0)),
+                        Span::from("0"),
+                    ])
+                    .bounds([-span, 0.0]),
             )
             .y_axis(
                 Axis::default()
-                    .title("Magnitude")
-                    .style(Style::default().fg(Color::Gray))
-                    .labels(vec![
-                        Span::styled("0", Style::default()),
-                        Span::styled("40", Style::default()),
-                    ])
-                    .bounds([0.0, 40.0]),
+                    .title("Note")
+                    .style(Style::default().fg(self.theme.muted))
+                    .labels(y_labels)
+                    .bounds(y_bounds),
             );
-
         frame.render_widget(chart, area);
     }
+    /// The last notes heard with how long ago each started, the newest on the
+    /// right, as many as fit
+    fn render_note_ticker(&self, frame: &mut Frame, area: Rect) {
+        let now = Instant::now();
+        let mut spans = vec![];
+        let mut width = 0;
+        for (i, item) in self.note_history.iter().rev().enumerate() {
+            let text = format!("{} {:.1}s ago", item.note, (now - item.time).as_secs_f32());
+            let separator = if i == 0 {
+                0
+            } else {
+                NOTE_TICKER_SEPARATOR.len()
+            };
+            width += text.chars().count() + separator;
+            if width > area.width as usize {
+                break;
+            }
+            if i > 0 {
+                spans.push(Span::from(NOTE_TICKER_SEPARATOR).fg(self.theme.faint));
+            }
+            spans.push(if i == 0 {
+                Span::from(text).fg(self.theme.highlight).bold()
+            } else {
+                Span::from(text).fg(self.theme.muted)
+            });
+        }
+        spans.reverse();
+        frame.render_widget(Line::from(spans).right_aligned(), area);
+    }
+    /// The note in big letters, a needle for how many cents off it is, and a
+    /// strobe that drifts the way the note is off and stands still once it's
+    /// in tune
+    fn render_tuner(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title("Tuner (u)")
+            .title_alignment(ratatui::layout::Alignment::Center);
+        let gauge_width = (block.inner(area).width as usize).saturating_sub(4).min(61);
+        let mut lines = vec![Line::default()];
+        let Some((reading, frequency)) = &self.tuner_reading else {
+            lines.push(Line::from("play a note").centered().fg(self.theme.faint));
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+            return;
+        };
+        let in_tune = reading.cents.abs() <= IN_TUNE_CENTS;
+        // the last note heard stays up, grayed out, until the next one
+        let color = if !self.tuner_live || self.idle {
+            self.theme.faint
+        } else if in_tune {
+            self.theme.good
+        } else {
+            self.theme.warning
+        };
+        lines.extend(
+            big_text(&reading.name)
+                .into_iter()
+                .map(|row| Line::from(row).centered().fg(color)),
+        );
+        lines.push(Line::default());
+        lines.push(
+            Line::from(format!("{frequency:.1} Hz, {:+.0} cents", reading.cents))
+                .centered()
+                .fg(self.theme.muted),
+        );
+        lines.push(Line::default());
+        lines.push(Line::from(cents_gauge(reading.cents, gauge_width, &self.theme)).centered());
+        let half = gauge_width.saturating_sub(7) / 2;
+        lines.push(
+            Line::from(format!(
+                "-50{}0{}+50",
+                " ".repeat(half),
+                " ".repeat(gauge_width.saturating_sub(7) - half)
+            ))
+            .centered()
+            .fg(self.theme.faint),
+        );
+        lines.push(Line::default());
+        let phase = self.strobe_phase as usize;
+        let strobe = (0..gauge_width)
+            .map(|i| {
+                // the pattern moves right as the phase grows
+                let cell = (i + 2 * STROBE_STRIPE - phase % (2 * STROBE_STRIPE)) / STROBE_STRIPE;
+                if cell.is_multiple_of(2) { '█' } else { ' ' }
+            })
+            .collect::<String>();
+        for _ in 0..2 {
+            lines.push(Line::from(strobe.clone()).centered().fg(color));
+        }
+        lines.push(
+            Line::from(if !self.tuner_live || self.idle {
+                "strobe stopped, nothing playing"
+            } else if in_tune {
+                "steady, in tune"
+            } else if reading.cents < 0.0 {
+                "← drifting, flat, tune up"
+            } else {
+                "drifting →, sharp, tune down"
+            })
+            .centered()
+            .fg(color),
+        );
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+    /// A goniometer: mono goes straight up, wide stereo spreads sideways and
+    /// out of phase lies flat. Below it the correlation of the channels.
+    fn render_phase_scope(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title("Phase scope")
+            .title_alignment(ratatui::layout::Alignment::Center);
+        if self.stereo_samples.is_empty() {
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+            frame.render_widget(
+                Paragraph::new("The input is mono, the phase scope needs two channels")
+                    .centered()
+                    .fg(self.theme.muted),
+                inner,
+            );
+            return;
+        }
+        let peak = self
+            .stereo_samples
+            .iter()
+            .map(|(left, right)| left.abs().max(right.abs()))
+            .fold(PHASE_SCOPE_MIN_PEAK, f32::max) as f64;
+        // rotated 45° so the mid is up and the side across
+        let points = self
+            .stereo_samples
+            .iter()
+            .map(|&(left, right)| {
+                let (left, right) = (left as f64 / peak, right as f64 / peak);
+                (
+                    (right - left) * std::f64::consts::FRAC_1_SQRT_2,
+                    (left + right) * std::f64::consts::FRAC_1_SQRT_2,
+                )
+            })
+            .collect::<Vec<_>>();
+        let (lr, ll, rr) =
+            self.stereo_samples
+                .iter()
+                .fold((0.0, 0.0, 0.0), |(lr, ll, rr), &(left, right)| {
+                    (lr + left * right, ll + left * left, rr + right * right)
+                });
+        let correlation = if ll > 0.0 && rr > 0.0 {
+            lr / (ll * rr).sqrt()
+        } else {
+            0.0
+        };
+        let canvas = Canvas::default()
+            .block(
+                block.title_bottom(
+                    Line::from(format!(
+                        "correlation {correlation:+.2} (+1 mono, 0 wide, -1 out of phase)"
+                    ))
+                    .centered()
+                    .fg(if correlation < 0.0 {
+                        self.theme.bad
+                    } else {
+                        self.theme.muted
+                    }),
+                ),
+            )
+            .marker(symbols::Marker::Braille)
+            .x_bounds([-1.0, 1.0])
+            .y_bounds([-1.0, 1.0])
+            .paint(|ctx| {
+                ctx.print(-0.75, 0.75, "L".fg(self.theme.faint));
+                ctx.print(0.75, 0.75, "R".fg(self.theme.faint));
+                ctx.print(0.0, 0.95, "M".fg(self.theme.faint));
+                ctx.print(-0.95, 0.0, "S".fg(self.theme.faint));
+                ctx.draw(&Points {
+                    coords: &points,
+                    color: self.theme.signal,
+                });
+            });
+        frame.render_widget(canvas, area);
+    }
+    /// F1 against F2 of the voice among the usual spots of the vowels, laid
+    /// out like the ipa vowel chart: front vowels left, open vowels down
+    fn render_vowels(&self, frame: &mut Frame, area: Rect) {
+        // both axes grow the other way than the canvas
+        let point = |f1: f64, f2: f64| (-f2, -f1);
+        let current = self.formant_history.back().copied().flatten();
+        let title = match current {
+            _ if self.idle => "Vowels (listening…)".to_string(),
+            Some(formants) => format!(
+                "Vowels (F1 {:.0} Hz, F2 {:.0} Hz)",
+                formants.f1, formants.f2
+            ),
+            None => "Vowels (sing or say a vowel)".to_string(),
+        };
+        let trail = self
+            .formant_history
+            .iter()
+            .flatten()
+            .map(|formants| point(formants.f1 as f64, formants.f2 as f64))
+            .collect::<Vec<_>>();
+        let canvas = Canvas::default()
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_alignment(ratatui::layout::Alignment::Center)
+                    .title_bottom(
+                        Line::from(format!(
+                            "front ← F2 {:.0} to {:.0} Hz → back, F1 {:.0} to {:.0} Hz downwards",
+                            VOWEL_F2_RANGE.1, VOWEL_F2_RANGE.0, VOWEL_F1_RANGE.0, VOWEL_F1_RANGE.1
+                        ))
+                        .centered()
+                        .fg(self.theme.muted),
+                    ),
+            )
+            .marker(symbols::Marker::Braille)
+            .x_bounds([-VOWEL_F2_RANGE.1, -VOWEL_F2_RANGE.0])
+            .y_bounds([-VOWEL_F1_RANGE.1, -VOWEL_F1_RANGE.0])
+            .paint(|ctx| {
+                for (vowel, f1, f2) in REFERENCE_VOWELS {
+                    let (x, y) = point(f1, f2);
+                    ctx.print(x, y, vowel.fg(self.theme.faint));
+                }
+                ctx.draw(&Points {
+                    coords: &trail,
+                    color: self.theme.signal,
+                });
+                if !self.idle
+                    && let Some(formants) = current
+                {
+                    let (x, y) = point(formants.f1 as f64, formants.f2 as f64);
+                    ctx.print(x, y, "●".fg(self.theme.highlight));
+                }
+            });
+        frame.render_widget(canvas, area);
+    }
 }
 
 struct Tutor {
     notes_sequence: Vec<MusicalSound>,
     current_note_index: usize,
+    /// The note that last moved the tutor on
+    last_played: Option<ScientificNote>,
+    /// A note was attacked since the tutor last moved on
+    attacked: bool,
 }
 
 #[derive(Clone, Debug)]
 enum MusicalSound {
     Silence,
-    Note(MusicalNote),
+    Note(ScientificNote),
+}
+
+/// A note with its octave, like "E2". The tutor's notes can leave the octave
+/// out, then any octave will do
+#[derive(Clone, Debug, PartialEq)]
+struct ScientificNote {
+    note: MusicalNote,
+    octave: Option<i32>,
+}
+
+impl ScientificNote {
+    /// Whether playing `played` counts as this note
+    fn matches(&self, played: &ScientificNote) -> bool {
+        self.note == played.note
+            && self
+                .octave
+                .is_none_or(|octave| played.octave == Some(octave))
+    }
+}
+
+impl ScientificNote {
+    /// Whether this is the note of `midi`, in any octave when it has none
+    fn is_midi_note(&self, midi: i32) -> bool {
+        match self.octave {
+            Some(octave) => midi == 12 * (octave + 1) + self.note.semitone(),
+            None => (midi - self.note.semitone()).rem_euclid(12) == 0,
+        }
+    }
+}
+
+impl FromStr for ScientificNote {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        // the octave starts at the first digit, or the minus of octave -1
+        let split = s
+            .find(|c: char| c.is_ascii_digit() || c == '-')
+            .unwrap_or(s.len());
+        let (note, octave) = s.split_at(split);
+        Ok(Self {
+            note: note.parse()?,
+            octave: if octave.is_empty() {
+                None
+            } else {
+                Some(
+                    octave
+                        .parse()
+                        .map_err(|_| Error::msg(format!("couldn't parse octave of {s}")))?,
+                )
+            },
+        })
+    }
+}
+
+impl Display for ScientificNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.note)?;
+        if let Some(octave) = self.octave {
+            write!(f, "{octave}")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -560,6 +5357,27 @@ impl FromStr for MusicalNote {
     }
 }
 
+impl MusicalNote {
+    /// Semitones above C, B# being the C of the octave above
+    fn semitone(&self) -> i32 {
+        match self {
+            MusicalNote::C => 0,
+            MusicalNote::CSharp => 1,
+            MusicalNote::D => 2,
+            MusicalNote::DSharp => 3,
+            MusicalNote::E => 4,
+            MusicalNote::ESharp | MusicalNote::F => 5,
+            MusicalNote::FSharp => 6,
+            MusicalNote::G => 7,
+            MusicalNote::GSharp => 8,
+            MusicalNote::A => 9,
+            MusicalNote::ASharp => 10,
+            MusicalNote::B => 11,
+            MusicalNote::BSharp => 12,
+        }
+    }
+}
+
 impl Display for MusicalNote {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -590,6 +5408,8 @@ impl Tutor {
         Self {
             notes_sequence: notes,
             current_note_index: 0,
+            last_played: None,
+            attacked: false,
         }
     }
 }
@@ -1,204 +1,2090 @@
-use std::sync::{
-    Arc, Mutex,
-    mpsc::{self, Receiver, Sender},
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError},
+    },
+    time::{Duration, Instant},
 };
 
-use color_eyre::eyre::Result;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use rustfft::{FftPlanner, num_complex::Complex};
+use clap::ValueEnum;
+use color_eyre::eyre::{Result, eyre};
+use cpal::{
+    Device, FromSample, Host, Sample, SampleFormat, SampleRate, SizedSample, Stream, StreamConfig,
+    StreamError, SupportedStreamConfig, SupportedStreamConfigRange,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+use itertools::Either;
+use rayon::prelude::*;
+use realfft::{RealFftPlanner, RealToComplex, num_complex::Complex};
+use ringbuf::{
+    HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+use rubato::{FftFixedIn, Resampler};
+use serde::Deserialize;
 
-use crate::app::TerminalMessage;
+use crate::{
+    app::TerminalMessage,
+    chord::{Chord, detect_chord},
+    cqt::{ConstantQ, chroma, midi_to_hz},
+    envelope::{EnvelopeTracker, NoteEnvelope},
+    features::{FLATNESS_RANGE, SpectralFeatures, flatness},
+    filter::{Biquad, Decimator},
+    formants::Formants,
+    goertzel::{GOERTZEL_NOTES, goertzel, goertzel_notes, goertzel_pitch},
+    harmonics::HarmonicSeries,
+    history::{SharedSpectrumHistory, SpectrumHistory},
+    instrument::{Instrument, InstrumentPreset},
+    key::{Key, KeyEstimator},
+    loudness::LoudnessMeter,
+    mel::MelFilterbank,
+    mfcc::MfccExtractor,
+    monitor::{MonitorOptions, start_monitor},
+    multipitch::detect_pitches,
+    onset::{OnsetDetector, TempoEstimator},
+    peaks::{Peak, find_peaks, parabolic_vertex},
+    pitch::{
+        Autocorrelation, Cepstrum, OctaveCorrection, Pitch, PitchDetector, PitchMethod, Pyin, Yin,
+        correct_octave,
+    },
+    recorder::start_recording,
+    vibrato::{Vibrato, VibratoTracker},
+};
 
+#[derive(Default)]
 pub struct FreqData {
     pub data: Vec<(f64, f64)>,
     pub peak_frequency: f32,
     pub fundamental_frequency: f32,
     pub max_magnitude: f32,
+    /// Magnitude a full scale sine gets in `data` and `mel_data`, which is 0
+    /// dBFS
+    pub full_scale: f32,
     pub sample_rate: u32,
+    /// Size of the fft, the window length times the zero padding
     pub samples_n: usize,
     pub time_domain_samples: Vec<f32>,
+    /// Input channel this data was analyzed from
+    pub channel: usize,
+    /// Number of channels the input has
+    pub channels: usize,
+    /// The noise gate was closed for this whole window
+    pub gated: bool,
+    /// Which listener this came from, 0 for the main input
+    pub source: usize,
+    /// Level of the raw input (before gain) since the previous window
+    pub level: InputLevel,
+    /// Centroid, rolloff, flux and flatness of the spectrum
+    pub features: SpectralFeatures,
+    /// How sure the pitch detector is of `fundamental_frequency`, 0 to 1
+    pub pitch_confidence: f32,
+    /// The algorithm that found `fundamental_frequency`
+    pub pitch_method: PitchMethod,
+    /// How the hps pitch was moved to another octave, if it was
+    pub octave_correction: Option<OctaveCorrection>,
+    /// (mels, magnitude) of each mel band over the same range as `data`
+    pub mel_data: Vec<(f64, f64)>,
+    /// Mel-frequency cepstral coefficients, the first being the loudness.
+    /// Empty unless the ui asked for them with `TerminalMessage::SetMfcc`
+    pub mfcc: Vec<f32>,
+    /// Fundamentals of all the notes sounding together, strongest first
+    pub pitches: Vec<f32>,
+    /// The partials of `fundamental_frequency`
+    pub harmonics: HarmonicSeries,
+    /// The strongest peaks of the spectrum, strongest first
+    pub peaks: Vec<Peak>,
+    /// F1 and F2 of the vowel being sung or spoken
+    pub formants: Option<Formants>,
+    /// How far into its attack, sustain or release the note sounding is
+    pub envelope: Option<NoteEnvelope>,
+    /// Amplitude of each semitone from C1 up, from the constant-q transform
+    pub cqt: Vec<f32>,
+    /// The first of `cqt` that's resolved to a semitone, the window is too
+    /// short for the ones below
+    pub cqt_constant_q_from: usize,
+    /// Energy of each pitch class over all octaves, C first, the strongest
+    /// being 1
+    pub chroma: [f32; 12],
+    /// The chord the chroma looks like, if any
+    pub chord: Option<Chord>,
+    /// The key the last few seconds are most likely in
+    pub key: Option<Key>,
+    /// A note started in this window
+    pub onset: bool,
+    /// Tempo of the last few seconds in beats per minute, once there's a clear one
+    pub bpm: Option<f32>,
+    /// Rate and depth of the vibrato over the last second, if there's one
+    pub vibrato: Option<Vibrato>,
+    /// How long the analysis of this window took
+    pub analysis_time: Duration,
+    /// Windows of this input skipped so far because the analysis was behind
+    pub dropped_windows: usize,
+}
+
+impl FreqData {
+    /// A magnitude from `data` or `mel_data` in dB below a full scale sine
+    pub fn to_dbfs(&self, magnitude: f64) -> f64 {
+        if self.full_scale > 0.0 {
+            linear_to_db((magnitude / self.full_scale as f64) as f32) as f64
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct InputLevel {
+    pub rms_db: f32,
+    pub peak_db: f32,
+    /// Some sample reached full scale
+    pub clipped: bool,
+    /// ITU-R BS.1770 loudness over the last 400 ms
+    pub momentary_lufs: f32,
+    /// ITU-R BS.1770 loudness over the last 3 s
+    pub short_term_lufs: f32,
+}
+
+impl Default for InputLevel {
+    fn default() -> Self {
+        Self {
+            rms_db: f32::NEG_INFINITY,
+            peak_db: f32::NEG_INFINITY,
+            clipped: false,
+            momentary_lufs: f32::NEG_INFINITY,
+            short_term_lufs: f32::NEG_INFINITY,
+        }
+    }
 }
 // type FreqData = Vec<(f64, f64)>;
 
+pub enum AudioMessage {
+    FreqData(Box<FreqData>),
+    /// Something the user should know about the input, like a lost device
+    Status(String),
+    /// Something that went wrong the user should know about, like a recording
+    /// that couldn't start
+    Error(String),
+    /// An error reported by the audio backend while streaming
+    StreamError(String),
+    RecordingStarted(PathBuf),
+    /// Sent once the recording's file is finished
+    RecordingStopped(PathBuf),
+    /// The listener with this source id hears only silence and stopped
+    /// analyzing until something comes in again
+    Idle(usize),
+    /// Where the listener with this source id keeps its recent spectra. Sent
+    /// again whenever the input changes
+    SpectrumHistory(usize, SharedSpectrumHistory),
+    /// The first two channels of the listener with this source id since its
+    /// previous window, as (left, right) pairs before the gain. Only sent for
+    /// inputs with two or more channels.
+    Stereo(usize, Vec<(f32, f32)>),
+}
+
+/// How often to try getting a lost input device back
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Backend-specific stream errors after which the stream gets rebuilt. They're
+/// counted from one check of the listener's loop to the next, every
+/// `RECONNECT_INTERVAL` or sooner when a message from the ui wakes it, and the
+/// count goes back to 0 at each check whether or not it got this far. A few
+/// are usually harmless (alsa likes to complain about timestamps).
+const ERRORS_BEFORE_REBUILD: usize = 3;
+
+/// How long the collector thread waits when no new samples have arrived
+const COLLECT_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Most (left, right) pairs sent for the phase scope per window
+const STEREO_SAMPLES: usize = 4096;
+
+/// Windows that can wait for analysis before new ones are dropped
+const ANALYSIS_QUEUE_SIZE: usize = 4;
+
+/// Window sizes allowed in `AnalysisOptions::window_size`
+pub const WINDOW_SIZES: [usize; 5] = [1024, 2048, 4096, 8192, 16384];
+/// Zero padding factors allowed in `AnalysisOptions::zero_padding`
+pub const ZERO_PADDING_FACTORS: [usize; 3] = [1, 2, 4];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelMode {
+    /// Analyze only this channel
+    Single(usize),
+    /// Analyze every channel separately
+    PerChannel,
+    /// Analyze the average of all channels
+    Downmix,
+}
+
+impl Default for ChannelMode {
+    fn default() -> Self {
+        ChannelMode::Single(0)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ListenerOptions {
+    /// Audio host name, `None` for cpal's default host
+    pub host: Option<String>,
+    /// Device name or index, `None` for the default input device
+    pub device: Option<String>,
+    pub sample_rate: Option<u32>,
+    /// Capture what the computer is playing instead of an input device
+    pub loopback: bool,
+    /// Analyze this wav file instead of capturing live audio
+    pub audio_file: Option<PathBuf>,
+    /// How fast to play back `audio_file`, 1.0 being real time
+    pub speed: f32,
+    pub channel_mode: ChannelMode,
+    /// Software gain applied before analysis, in dB
+    pub gain_db: f32,
+    /// Automatic gain control on top of `gain_db`
+    pub agc: bool,
+    pub noise_gate: NoiseGateOptions,
+    /// Tags the `FreqData` this listener sends, so several can share the ui
+    pub source: usize,
+    /// Play the input back on an output device
+    pub monitor: Option<MonitorOptions>,
+    pub analysis: AnalysisOptions,
+    pub instrument: Instrument,
+}
+
+/// The capture stream along with the stream monitoring it, both stop when dropped
+struct CaptureStream {
+    _input: Stream,
+    _monitor: Option<Stream>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NoiseGateOptions {
+    pub enabled: bool,
+    /// Input below this level (dBFS) is silenced
+    pub threshold_db: f32,
+    /// How long the gate takes to open once the input is loud enough
+    pub attack_ms: f32,
+    /// How long the gate takes to close once the input gets quiet
+    pub release_ms: f32,
+}
+
+/// Settings for how windows are analyzed
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisOptions {
+    /// Number of samples analyzed at once. Longer windows tell closer notes
+    /// apart but follow changes slower
+    pub window_size: usize,
+    /// Cutoff of the high-pass run before the fft, to get rid of dc offset and
+    /// rumble. 0 turns it off
+    pub high_pass_hz: f32,
+    pub window: WindowFunction,
+    pub pitch_detector: PitchDetector,
+    /// How many spectra to keep for the spectrogram, per channel
+    pub history_depth: usize,
+    /// Bands of the mel spectrum
+    pub mel_bands: usize,
+    /// How many seconds of music the key is guessed from
+    pub key_window_secs: f32,
+    /// Mel-frequency cepstral coefficients to compute per window
+    pub mfcc_coefficients: usize,
+    /// Most simultaneous notes to look for, for chords
+    pub max_pitches: usize,
+    /// The full fft analysis, or just a goertzel filter per note (`--goertzel`)
+    pub mode: AnalysisMode,
+    /// Strongest spectral peaks to find per window, labelled on the fft chart
+    pub peak_count: usize,
+    /// Highest frequency of the spectrum sent to the ui, the instrument's when
+    /// `None`
+    pub max_frequency: Option<f32>,
+    /// How many times its length each window is padded to with zeros before
+    /// the fft. More bins for the same window, so a smoother spectrum and
+    /// finer peaks, but no more real resolution
+    pub zero_padding: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self {
+            window_size: 4096,
+            high_pass_hz: 20.0,
+            window: WindowFunction::default(),
+            pitch_detector: PitchDetector::default(),
+            history_depth: 128,
+            mel_bands: 64,
+            key_window_secs: 15.0,
+            mfcc_coefficients: 13,
+            max_pitches: 4,
+            zero_padding: 1,
+            mode: AnalysisMode::default(),
+            peak_count: 5,
+            max_frequency: None,
+        }
+    }
+}
+
+/// How much of the analysis runs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalysisMode {
+    /// The full spectrum and everything worked out from it
+    #[default]
+    Fft,
+    /// Only the energy at the notes the instrument can play, by a goertzel
+    /// filter per note. Enough for the tutor and the note display at a
+    /// fraction of the cpu, for small boards like a Raspberry Pi
+    Goertzel,
+}
+
+/// Tapers each window before the fft. Without it (rectangular) the edges of
+/// the window smear every peak over the neighbouring bins.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowFunction {
+    Rectangular,
+    #[default]
+    Hann,
+    Hamming,
+    /// Lowest leakage of these, for the price of wider peaks
+    BlackmanHarris,
+}
+
+impl WindowFunction {
+    /// The window's coefficients, scaled so a sine's peak keeps the magnitude it
+    /// would have with a rectangular window
+    fn coefficients(self, n: usize) -> Vec<f32> {
+        let coefficients = (0..n)
+            .map(|i| {
+                let x = 2.0 * std::f32::consts::PI * i as f32 / n as f32;
+                match self {
+                    WindowFunction::Rectangular => 1.0,
+                    WindowFunction::Hann => 0.5 - 0.5 * x.cos(),
+                    WindowFunction::Hamming => 0.54 - 0.46 * x.cos(),
+                    WindowFunction::BlackmanHarris => {
+                        0.35875 - 0.48829 * x.cos() + 0.14128 * (2.0 * x).cos()
+                            - 0.01168 * (3.0 * x).cos()
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let coherent_gain = coefficients.iter().sum::<f32>() / n as f32;
+        coefficients.iter().map(|c| c / coherent_gain).collect()
+    }
+}
+
+impl Default for NoiseGateOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: -50.0,
+            attack_ms: 5.0,
+            release_ms: 200.0,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AudioListener {
-    freq_dump_channel: Sender<FreqData>,
+    ui_channel: Sender<AudioMessage>,
     terminal_msg_receiver: Receiver<TerminalMessage>,
+    options: ListenerOptions,
+    /// Set from the stream's error callback when the device goes away
+    device_lost: Arc<AtomicBool>,
+    /// Backend errors since the run loop last checked
+    backend_errors: Arc<AtomicUsize>,
+    controls: Arc<InputControls>,
+}
+
+/// Settings the ui can change while samples are being collected
+#[derive(Debug)]
+struct InputControls {
+    /// Linear gain as `f32` bits
+    gain: AtomicU32,
+    agc: AtomicBool,
+    noise_gate: AtomicBool,
+    /// Channels and sample rate of the input currently being collected
+    input_format: Mutex<(u16, u32)>,
+    /// Where the raw input goes while recording
+    recorder: Mutex<Option<Sender<Vec<f32>>>>,
+    instrument: Mutex<Instrument>,
+    /// Overrides the instrument's `max_display_hz`
+    max_frequency: Mutex<Option<f32>>,
+    /// Work out the mfcc of each window
+    mfcc: AtomicBool,
 }
 
 impl AudioListener {
     pub fn new(
-        freq_dump_channel: Sender<FreqData>,
+        ui_channel: Sender<AudioMessage>,
         terminal_msg_receiver: Receiver<TerminalMessage>,
+        options: ListenerOptions,
     ) -> Self {
         Self {
-            freq_dump_channel,
+            ui_channel,
             terminal_msg_receiver,
+            device_lost: Arc::new(AtomicBool::new(false)),
+            backend_errors: Arc::new(AtomicUsize::new(0)),
+            controls: Arc::new(InputControls {
+                gain: AtomicU32::new(db_to_linear(options.gain_db).to_bits()),
+                agc: AtomicBool::new(options.agc),
+                noise_gate: AtomicBool::new(options.noise_gate.enabled),
+                input_format: Mutex::new((0, 0)),
+                recorder: Mutex::new(None),
+                instrument: Mutex::new(options.instrument),
+                max_frequency: Mutex::new(options.analysis.max_frequency),
+                // nothing shows them until the ui says so
+                mfcc: AtomicBool::new(false),
+            }),
+            options,
         }
     }
 
     #[tracing::instrument]
-    pub fn run(&self) -> Result<()> {
-        let host = cpal::default_host();
-        let input_device = host
-            .default_input_device()
-            .expect("No default input device found");
-        let mut supported_input_configs_range = input_device
-            .supported_input_configs()
-            .expect("Error querying supported configs");
-        let supported_input_config = supported_input_configs_range
-            .next()
-            .expect("No supported config")
-            .with_max_sample_rate();
-        let config = supported_input_config.config();
-        let sample_rate = config.sample_rate.0;
-        let channels = config.channels as usize;
-        let rms_window_size = 4096;
-        let samples: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(vec![]));
-        let freq_dump_channel = self.freq_dump_channel.clone();
-        let epsilon = 1e-10;
-        let stream = input_device
-            .build_input_stream(
-                &config,
-                move |data: &[f32], _| {
-                    let mut samples = samples.lock().unwrap();
-                    for sample in data.chunks_exact(channels) {
-                        let left_sample = sample[0];
-                        samples.push(left_sample);
-                        if samples.len() >= rms_window_size {
-                            let n = samples.len();
-                            let mut planner = FftPlanner::new();
-                            let fft = planner.plan_fft_forward(n);
-                            let mut buffer = samples
-                                .iter()
-                                .map(|sample| Complex {
-                                    re: *sample,
-                                    im: 0.0,
-                                })
-                                .collect::<Vec<_>>();
-                            fft.process(&mut buffer);
-
-                            let max_k = n / 2 + 1;
-                            let mut downsampled_spectra = vec![];
-                            let mut smallest_len = usize::MAX;
-                            for i in 2..5 {
-                                let downsampled_spectrum = (0..max_k)
-                                    .step_by(i)
-                                    .map(|j| buffer[j].norm().max(epsilon))
-                                    .collect::<Vec<_>>();
-                                let spec_len = downsampled_spectrum.len();
-                                downsampled_spectra.push(downsampled_spectrum);
-                                if spec_len < smallest_len {
-                                    smallest_len = spec_len;
-                                }
-                            }
-                            let mut log_product_spectrum = buffer[0..smallest_len]
-                                .iter()
-                                .map(|b| {
-                                    let m = b.norm();
-                                    20.0 * m.max(epsilon).log10()
-                                })
-                                .collect::<Vec<_>>();
-                            let mut max_product_spectrum_i = 0;
-                            let mut max_product_spectrum = f32::NEG_INFINITY;
-                            for i in 0..smallest_len {
-                                let mut log_psi = log_product_spectrum[i];
-                                for spectrum in downsampled_spectra.iter() {
-                                    log_psi += spectrum[i];
-                                }
-                                log_product_spectrum[i] = log_psi;
-                                if log_psi > max_product_spectrum {
-                                    max_product_spectrum_i = i;
-                                    max_product_spectrum = log_psi;
-                                }
-                            }
-                            // quadratic interpolation gang
-                            let multiplier_index = if max_product_spectrum_i != 0 {
-                                let yc = buffer[max_product_spectrum_i].norm();
-                                let yl = buffer[max_product_spectrum_i - 1].norm();
-                                let yr = buffer[max_product_spectrum_i + 1].norm();
-                                let p = 0.5 * (yl - yr) / (yl - 2.0 * yc + yr);
-
-                                max_product_spectrum_i as f32 + p
-                            } else {
-                                max_product_spectrum_i as f32
-                            };
-                            let fundamental_frequency =
-                                multiplier_index * sample_rate as f32 / n as f32;
-
-                            let mut max_magnitude_freq = 0.0;
-                            let mut max_magnitude = buffer[0].norm();
-                            let mut freq_data = vec![];
-                            for (i, raw_magnitude) in buffer.iter().enumerate().take(max_k) {
-                                let freq = i as f32 * sample_rate as f32 / n as f32;
-                                let magnitude = raw_magnitude.norm();
-                                if freq <= 1500.0 {
-                                    freq_data.push((freq as f64, magnitude as f64));
-                                }
-                                if magnitude > max_magnitude {
-                                    max_magnitude = magnitude;
-                                    max_magnitude_freq = freq;
-                                }
-                            }
-                            freq_dump_channel
-                                .send(FreqData {
-                                    data: freq_data,
-                                    max_magnitude,
-                                    peak_frequency: max_magnitude_freq,
-                                    fundamental_frequency,
-                                    samples_n: n,
-                                    sample_rate,
-                                    time_domain_samples: samples.clone(),
-                                })
-                                .unwrap();
-                            // println!(
-                            //     "Peak frequency is {max_magnitude_freq} with magnitude {max_magnitude}"
-                            // );
-
-                            samples.clear();
-                        }
-                    }
-                },
-                move |err| {
-                    println!("Error: {err}");
-                },
-                None,
-            )
-            .expect("uhhhh error in stream input building");
-        stream.play()?;
-        // println!("ok. started playing");
+    pub fn run(&mut self) -> Result<()> {
+        if let Some(audio_file) = self.options.audio_file.clone() {
+            return self.run_file(&audio_file);
+        }
+        let host = select_host(self.options.host.as_deref())?;
+        let mut stream = Some(self.start_stream(&host)?);
+        let mut last_reconnect_attempt = Instant::now();
         loop {
-            match self.terminal_msg_receiver.try_recv() {
+            match self.terminal_msg_receiver.recv_timeout(RECONNECT_INTERVAL) {
                 Ok(TerminalMessage::Quit) => {
-                    // println!("Quit signal recieved");
                     break;
                 }
-                Err(mpsc::TryRecvError::Empty) => {
-                    // ignore
+                Ok(TerminalMessage::SwitchDevice(name)) => {
+                    let previous_options = self.options.clone();
+                    self.options.device = Some(name);
+                    self.options.loopback = false;
+                    self.restart_stream(&host, &mut stream, previous_options);
+                }
+                Ok(TerminalMessage::SetLoopback(loopback)) => {
+                    let previous_options = self.options.clone();
+                    self.options.loopback = loopback;
+                    self.restart_stream(&host, &mut stream, previous_options);
+                }
+                Ok(TerminalMessage::SetGain(gain_db)) => {
+                    self.set_gain(gain_db);
+                }
+                Ok(TerminalMessage::SetAgc(agc)) => {
+                    self.set_agc(agc);
+                }
+                Ok(TerminalMessage::SetNoiseGate(enabled)) => {
+                    self.set_noise_gate(enabled);
+                }
+                Ok(TerminalMessage::SetInstrument(instrument)) => {
+                    self.set_instrument(instrument);
                 }
-                Err(mpsc::TryRecvError::Disconnected) => {
-                    // println!("UI thread disconnected.. kinda weird tbh");
+                Ok(TerminalMessage::SetMaxFrequency(max_frequency)) => {
+                    self.set_max_frequency(max_frequency);
+                }
+                Ok(TerminalMessage::SetMfcc(mfcc)) => {
+                    self.controls.mfcc.store(mfcc, Ordering::Relaxed);
+                }
+                Ok(TerminalMessage::SetAnalysis(analysis)) => {
+                    let previous_options = self.options.clone();
+                    self.options.analysis = *analysis;
+                    self.restart_stream(&host, &mut stream, previous_options);
+                }
+                Ok(TerminalMessage::SetNoiseGateThreshold(threshold_db)) => {
+                    let previous_options = self.options.clone();
+                    self.set_noise_gate_threshold(threshold_db);
+                    self.restart_stream(&host, &mut stream, previous_options);
+                }
+                Ok(TerminalMessage::StartRecording(path)) => {
+                    self.start_recording(path);
+                }
+                Ok(TerminalMessage::StopRecording) => {
+                    self.stop_recording();
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    // ui thread is gone, nobody's listening anymore
                     break;
                 }
             }
+            if self.device_lost.swap(false, Ordering::Relaxed) {
+                stream = None;
+                self.send_status("Input device disconnected, trying to reconnect...");
+            }
+            if self.backend_errors.swap(0, Ordering::Relaxed) >= ERRORS_BEFORE_REBUILD
+                && stream.is_some()
+            {
+                // the old stream has to go first, some backends won't open a busy device
+                stream = None;
+                match self.start_stream(&host) {
+                    Ok(new_stream) => {
+                        stream = Some(new_stream);
+                        self.send_status("Input stream rebuilt after backend errors");
+                    }
+                    Err(err) => {
+                        tracing::error!("couldn't rebuild the input stream: {err}");
+                        self.send_error("Input stream failed, trying to reconnect...");
+                    }
+                }
+            }
+            if stream.is_none() && last_reconnect_attempt.elapsed() >= RECONNECT_INTERVAL {
+                last_reconnect_attempt = Instant::now();
+                stream = self.reconnect(&host);
+            }
         }
-        // println!("Sample rate: {sample_rate}");
-        // println!("Channels: {channels}");
-        // println!("end");
+        drop(stream);
+        self.stop_recording();
         Ok(())
     }
+
+    /// Tries to get the stream back on the same device, falling back to whatever
+    /// the default device is now. Only getting it back is reported.
+    fn reconnect(&mut self, host: &Host) -> Option<CaptureStream> {
+        if let Ok(stream) = self.start_stream(host) {
+            self.send_status("Input device reconnected");
+            return Some(stream);
+        }
+        if self.options.device.is_some() && !self.options.loopback {
+            let previous_device = self.options.device.take();
+            if let Ok(stream) = self.start_stream(host) {
+                let name = find_input_device(host, None)
+                    .and_then(|d| Ok(d.name()?))
+                    .unwrap_or_else(|_| "the default device".to_string());
+                self.send_status(&format!("Input device lost, switched to {name}"));
+                return Some(stream);
+            }
+            self.options.device = previous_device;
+        }
+        // the user heard about the loss already, once is enough
+        tracing::debug!("input device still unavailable");
+        None
+    }
+
+    fn set_gain(&mut self, gain_db: f32) {
+        self.options.gain_db = gain_db;
+        self.controls
+            .gain
+            .store(db_to_linear(gain_db).to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_agc(&mut self, agc: bool) {
+        self.options.agc = agc;
+        self.controls.agc.store(agc, Ordering::Relaxed);
+    }
+
+    fn set_noise_gate(&mut self, enabled: bool) {
+        self.options.noise_gate.enabled = enabled;
+        self.controls.noise_gate.store(enabled, Ordering::Relaxed);
+    }
+
+    fn set_instrument(&mut self, instrument: Instrument) {
+        self.options.instrument = instrument;
+        *self.controls.instrument.lock().unwrap() = instrument;
+    }
+
+    fn set_max_frequency(&mut self, max_frequency: f32) {
+        self.options.analysis.max_frequency = Some(max_frequency);
+        *self.controls.max_frequency.lock().unwrap() = Some(max_frequency);
+    }
+
+    fn set_noise_gate_threshold(&mut self, threshold_db: f32) {
+        self.options.noise_gate.threshold_db = threshold_db;
+    }
+
+    /// A collector for `channels` channels of the wav file at `sample_rate`,
+    /// with the current options
+    fn file_collector(&self, channels: usize, sample_rate: u32) -> Result<WindowCollector> {
+        WindowCollector::new(
+            &self.options,
+            channels,
+            sample_rate,
+            self.controls.clone(),
+            self.ui_channel.clone(),
+        )
+    }
+
+    fn start_recording(&mut self, path: PathBuf) {
+        self.stop_recording();
+        let (channels, sample_rate) = *self.controls.input_format.lock().unwrap();
+        match start_recording(path.clone(), channels, sample_rate, self.ui_channel.clone()) {
+            Ok(recorder) => {
+                *self.controls.recorder.lock().unwrap() = Some(recorder);
+                let _ = self.ui_channel.send(AudioMessage::RecordingStarted(path));
+            }
+            Err(err) => {
+                tracing::error!("couldn't start recording to {}: {err}", path.display());
+                self.send_error(&format!("Couldn't record to {}: {err}", path.display()));
+            }
+        }
+    }
+
+    /// Dropping the sender lets the recorder thread finish the file.
+    fn stop_recording(&mut self) {
+        self.controls.recorder.lock().unwrap().take();
+    }
+
+    fn send_status(&self, status: &str) {
+        tracing::info!("{status}");
+        let _ = self
+            .ui_channel
+            .send(AudioMessage::Status(status.to_string()));
+    }
+
+    /// Tells the user, the error is logged already
+    fn send_error(&self, error: &str) {
+        let _ = self.ui_channel.send(AudioMessage::Error(error.to_string()));
+    }
+
+    /// Feeds the wav file through the analysis window by window, paced so that it
+    /// plays back at `speed` times real time. The file is read a window at a
+    /// time, so a long recording doesn't have to fit in memory.
+    fn run_file(&mut self, path: &Path) -> Result<()> {
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        let channels = spec.channels as usize;
+        let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+        let mut samples = match spec.sample_format {
+            hound::SampleFormat::Float => Either::Left(reader.samples::<f32>()),
+            hound::SampleFormat::Int => Either::Right(
+                reader
+                    .samples::<i32>()
+                    .map(move |s| s.map(|s| s as f32 * scale)),
+            ),
+        };
+        let mut collector = self.file_collector(channels, spec.sample_rate)?;
+        // the file is fed in chunks of the first window size, changing it later
+        // only changes how the collector splits them up
+        let chunk_size = self.options.analysis.window_size;
+        let window_duration = Duration::from_secs_f32(
+            chunk_size as f32 / spec.sample_rate as f32 / self.options.speed,
+        );
+        let mut window = Vec::with_capacity(chunk_size * channels);
+        loop {
+            window.clear();
+            for sample in samples.by_ref().take(chunk_size * channels) {
+                window.push(sample?);
+            }
+            if window.is_empty() {
+                break;
+            }
+            collector.push(&window);
+            // the ui is listened to while the window plays out
+            let next_window = Instant::now() + window_duration;
+            loop {
+                let timeout = next_window.saturating_duration_since(Instant::now());
+                match self.terminal_msg_receiver.recv_timeout(timeout) {
+                    Ok(TerminalMessage::Quit) | Err(RecvTimeoutError::Disconnected) => {
+                        return Ok(());
+                    }
+                    Ok(TerminalMessage::SetGain(gain_db)) => self.set_gain(gain_db),
+                    Ok(TerminalMessage::SetAgc(agc)) => self.set_agc(agc),
+                    Ok(TerminalMessage::SetNoiseGate(enabled)) => self.set_noise_gate(enabled),
+                    Ok(TerminalMessage::SetInstrument(instrument)) => {
+                        self.set_instrument(instrument)
+                    }
+                    Ok(TerminalMessage::SetMaxFrequency(max_frequency)) => {
+                        self.set_max_frequency(max_frequency)
+                    }
+                    Ok(TerminalMessage::SetMfcc(mfcc)) => {
+                        self.controls.mfcc.store(mfcc, Ordering::Relaxed)
+                    }
+                    Ok(TerminalMessage::SetAnalysis(analysis)) => {
+                        self.options.analysis = *analysis;
+                        collector = self.file_collector(channels, spec.sample_rate)?;
+                    }
+                    Ok(TerminalMessage::SetNoiseGateThreshold(threshold_db)) => {
+                        self.set_noise_gate_threshold(threshold_db);
+                        collector = self.file_collector(channels, spec.sample_rate)?;
+                    }
+                    Ok(TerminalMessage::StartRecording(path)) => self.start_recording(path),
+                    Ok(TerminalMessage::StopRecording) => self.stop_recording(),
+                    // there's no device to switch when playing a file
+                    Ok(_) => {}
+                    Err(RecvTimeoutError::Timeout) => break,
+                }
+            }
+        }
+        // keep the last frame on screen until the ui quits
+        while let Ok(message) = self.terminal_msg_receiver.recv() {
+            if let TerminalMessage::Quit = message {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces `stream` with one built from the current options, going back to
+    /// `previous_options` if that fails.
+    fn restart_stream(
+        &mut self,
+        host: &Host,
+        stream: &mut Option<CaptureStream>,
+        previous_options: ListenerOptions,
+    ) {
+        match self.start_stream(host) {
+            Ok(new_stream) => *stream = Some(new_stream),
+            Err(err) => {
+                tracing::error!("couldn't switch input: {err}");
+                self.send_error(&format!("Couldn't switch input: {err}"));
+                self.options = previous_options;
+            }
+        }
+    }
+
+    fn start_stream(&self, host: &Host) -> Result<CaptureStream> {
+        let input_device = find_capture_device(host, &self.options)?;
+        let supported_input_config = capture_config(&input_device, &self.options)?;
+        tracing::info!("using input config {supported_input_config:?}");
+        let sample_format = supported_input_config.sample_format();
+        let config = supported_input_config.config();
+        // a broken monitor shouldn't keep you from using the tuner
+        let (monitor, monitor_stream) = match self.options.monitor.as_ref().map(|monitor| {
+            start_monitor(
+                host,
+                monitor,
+                config.channels as usize,
+                config.sample_rate.0,
+            )
+        }) {
+            Some(Ok((producer, stream))) => (Some(producer), Some(stream)),
+            Some(Err(err)) => {
+                tracing::error!("couldn't start monitoring: {err}");
+                self.send_error(&format!("Couldn't start monitoring: {err}"));
+                (None, None)
+            }
+            None => (None, None),
+        };
+        let stream = match sample_format {
+            SampleFormat::I8 => self.build_input_stream::<i8>(&input_device, &config, monitor),
+            SampleFormat::I16 => self.build_input_stream::<i16>(&input_device, &config, monitor),
+            SampleFormat::I32 => self.build_input_stream::<i32>(&input_device, &config, monitor),
+            SampleFormat::I64 => self.build_input_stream::<i64>(&input_device, &config, monitor),
+            SampleFormat::U8 => self.build_input_stream::<u8>(&input_device, &config, monitor),
+            SampleFormat::U16 => self.build_input_stream::<u16>(&input_device, &config, monitor),
+            SampleFormat::U32 => self.build_input_stream::<u32>(&input_device, &config, monitor),
+            SampleFormat::U64 => self.build_input_stream::<u64>(&input_device, &config, monitor),
+            SampleFormat::F32 => self.build_input_stream::<f32>(&input_device, &config, monitor),
+            SampleFormat::F64 => self.build_input_stream::<f64>(&input_device, &config, monitor),
+            sample_format => Err(eyre!("Unsupported sample format {sample_format}")),
+        }?;
+        stream.play()?;
+        Ok(CaptureStream {
+            _input: stream,
+            _monitor: monitor_stream,
+        })
+    }
+
+    /// Builds the input stream for devices delivering samples as `T`, converting
+    /// them to `f32` before analysis. With `monitor` the samples are also passed
+    /// on for playback.
+    fn build_input_stream<T>(
+        &self,
+        device: &Device,
+        config: &StreamConfig,
+        mut monitor: Option<HeapProd<f32>>,
+    ) -> Result<Stream>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let channels = config.channels as usize;
+        let mut collector = WindowCollector::new(
+            &self.options,
+            channels,
+            config.sample_rate.0,
+            self.controls.clone(),
+            self.ui_channel.clone(),
+        )?;
+        // the callback only copies into the ring buffer, everything else happens
+        // on the collector thread so the callback never blocks or allocates
+        let capacity = config.sample_rate.0 as usize * channels;
+        let (mut producer, mut consumer) = HeapRb::<f32>::new(capacity).split();
+        std::thread::spawn(move || {
+            let mut samples = vec![0.0; capacity];
+            // the producer is dropped along with the stream
+            while consumer.write_is_held() {
+                let n = consumer.occupied_len() / channels * channels;
+                if n == 0 {
+                    std::thread::sleep(if collector.idle {
+                        IDLE_COLLECT_INTERVAL
+                    } else {
+                        COLLECT_INTERVAL
+                    });
+                    continue;
+                }
+                consumer.pop_slice(&mut samples[..n]);
+                collector.push(&samples[..n]);
+            }
+        });
+        let stream = device.build_input_stream(
+            config,
+            move |data: &[T], _| {
+                // whole frames only; if the collector fell behind, the rest is dropped
+                let frames = producer.vacant_len() / channels;
+                producer.push_iter(
+                    data.iter()
+                        .take(frames * channels)
+                        .map(|s| f32::from_sample(*s)),
+                );
+                if let Some(monitor) = monitor.as_mut() {
+                    let frames = monitor.vacant_len() / channels;
+                    monitor.push_iter(
+                        data.iter()
+                            .take(frames * channels)
+                            .map(|s| f32::from_sample(*s)),
+                    );
+                }
+            },
+            {
+                let device_lost = self.device_lost.clone();
+                let backend_errors = self.backend_errors.clone();
+                let ui_channel = self.ui_channel.clone();
+                move |err| {
+                    match err {
+                        StreamError::DeviceNotAvailable => {
+                            device_lost.store(true, Ordering::Relaxed);
+                        }
+                        StreamError::BackendSpecific { .. } => {
+                            backend_errors.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    tracing::error!("input stream error: {err}");
+                    let _ = ui_channel.send(AudioMessage::StreamError(err.to_string()));
+                }
+            },
+            None,
+        )?;
+        Ok(stream)
+    }
+}
+
+/// The config the device would be captured with given the listener options
+pub fn capture_config(device: &Device, options: &ListenerOptions) -> Result<SupportedStreamConfig> {
+    let supported_configs = if options.loopback && cfg!(target_os = "windows") {
+        // wasapi does loopback by building an input stream on an output device
+        device.supported_output_configs()?.collect::<Vec<_>>()
+    } else {
+        device.supported_input_configs()?.collect::<Vec<_>>()
+    };
+    choose_input_config(supported_configs, options.sample_rate)
+}
+
+/// Fails if the channel mode picks a channel the input doesn't have
+pub fn check_channel(mode: ChannelMode, channels: usize) -> Result<()> {
+    match mode {
+        ChannelMode::Single(channel) if channel >= channels => Err(eyre!(
+            "There's no channel {}, the input only has {channels}",
+            channel + 1
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Picks the supported config closest to what we want for analysis: the preferred
+/// sample rate (or 48/44.1 kHz), mono or stereo, and preferably float samples.
+/// Backends tend to list their widest configs first, which are a waste of cpu here.
+fn choose_input_config(
+    supported_configs: Vec<SupportedStreamConfigRange>,
+    preferred_sample_rate: Option<u32>,
+) -> Result<SupportedStreamConfig> {
+    let preferred_rates = preferred_sample_rate.map_or(vec![48000, 44100], |r| vec![r]);
+    let config = supported_configs
+        .into_iter()
+        .map(|range| {
+            let (min, max) = (range.min_sample_rate().0, range.max_sample_rate().0);
+            let rate = preferred_rates
+                .iter()
+                .find(|r| (min..=max).contains(r))
+                .copied()
+                .unwrap_or_else(|| preferred_rates[0].clamp(min, max));
+            range.with_sample_rate(SampleRate(rate))
+        })
+        .min_by_key(|config| {
+            let rate_penalty = config.sample_rate().0.abs_diff(preferred_rates[0]) / 1000;
+            let channels_penalty = config.channels().saturating_sub(2) as u32 * 10;
+            let format_penalty = match config.sample_format() {
+                SampleFormat::F32 => 0,
+                SampleFormat::I16 | SampleFormat::I32 => 1,
+                _ => 2,
+            };
+            rate_penalty + channels_penalty + format_penalty
+        })
+        .ok_or_else(|| eyre!("No supported config"))?;
+    if let Some(preferred) = preferred_sample_rate
+        && config.sample_rate().0 != preferred
+    {
+        tracing::warn!(
+            "device doesn't support {preferred} Hz, capturing at {} Hz and resampling",
+            config.sample_rate().0
+        );
+    }
+    Ok(config)
 }
 
-fn note_from_midi_note_number(midi_note_number: usize) -> String {
-    let i = midi_note_number % 12;
-    NOTES[i].to_string()
+/// Splits interleaved samples into per-channel analysis windows and hands each
+/// window to the analysis thread once it's full. Samples are resampled on the way
+/// if the input rate isn't the one asked for.
+struct WindowCollector {
+    mode: ChannelMode,
+    source: usize,
+    channels: usize,
+    sample_rate: u32,
+    window_size: usize,
+    windows: Vec<Vec<f32>>,
+    resampler: Option<FftFixedIn<f32>>,
+    /// Samples waiting for the resampler, per analyzed channel
+    resampler_input: Vec<Vec<f32>>,
+    controls: Arc<InputControls>,
+    agc: Agc,
+    noise_gate: NoiseGate,
+    /// Full windows go to the analysis thread
+    analysis: SyncSender<Window>,
+    meter: LevelMeter,
+    loudness: LoudnessMeter,
+    level: InputLevel,
+    /// Windows in a row that were all silence
+    silent_windows: usize,
+    /// Silent for long enough that only the wake check runs
+    idle: bool,
+    /// One per analyzed channel, empty when the high-pass is off
+    high_pass: Vec<Biquad>,
+    /// Left and right samples since the last window, empty for mono inputs
+    stereo: Vec<(f32, f32)>,
+    /// Windows skipped so far because the analysis was behind
+    dropped_windows: usize,
+    ui_channel: Sender<AudioMessage>,
 }
-pub fn get_note_from_frequency(freq: f32) -> Option<String> {
-    let midi_note_number = (12.0 * (freq / 440.0).log2() + 69.0).round();
-    midi_note_number
-        .is_finite()
-        .then(|| note_from_midi_note_number(midi_note_number as usize))
+
+/// A window of samples waiting for analysis, along with where it came from
+struct Window {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channel: usize,
+    channels: usize,
+    source: usize,
+    gated: bool,
+    level: InputLevel,
+    /// The instrument's preset, with the spectrum's top changed if it was
+    preset: InstrumentPreset,
+    /// Windows the collector skipped before this one
+    dropped_windows: usize,
+    /// Work out the mfcc of this window
+    mfcc: bool,
+}
+
+impl WindowCollector {
+    fn new(
+        options: &ListenerOptions,
+        channels: usize,
+        input_sample_rate: u32,
+        controls: Arc<InputControls>,
+        ui_channel: Sender<AudioMessage>,
+    ) -> Result<Self> {
+        let mode = options.channel_mode;
+        check_channel(mode, channels)?;
+        let analyzed_channels = match mode {
+            ChannelMode::Single(_) | ChannelMode::Downmix => 1,
+            ChannelMode::PerChannel => channels,
+        };
+        {
+            let mut input_format = controls.input_format.lock().unwrap();
+            if controls.recorder.lock().unwrap().is_some()
+                && *input_format != (channels as u16, input_sample_rate)
+            {
+                // the recording's wav header can't change anymore
+                controls.recorder.lock().unwrap().take();
+            }
+            *input_format = (channels as u16, input_sample_rate);
+        }
+        let sample_rate = options.sample_rate.unwrap_or(input_sample_rate);
+        let resampler = if sample_rate != input_sample_rate {
+            tracing::info!("resampling {input_sample_rate} Hz input to {sample_rate} Hz");
+            Some(FftFixedIn::new(
+                input_sample_rate as usize,
+                sample_rate as usize,
+                1024,
+                2,
+                analyzed_channels,
+            )?)
+        } else {
+            None
+        };
+        // the fft is the expensive part, so it gets its own thread and the
+        // collector can keep up with the input
+        let (analysis, windows) = mpsc::sync_channel::<Window>(ANALYSIS_QUEUE_SIZE);
+        let analysis_ui_channel = ui_channel.clone();
+        let analysis_options = options.analysis.clone();
+        let history = SpectrumHistory::shared(options.analysis.history_depth, analyzed_channels);
+        let _ = ui_channel.send(AudioMessage::SpectrumHistory(
+            options.source,
+            history.clone(),
+        ));
+        std::thread::spawn(move || {
+            let mut analyzer = Analyzer::new(analysis_options.window_size, &analysis_options);
+            let mut onsets: Vec<OnsetDetector> = vec![];
+            let mut tempos: Vec<TempoEstimator> = vec![];
+            let mut vibratos: Vec<VibratoTracker> = vec![];
+            let mut decimators: Vec<Decimator> = vec![];
+            let mut envelopes: Vec<EnvelopeTracker> = vec![];
+            let mut mfcc = MfccExtractor::new(analysis_options.mfcc_coefficients);
+            for window in windows {
+                let started = Instant::now();
+                let preset = window.preset;
+                let mut freq_data = if preset.decimation > 1 {
+                    if decimators.len() <= window.channel {
+                        decimators.resize_with(window.channel + 1, Decimator::default);
+                    }
+                    let samples = decimators[window.channel].add(
+                        &window.samples,
+                        window.sample_rate,
+                        preset.decimation,
+                    );
+                    analyzer.analyze(
+                        &samples,
+                        window.sample_rate / preset.decimation as u32,
+                        &preset,
+                    )
+                } else {
+                    analyzer.analyze(&window.samples, window.sample_rate, &preset)
+                };
+                freq_data.channel = window.channel;
+                freq_data.channels = window.channels;
+                freq_data.source = window.source;
+                freq_data.gated = window.gated;
+                freq_data.level = window.level;
+                freq_data.dropped_windows = window.dropped_windows;
+                // the goertzel mode has no spectrum to keep
+                if analysis_options.mode == AnalysisMode::Fft {
+                    history.lock().unwrap().push(
+                        window.channel,
+                        freq_data.sample_rate as f32 / freq_data.samples_n as f32,
+                        analyzer.magnitudes(),
+                    );
+                    // only the mfcc view shows them
+                    if window.mfcc {
+                        freq_data.mfcc = mfcc.extract(
+                            |bin| analyzer.bin_magnitudes[bin],
+                            freq_data.sample_rate,
+                            freq_data.samples_n,
+                        );
+                    }
+                }
+                if onsets.len() <= window.channel {
+                    onsets.resize_with(window.channel + 1, OnsetDetector::default);
+                }
+                freq_data.onset = onsets[window.channel].detect(analyzer.magnitudes());
+                freq_data.features.flux = onsets[window.channel].flux();
+                if tempos.len() <= window.channel {
+                    tempos.resize_with(window.channel + 1, TempoEstimator::default);
+                }
+                freq_data.bpm = tempos[window.channel].add(&window.samples, window.sample_rate);
+                if envelopes.len() <= window.channel {
+                    envelopes.resize_with(window.channel + 1, EnvelopeTracker::default);
+                }
+                freq_data.envelope = envelopes[window.channel].add(
+                    &window.samples,
+                    window.sample_rate,
+                    freq_data.onset,
+                );
+                // following the pitch every few ms costs more than the goertzel
+                // mode saves
+                if analysis_options.mode == AnalysisMode::Fft {
+                    if vibratos.len() <= window.channel {
+                        vibratos.resize_with(window.channel + 1, VibratoTracker::default);
+                    }
+                    freq_data.vibrato = vibratos[window.channel].add(
+                        &window.samples,
+                        window.sample_rate,
+                        preset.pitch_range,
+                    );
+                }
+                freq_data.analysis_time = started.elapsed();
+                // the ui may have quit already, nothing to do about it
+                let _ = analysis_ui_channel.send(AudioMessage::FreqData(Box::new(freq_data)));
+            }
+        });
+        Ok(Self {
+            mode,
+            source: options.source,
+            channels,
+            sample_rate,
+            window_size: options.analysis.window_size,
+            windows: vec![Vec::with_capacity(options.analysis.window_size); analyzed_channels],
+            resampler,
+            resampler_input: vec![vec![]; analyzed_channels],
+            controls,
+            agc: Agc::new(input_sample_rate, analyzed_channels),
+            noise_gate: NoiseGate::new(&options.noise_gate, input_sample_rate, analyzed_channels),
+            analysis,
+            silent_windows: 0,
+            idle: false,
+            high_pass: if options.analysis.high_pass_hz > 0.0 {
+                vec![
+                    Biquad::high_pass(options.analysis.high_pass_hz as f64, sample_rate as f64);
+                    analyzed_channels
+                ]
+            } else {
+                vec![]
+            },
+            stereo: vec![],
+            dropped_windows: 0,
+            ui_channel,
+            meter: LevelMeter::default(),
+            loudness: LoudnessMeter::new(input_sample_rate, channels),
+            level: InputLevel::default(),
+        })
+    }
+
+    fn push(&mut self, data: &[f32]) {
+        if let Some(recorder) = self.controls.recorder.lock().unwrap().as_ref() {
+            let _ = recorder.send(data.to_vec());
+        }
+        self.meter.add(data);
+        self.loudness.add(data);
+        let gain = f32::from_bits(self.controls.gain.load(Ordering::Relaxed));
+        if self.idle {
+            // only a cheap loudness check until something worth analyzing comes in
+            let mut wake_level = db_to_linear(WAKE_THRESHOLD_DB);
+            if self.controls.noise_gate.load(Ordering::Relaxed) {
+                wake_level = wake_level.max(self.noise_gate.threshold);
+            }
+            let mean_square = data.iter().map(|s| s * s).sum::<f32>() / data.len().max(1) as f32;
+            if mean_square.sqrt() * gain < wake_level {
+                return;
+            }
+            self.idle = false;
+            self.silent_windows = 0;
+        }
+        if self.channels >= 2 {
+            self.stereo.extend(
+                data.chunks_exact(self.channels)
+                    .map(|frame| (frame[0], frame[1])),
+            );
+            let excess = self.stereo.len().saturating_sub(STEREO_SAMPLES);
+            self.stereo.drain(..excess);
+        }
+        let mut noise_gate = self
+            .controls
+            .noise_gate
+            .load(Ordering::Relaxed)
+            .then_some(&mut self.noise_gate);
+        let mut agc = self
+            .controls
+            .agc
+            .load(Ordering::Relaxed)
+            .then_some(&mut self.agc);
+        let mut level = |sample: f32| {
+            let mut sample = sample * gain;
+            if let Some(noise_gate) = noise_gate.as_mut() {
+                sample = noise_gate.process(sample);
+            }
+            if let Some(agc) = agc.as_mut() {
+                sample = agc.process(sample);
+            }
+            sample
+        };
+        let collected = if self.resampler.is_some() {
+            &mut self.resampler_input
+        } else {
+            &mut self.windows
+        };
+        for frame in data.chunks_exact(self.channels) {
+            match self.mode {
+                ChannelMode::Single(channel) => {
+                    collected[0].push(level(frame[channel]));
+                }
+                ChannelMode::PerChannel => {
+                    for (window, sample) in collected.iter_mut().zip(frame) {
+                        window.push(level(*sample));
+                    }
+                }
+                ChannelMode::Downmix => {
+                    let sum = frame.iter().sum::<f32>();
+                    collected[0].push(level(sum / self.channels as f32));
+                }
+            }
+        }
+        if let Some(resampler) = self.resampler.as_mut() {
+            while self.resampler_input[0].len() >= resampler.input_frames_next() {
+                let frames = resampler.input_frames_next();
+                match resampler.process(&self.resampler_input, None) {
+                    Ok(resampled) => {
+                        for (window, samples) in self.windows.iter_mut().zip(resampled) {
+                            window.extend(samples);
+                        }
+                    }
+                    Err(err) => tracing::error!("resampling failed: {err}"),
+                }
+                for input in self.resampler_input.iter_mut() {
+                    input.drain(..frames);
+                }
+            }
+        }
+        while self.windows[0].len() >= self.window_size {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        // several windows can fill up from one block, they all get its level
+        if let Some(level) = self.meter.take() {
+            self.level = InputLevel {
+                momentary_lufs: self.loudness.momentary(),
+                short_term_lufs: self.loudness.short_term(),
+                ..level
+            };
+        }
+        let silent = self.windows.iter().all(|window| {
+            let mean_square = window[..self.window_size]
+                .iter()
+                .map(|s| s * s)
+                .sum::<f32>()
+                / self.window_size as f32;
+            10.0 * mean_square.log10() < SILENCE_THRESHOLD_DB
+        });
+        self.silent_windows = if silent { self.silent_windows + 1 } else { 0 };
+        let hold_windows = (SILENCE_HOLD.as_secs_f32() * self.sample_rate as f32
+            / self.window_size as f32) as usize;
+        if self.silent_windows > hold_windows {
+            // nothing to see, stop processing until there's sound again
+            let _ = self.ui_channel.send(AudioMessage::Idle(self.source));
+            self.idle = true;
+            for window in self
+                .windows
+                .iter_mut()
+                .chain(self.resampler_input.iter_mut())
+            {
+                window.clear();
+            }
+            return;
+        }
+        if !self.stereo.is_empty() {
+            let stereo = std::mem::take(&mut self.stereo);
+            let _ = self
+                .ui_channel
+                .send(AudioMessage::Stereo(self.source, stereo));
+        }
+        for (i, window) in self.windows.iter_mut().enumerate() {
+            let mut samples = window.drain(..self.window_size).collect::<Vec<_>>();
+            // a closed gate leaves exact zeros, give or take some resampler ringing
+            let gated = self.controls.noise_gate.load(Ordering::Relaxed)
+                && samples.iter().all(|s| s.abs() < 1e-6);
+            // dc offset and rumble from cheap mics end up in the lowest bins and throw off hps
+            if let Some(high_pass) = self.high_pass.get_mut(i) {
+                for sample in samples.iter_mut() {
+                    *sample = high_pass.process(*sample as f64) as f32;
+                }
+            }
+            let window = Window {
+                samples,
+                sample_rate: self.sample_rate,
+                channel: match self.mode {
+                    ChannelMode::Single(channel) => channel,
+                    ChannelMode::PerChannel => i,
+                    ChannelMode::Downmix => 0,
+                },
+                channels: self.channels,
+                source: self.source,
+                gated,
+                level: self.level,
+                preset: self
+                    .controls
+                    .instrument
+                    .lock()
+                    .unwrap()
+                    .preset()
+                    .with_max_display_hz(*self.controls.max_frequency.lock().unwrap()),
+                dropped_windows: self.dropped_windows,
+                mfcc: self.controls.mfcc.load(Ordering::Relaxed),
+            };
+            // if the analysis falls behind, skip windows instead of lagging further
+            if let Err(TrySendError::Full(_)) = self.analysis.try_send(window) {
+                tracing::debug!("analysis is behind, dropping a window");
+                self.dropped_windows += 1;
+            }
+        }
+    }
+}
+
+/// Windows quieter than this (RMS, after the gain) count as silence
+const SILENCE_THRESHOLD_DB: f32 = -60.0;
+/// How long it has to be silent before analysis pauses
+const SILENCE_HOLD: Duration = Duration::from_secs(1);
+/// Input (RMS, after the gain) louder than this wakes the analysis up again.
+/// A bit above `SILENCE_THRESHOLD_DB` so noise right at the threshold doesn't
+/// keep waking it
+const WAKE_THRESHOLD_DB: f32 = -55.0;
+/// How long the collector thread waits between checks while idle
+const IDLE_COLLECT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Samples at least this loud count as clipped
+const CLIP_LEVEL: f32 = 0.999;
+
+/// Collects the RMS and peak of the raw input between windows
+#[derive(Default)]
+struct LevelMeter {
+    sum_squares: f64,
+    samples: usize,
+    peak: f32,
+}
+
+impl LevelMeter {
+    fn add(&mut self, data: &[f32]) {
+        for sample in data {
+            self.sum_squares += (*sample as f64).powi(2);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.samples += data.len();
+    }
+
+    /// The level since the last call, `None` if nothing came in meanwhile
+    fn take(&mut self) -> Option<InputLevel> {
+        if self.samples == 0 {
+            return None;
+        }
+        let rms = (self.sum_squares / self.samples as f64).sqrt() as f32;
+        let level = InputLevel {
+            rms_db: linear_to_db(rms),
+            peak_db: linear_to_db(self.peak),
+            clipped: self.peak >= CLIP_LEVEL,
+            ..InputLevel::default()
+        };
+        *self = Self::default();
+        Some(level)
+    }
+}
+
+/// RMS level the agc aims for
+const AGC_TARGET_RMS: f32 = 0.1;
+/// Roughly how many seconds of input the agc averages over
+const AGC_TIME_CONSTANT_SECS: f32 = 3.0;
+/// The agc won't boost more than this (+40 dB), so silence stays silence
+const AGC_MAX_GAIN: f32 = 100.0;
+
+/// Automatic gain control: follows the input's RMS level over a few seconds and
+/// scales it towards `AGC_TARGET_RMS`, so quiet and loud mics end up in the same range.
+struct Agc {
+    mean_square: f32,
+    coefficient: f32,
+}
+
+impl Agc {
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            mean_square: AGC_TARGET_RMS * AGC_TARGET_RMS,
+            coefficient: 1.0 / (AGC_TIME_CONSTANT_SECS * sample_rate as f32 * channels as f32),
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        if sample == 0.0 {
+            // don't let a closed noise gate drag the level down
+            return sample;
+        }
+        self.mean_square += self.coefficient * (sample * sample - self.mean_square);
+        let gain = (AGC_TARGET_RMS / self.mean_square.sqrt().max(f32::EPSILON)).min(AGC_MAX_GAIN);
+        sample * gain
+    }
+}
+
+/// Silences the input while it's below the threshold. The level is followed with
+/// a fast attack and slow release, and the gate fades in and out over the attack
+/// and release times so it doesn't click.
+struct NoiseGate {
+    threshold: f32,
+    envelope: f32,
+    envelope_attack: f32,
+    envelope_release: f32,
+    gain: f32,
+    gain_attack_step: f32,
+    gain_release_step: f32,
+}
+
+impl NoiseGate {
+    fn new(options: &NoiseGateOptions, sample_rate: u32, channels: usize) -> Self {
+        let samples_per_ms = sample_rate as f32 * channels as f32 / 1000.0;
+        let attack_samples = (options.attack_ms * samples_per_ms).max(1.0);
+        let release_samples = (options.release_ms * samples_per_ms).max(1.0);
+        Self {
+            threshold: db_to_linear(options.threshold_db),
+            envelope: 0.0,
+            envelope_attack: 1.0 - (-1.0 / attack_samples).exp(),
+            envelope_release: 1.0 - (-1.0 / release_samples).exp(),
+            gain: 0.0,
+            gain_attack_step: 1.0 / attack_samples,
+            gain_release_step: 1.0 / release_samples,
+        }
+    }
+
+    fn process(&mut self, sample: f32) -> f32 {
+        let level = sample.abs();
+        let coefficient = if level > self.envelope {
+            self.envelope_attack
+        } else {
+            self.envelope_release
+        };
+        self.envelope += coefficient * (level - self.envelope);
+        self.gain = if self.envelope >= self.threshold {
+            (self.gain + self.gain_attack_step).min(1.0)
+        } else {
+            (self.gain - self.gain_release_step).max(0.0)
+        };
+        sample * self.gain
+    }
+}
+
+/// Runs the fft and pitch detection on windows of one size. The fft plan and
+/// the work buffers are made once, since windows keep coming at the same size.
+struct Analyzer {
+    fft: Arc<dyn RealToComplex<f32>>,
+    /// The windowed and zero padded samples, the fft's input
+    input: Vec<f32>,
+    window: Vec<f32>,
+    /// Half the sum of the window, the magnitude a full scale sine peaks at
+    full_scale: f32,
+    detector: PitchDetector,
+    mel: MelFilterbank,
+    max_pitches: usize,
+    peak_count: usize,
+    cqt: ConstantQ,
+    key: KeyEstimator,
+    yin: Yin,
+    pyin: Pyin,
+    autocorrelation: Autocorrelation,
+    cepstrum: Cepstrum,
+    buffer: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    /// Magnitudes of the bins in `buffer`, worked out once per window
+    bin_magnitudes: Vec<f32>,
+    log_product_spectrum: Vec<f32>,
+    zero_padding: usize,
+    mode: AnalysisMode,
+    /// Magnitude of each note listened for in the goertzel mode
+    note_magnitudes: Vec<f32>,
+}
+
+impl Analyzer {
+    fn new(n: usize, options: &AnalysisOptions) -> Self {
+        let fft_len = n * options.zero_padding;
+        let fft = RealFftPlanner::new().plan_fft_forward(fft_len);
+        let window = options.window.coefficients(n);
+        Self {
+            full_scale: window.iter().sum::<f32>() / 2.0,
+            input: fft.make_input_vec(),
+            buffer: fft.make_output_vec(),
+            scratch: fft.make_scratch_vec(),
+            fft,
+            window,
+            detector: options.pitch_detector,
+            mel: MelFilterbank::new(options.mel_bands),
+            max_pitches: options.max_pitches,
+            peak_count: options.peak_count,
+            cqt: ConstantQ::default(),
+            key: KeyEstimator::new(Duration::from_secs_f32(options.key_window_secs)),
+            yin: Yin::default(),
+            pyin: Pyin::default(),
+            autocorrelation: Autocorrelation::default(),
+            cepstrum: Cepstrum::new(fft_len),
+            bin_magnitudes: Vec::with_capacity(fft_len / 2 + 1),
+            log_product_spectrum: Vec::with_capacity(fft_len / 2 / HPS_HARMONICS as usize + 1),
+            zero_padding: options.zero_padding,
+            mode: options.mode,
+            note_magnitudes: Vec::with_capacity(GOERTZEL_NOTES),
+        }
+    }
+
+    fn analyze(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        preset: &InstrumentPreset,
+    ) -> FreqData {
+        if self.mode == AnalysisMode::Goertzel {
+            return self.analyze_notes(samples, sample_rate, preset);
+        }
+        // the fft size, the bins are spaced by the sample rate over this
+        let n = samples.len() * self.zero_padding;
+        self.input.clear();
+        self.input.extend(
+            samples
+                .iter()
+                .zip(self.window.iter())
+                .map(|(sample, window)| sample * window),
+        );
+        self.input.resize(n, 0.0);
+        // the input is real, so only the bins up to nyquist are computed
+        self.fft
+            .process_with_scratch(&mut self.input, &mut self.buffer, &mut self.scratch)
+            .expect("windows are the size the fft was planned for");
+        self.bin_magnitudes.resize(self.buffer.len(), 0.0);
+        self.bin_magnitudes
+            .par_iter_mut()
+            .zip(self.buffer.par_iter())
+            .with_min_len(PARALLEL_MIN_BINS)
+            .for_each(|(magnitude, bin)| *magnitude = bin.norm());
+
+        let max_k = n / 2 + 1;
+        let mut octave_correction = None;
+        let (pitch, pitch_method) = match self.detector {
+            PitchDetector::Hps => {
+                let hps = self.hps(n, sample_rate, preset);
+                let (hps, correction) = correct_octave(
+                    &self.bin_magnitudes,
+                    hps,
+                    sample_rate as f32 / n as f32,
+                    self.zero_padding,
+                    preset.pitch_range,
+                );
+                // hps only has a few bins to work with down here
+                if hps < AUTOCORRELATION_BELOW_HZ
+                    && let Some(pitch) =
+                        self.autocorrelation
+                            .detect(samples, sample_rate, preset.pitch_range)
+                {
+                    (Some(pitch), PitchMethod::Autocorrelation)
+                } else {
+                    let pitch = Pitch {
+                        frequency: hps,
+                        confidence: harmonicity(
+                            &self.buffer[..max_k],
+                            hps,
+                            sample_rate,
+                            n,
+                            self.zero_padding,
+                        ),
+                    };
+                    octave_correction = correction;
+                    (Some(pitch), PitchMethod::Hps)
+                }
+            }
+            PitchDetector::Yin => (
+                self.yin.detect(samples, sample_rate, preset.pitch_range),
+                PitchMethod::Yin,
+            ),
+            PitchDetector::Pyin => (
+                self.pyin.detect(samples, sample_rate, preset.pitch_range),
+                PitchMethod::Pyin,
+            ),
+            PitchDetector::Cepstrum => (
+                self.cepstrum
+                    .detect(&self.buffer, sample_rate, preset.pitch_range),
+                PitchMethod::Cepstrum,
+            ),
+        };
+        let (fundamental_frequency, pitch_confidence) = match pitch {
+            Some(pitch) if pitch.frequency > 0.0 => (pitch.frequency, pitch.confidence),
+            _ => (0.0, 0.0),
+        };
+        let features = SpectralFeatures::new(&self.buffer, sample_rate, n);
+        let magnitudes = &self.bin_magnitudes;
+
+        let mut max_magnitude_freq = 0.0;
+        let mut max_magnitude = magnitudes[0];
+        let mut freq_data = vec![];
+        for (i, &magnitude) in magnitudes.iter().enumerate() {
+            let freq = i as f32 * sample_rate as f32 / n as f32;
+            if freq <= preset.max_display_hz {
+                freq_data.push((freq as f64, magnitude as f64));
+            }
+            if magnitude > max_magnitude {
+                max_magnitude = magnitude;
+                max_magnitude_freq = freq;
+            }
+        }
+        let mel_data = self
+            .mel
+            .apply(|bin| magnitudes[bin], sample_rate, n, preset.max_display_hz);
+        // noise has peaks everywhere that would all pass for notes
+        let pitches = if features.flatness < MULTI_PITCH_MAX_FLATNESS {
+            detect_pitches(
+                magnitudes,
+                sample_rate,
+                n,
+                self.zero_padding,
+                preset.pitch_range,
+                self.max_pitches,
+            )
+        } else {
+            vec![]
+        };
+        let harmonics = HarmonicSeries::new(
+            magnitudes,
+            fundamental_frequency,
+            sample_rate as f32 / n as f32,
+            self.zero_padding,
+        );
+        // noise has no vocal tract resonances to find
+        let formants = (features.flatness < MULTI_PITCH_MAX_FLATNESS)
+            .then(|| Formants::estimate(samples, sample_rate))
+            .flatten();
+        let peaks = find_peaks(
+            magnitudes,
+            sample_rate as f32 / n as f32,
+            self.zero_padding,
+            self.peak_count,
+        );
+        let cqt = self.cqt.apply(samples, sample_rate);
+        let chroma = chroma(&cqt);
+        FreqData {
+            data: freq_data,
+            max_magnitude,
+            full_scale: self.full_scale,
+            peak_frequency: max_magnitude_freq,
+            fundamental_frequency,
+            samples_n: n,
+            sample_rate,
+            time_domain_samples: samples.to_vec(),
+            channel: 0,
+            channels: 1,
+            gated: false,
+            source: 0,
+            level: InputLevel::default(),
+            features,
+            pitch_confidence,
+            pitch_method,
+            octave_correction,
+            mel_data,
+            mfcc: vec![],
+            pitches,
+            harmonics,
+            peaks,
+            formants,
+            envelope: None,
+            chord: detect_chord(&chroma),
+            key: self.key.add(&chroma, features.flatness),
+            onset: false,
+            bpm: None,
+            vibrato: None,
+            chroma,
+            cqt,
+            cqt_constant_q_from: self.cqt.constant_q_from(),
+            analysis_time: Duration::ZERO,
+            dropped_windows: 0,
+        }
+    }
+
+    /// Only the notes the instrument can play, by goertzel filters instead of
+    /// an fft. The spectrum has a point per note and there's no pitch but the
+    /// loudest note, no chords and no key.
+    fn analyze_notes(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        preset: &InstrumentPreset,
+    ) -> FreqData {
+        let notes = goertzel_notes(preset.pitch_range);
+        self.note_magnitudes.clear();
+        self.note_magnitudes.extend(
+            notes
+                .clone()
+                .map(|note| goertzel(samples, &self.window, sample_rate, midi_to_hz(note))),
+        );
+        let (fundamental_frequency, pitch_confidence) = goertzel_pitch(
+            samples,
+            &self.window,
+            sample_rate,
+            &notes,
+            &self.note_magnitudes,
+        )
+        .unwrap_or((0.0, 0.0));
+        let (peak_frequency, max_magnitude) = notes
+            .clone()
+            .zip(&self.note_magnitudes)
+            .map(|(note, magnitude)| (midi_to_hz(note), *magnitude))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or_default();
+        FreqData {
+            data: notes
+                .zip(&self.note_magnitudes)
+                .map(|(note, magnitude)| (midi_to_hz(note) as f64, *magnitude as f64))
+                .filter(|(frequency, _)| *frequency <= preset.max_display_hz as f64)
+                .collect(),
+            peak_frequency,
+            fundamental_frequency,
+            max_magnitude,
+            full_scale: self.full_scale,
+            sample_rate,
+            samples_n: samples.len(),
+            time_domain_samples: samples.to_vec(),
+            channels: 1,
+            // over the notes rather than the bins, still near 1 for noise
+            features: SpectralFeatures {
+                flatness: flatness(self.note_magnitudes.iter().map(|m| m * m)),
+                ..SpectralFeatures::default()
+            },
+            pitch_confidence,
+            pitch_method: PitchMethod::Goertzel,
+            ..FreqData::default()
+        }
+    }
+
+    /// Magnitudes of the last analyzed window's bins up to the nyquist
+    /// frequency, or of its notes in the goertzel mode
+    fn magnitudes(&self) -> impl Iterator<Item = f32> + '_ {
+        match self.mode {
+            AnalysisMode::Fft => Either::Left(self.bin_magnitudes.iter().copied()),
+            AnalysisMode::Goertzel => Either::Right(self.note_magnitudes.iter().copied()),
+        }
+    }
+
+    /// Harmonic product spectrum on the spectrum in `buffer`
+    fn hps(&mut self, n: usize, sample_rate: u32, preset: &InstrumentPreset) -> f32 {
+        let epsilon = 1e-10;
+        let buffer = &self.buffer;
+        let magnitudes = &self.bin_magnitudes;
+        let max_k = n / 2 + 1;
+        // the spectrum downsampled by the highest harmonic is the shortest
+        let smallest_len = max_k.div_ceil(HPS_HARMONICS as usize);
+        let log_product_spectrum = &mut self.log_product_spectrum;
+        log_product_spectrum.resize(smallest_len, 0.0);
+        log_product_spectrum
+            .par_iter_mut()
+            .enumerate()
+            .with_min_len(PARALLEL_MIN_BINS)
+            .for_each(|(i, log_psi)| {
+                // the product of the downsampled spectra, as a sum of logs
+                *log_psi = (1..=HPS_HARMONICS as usize)
+                    .map(|h| magnitudes[i * h].max(epsilon).ln())
+                    .sum::<f32>();
+            });
+        let mut max_product_spectrum_i = 0;
+        let mut max_product_spectrum = f32::NEG_INFINITY;
+        let bin_width = sample_rate as f32 / n as f32;
+        let pitch_bins = (preset.pitch_range.0 / bin_width).ceil() as usize
+            ..=(preset.pitch_range.1 / bin_width).min(n as f32) as usize;
+        for (i, &log_psi) in log_product_spectrum.iter().enumerate() {
+            if pitch_bins.contains(&i) && log_psi > max_product_spectrum {
+                max_product_spectrum_i = i;
+                max_product_spectrum = log_psi;
+            }
+        }
+        // quadratic interpolation gang. the peak bin's neighbours are too far off
+        // for the higher harmonics (bin i ± 1 is bin 4i ± 4 for the 4th), so the
+        // log product spectrum is evaluated between the bins around the peak
+        // instead, and the parabola goes through its best three points
+        let i = max_product_spectrum_i;
+        if i < 2 || HPS_HARMONICS as usize * (i + 1) + 1 >= max_k {
+            // no room for neighbours at the edges, half a bin below bin 1 rounds
+            // to bin 0 which has none below it
+            return i as f32 * bin_width;
+        }
+        let product_at = |k: i32| {
+            let x = i as f32 + k as f32 / HPS_REFINE_STEPS as f32;
+            (1..=HPS_HARMONICS)
+                .map(|h| log_magnitude_at(buffer, h as f32 * x))
+                .sum::<f32>()
+        };
+        let steps = HPS_REFINE_STEPS as i32;
+        let best = (-steps / 2..=steps / 2)
+            .max_by(|a, b| product_at(*a).total_cmp(&product_at(*b)))
+            .unwrap_or(0);
+        let yc = product_at(best);
+        let (p, top) = parabolic_vertex(product_at(best - 1), yc, product_at(best + 1));
+        // a dip at the edge of the steps has no top to move to
+        let p = if top < yc { 0.0 } else { p.clamp(-1.0, 1.0) };
+        (i as f32 + (best as f32 + p) / HPS_REFINE_STEPS as f32) * bin_width
+    }
+}
+
+/// Fewest bins handed to a thread at once, smaller spectra aren't worth
+/// splitting up so only the padded and bigger windows go parallel
+const PARALLEL_MIN_BINS: usize = 4096;
+
+/// Harmonics the hps multiplies
+const HPS_HARMONICS: u32 = 4;
+/// Points per bin the hps refinement evaluates the product spectrum at
+const HPS_REFINE_STEPS: u32 = 16;
+
+/// Log magnitude of `spectrum` at a fractional bin, from a parabola through
+/// the nearest bins
+fn log_magnitude_at(spectrum: &[Complex<f32>], bin: f32) -> f32 {
+    let log_magnitude = |i: usize| spectrum[i].norm().max(1e-10).ln();
+    let c = bin.round() as usize;
+    let d = bin - c as f32;
+    let (l, m, r) = (log_magnitude(c - 1), log_magnitude(c), log_magnitude(c + 1));
+    m + 0.5 * d * (r - l) + 0.5 * d * d * (l - 2.0 * m + r)
+}
+
+/// Below this hps is unreliable, bins are ~10 Hz apart at 4096 samples, so the
+/// autocorrelation takes over
+const AUTOCORRELATION_BELOW_HZ: f32 = 100.0;
+
+/// Noisier windows than this (spectral flatness) aren't searched for notes
+const MULTI_PITCH_MAX_FLATNESS: f32 = 0.3;
+
+/// Harmonics of the fundamental counted towards its harmonicity
+const HARMONICITY_HARMONICS: usize = 8;
+
+/// Share of the power in `FLATNESS_RANGE` that sits on the harmonics of
+/// `fundamental`, within `spread` bins either side for the window's spread (a
+/// bin of the unpadded window). Near 1 when the pitch explains the sound,
+/// near 0 for noise or a wrong pitch.
+fn harmonicity(
+    spectrum: &[Complex<f32>],
+    fundamental: f32,
+    sample_rate: u32,
+    n: usize,
+    spread: usize,
+) -> f32 {
+    if fundamental <= 0.0 {
+        return 0.0;
+    }
+    let bin_width = sample_rate as f32 / n as f32;
+    let first = (FLATNESS_RANGE.0 / bin_width).ceil() as usize;
+    let last = ((FLATNESS_RANGE.1 / bin_width) as usize).min(spectrum.len() - 1);
+    let power = |range: RangeInclusive<usize>| {
+        spectrum[range]
+            .iter()
+            .map(|bin| bin.norm_sqr())
+            .sum::<f32>()
+    };
+    let total = power(first..=last);
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let mut on_harmonics = 0.0;
+    let mut counted_up_to = 0;
+    for harmonic in 1..=HARMONICITY_HARMONICS {
+        let bin = (harmonic as f32 * fundamental / bin_width).round() as usize;
+        // close harmonics of a low note share bins, count them once
+        let start = bin.saturating_sub(spread).max(first).max(counted_up_to);
+        let end = (bin + spread).min(last);
+        if start > end {
+            continue;
+        }
+        on_harmonics += power(start..=end);
+        counted_up_to = end + 1;
+    }
+    (on_harmonics / total).clamp(0.0, 1.0)
+}
+
+pub struct InputDeviceInfo {
+    pub name: String,
+    pub configs: Vec<String>,
+}
+
+/// Lists the host's input devices along with a short description of each
+/// config they support.
+pub fn list_input_devices(host: &Host) -> Result<Vec<InputDeviceInfo>> {
+    let devices = host
+        .input_devices()?
+        .map(|device| {
+            let name = device.name().unwrap_or_else(|_| "<unnamed>".to_string());
+            let configs = device
+                .supported_input_configs()
+                .map(|configs| {
+                    configs
+                        .map(|c| {
+                            format!(
+                                "{} ch, {}-{} Hz, {}",
+                                c.channels(),
+                                c.min_sample_rate().0,
+                                c.max_sample_rate().0,
+                                c.sample_format()
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            InputDeviceInfo { name, configs }
+        })
+        .collect();
+    Ok(devices)
+}
+
+pub fn select_host(name: Option<&str>) -> Result<Host> {
+    let Some(name) = name.filter(|name| *name != "default") else {
+        return Ok(cpal::default_host());
+    };
+    if let Some(host_id) = cpal::available_hosts()
+        .into_iter()
+        .find(|host_id| host_id.name().eq_ignore_ascii_case(name))
+    {
+        return Ok(cpal::host_from_id(host_id)?);
+    }
+    if cpal::ALL_HOSTS
+        .iter()
+        .any(|host_id| host_id.name().eq_ignore_ascii_case(name))
+    {
+        return Err(eyre!(
+            "The {name} host isn't available right now, is it running?"
+        ));
+    }
+    if name.eq_ignore_ascii_case("jack") {
+        return Err(eyre!(
+            "JACK support isn't built in, rebuild with `cargo build --features jack`"
+        ));
+    }
+    Err(eyre!(
+        "Unknown host `{name}`, expected `default` or one of: {}",
+        available_host_names().join(", ")
+    ))
+}
+
+/// Names of the hosts that can be used on this machine, for `--host`
+pub fn available_host_names() -> Vec<String> {
+    cpal::available_hosts()
+        .into_iter()
+        .map(|host_id| host_id.name().to_lowercase())
+        .collect()
+}
+
+/// The device to capture from given the listener options, either a loopback
+/// source or an input device.
+pub fn find_capture_device(host: &Host, options: &ListenerOptions) -> Result<Device> {
+    if options.loopback {
+        find_loopback_device(host)
+    } else {
+        find_input_device(host, options.device.as_deref())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn find_loopback_device(host: &Host) -> Result<Device> {
+    host.default_output_device()
+        .ok_or_else(|| eyre!("No default output device to capture from"))
+}
+
+/// PulseAudio and PipeWire expose what's playing as "Monitor of ..." sources, and
+/// the snd-aloop module shows up as a "Loopback" card.
+#[cfg(not(target_os = "windows"))]
+fn find_loopback_device(host: &Host) -> Result<Device> {
+    host.input_devices()?
+        .find(|d| {
+            d.name().is_ok_and(|name| {
+                let name = name.to_lowercase();
+                name.contains("monitor") || name.contains("loopback")
+            })
+        })
+        .ok_or_else(|| {
+            eyre!(
+                "No monitor or loopback input device found. Available input devices:\n{}\n\
+                 On PulseAudio/PipeWire you can instead record from the default device and \
+                 point it at the monitor of your output (e.g. with pavucontrol).",
+                describe_input_devices(host)
+            )
+        })
+}
+
+/// Picks the input device matching `selector`, which is either a device name
+/// or its index in the host's input device list. `None` means the default device.
+pub fn find_input_device(host: &Host, selector: Option<&str>) -> Result<Device> {
+    let Some(selector) = selector else {
+        return host
+            .default_input_device()
+            .ok_or_else(|| eyre!("No default input device found"));
+    };
+    find_device(host.input_devices()?, selector, "input")
+}
+
+/// The one of `devices` that `selector` names, or is the index of. `kind` of
+/// device, like "input" or "output", is for the error, which lists them all.
+pub fn find_device(
+    devices: impl Iterator<Item = Device>,
+    selector: &str,
+    kind: &str,
+) -> Result<Device> {
+    let mut devices = devices.collect::<Vec<_>>();
+    let found = if let Ok(index) = selector.parse::<usize>() {
+        (index < devices.len()).then_some(index)
+    } else {
+        devices
+            .iter()
+            .position(|d| d.name().is_ok_and(|name| name == selector))
+    };
+    match found {
+        Some(index) => Ok(devices.swap_remove(index)),
+        None => Err(eyre!(
+            "No {kind} device matching `{selector}`. Available {kind} devices:\n{}",
+            describe_devices(&devices)
+        )),
+    }
+}
+
+fn describe_input_devices(host: &Host) -> String {
+    match host.input_devices() {
+        Ok(devices) => describe_devices(&devices.collect::<Vec<_>>()),
+        Err(_) => "  (couldn't list input devices)".to_string(),
+    }
+}
+
+/// A line per device with its index and name
+fn describe_devices(devices: &[Device]) -> String {
+    let lines = devices
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            format!(
+                "  {i}: {}",
+                d.name().unwrap_or_else(|_| "<unnamed>".to_string())
+            )
+        })
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        "  (none)".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+pub fn linear_to_db(linear: f32) -> f32 {
+    20.0 * linear.log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, harmonic_tone, sine};
+
+    fn preset(pitch_range: (f32, f32)) -> InstrumentPreset {
+        InstrumentPreset {
+            pitch_range,
+            ..Instrument::Any.preset()
+        }
+    }
+
+    #[test]
+    fn hps_refines_the_fundamental_between_the_bins() {
+        let mut analyzer = Analyzer::new(4096, &AnalysisOptions::default());
+        // the bins are 10.8 Hz apart
+        let data = analyzer.analyze(
+            &harmonic_tone(330.0, 6, 4096),
+            SAMPLE_RATE,
+            &preset((50.0, 2000.0)),
+        );
+        assert_eq!(data.pitch_method, PitchMethod::Hps);
+        assert_near(data.fundamental_frequency, 330.0, 1.0);
+    }
+
+    #[test]
+    fn hps_at_bin_1_is_left_unrefined() {
+        let n = 4096;
+        let bin_width = SAMPLE_RATE as f32 / n as f32;
+        let preset = preset((bin_width / 2.0, bin_width * 1.5));
+        let mut analyzer = Analyzer::new(n, &AnalysisOptions::default());
+        analyzer.analyze(&harmonic_tone(bin_width, 4, n), SAMPLE_RATE, &preset);
+        // there's no room for the refinement half a bin below
+        assert_eq!(analyzer.hps(n, SAMPLE_RATE, &preset), bin_width);
+    }
+
+    #[test]
+    fn hps_finds_a_fundamental_quieter_than_its_harmonics() {
+        let n = 4096;
+        // the 2nd and 5th harmonics are the loudest. Summing magnitudes
+        // instead of multiplying them would pick 110 Hz, whose 2nd and 4th
+        // harmonics are 220 and 440
+        let mut samples = vec![0.0; n];
+        for (h, amplitude) in [(1, 0.2), (2, 0.6), (3, 0.1), (4, 0.05), (5, 0.3)] {
+            let harmonic = sine(220.0 * h as f32, n);
+            samples
+                .iter_mut()
+                .zip(harmonic)
+                .for_each(|(sample, harmonic)| *sample += amplitude * harmonic);
+        }
+        let preset = preset((50.0, 2000.0));
+        let mut analyzer = Analyzer::new(n, &AnalysisOptions::default());
+        analyzer.analyze(&samples, SAMPLE_RATE, &preset);
+        assert_near(analyzer.hps(n, SAMPLE_RATE, &preset), 220.0, 1.0);
+    }
 }
-const NOTES: [&str; 12] = [
-    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
-];
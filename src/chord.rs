@@ -0,0 +1,152 @@
+use std::fmt::Display;
+
+use crate::tuning::NOTES;
+
+/// Chords whose template matches the chroma worse than this (cosine
+/// similarity) aren't reported
+const CHORD_MIN_SCORE: f32 = 0.8;
+/// Every note of the chord needs at least this much of the strongest pitch
+/// class. Keeps a single note, whose harmonics hint at a major chord, from
+/// being called one
+const CHORD_MIN_TONE: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Dominant7,
+    Major7,
+    Minor7,
+}
+
+impl ChordQuality {
+    const ALL: [ChordQuality; 5] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Dominant7,
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+    ];
+
+    /// Semitones above the root
+    fn intervals(self) -> &'static [usize] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Dominant7 => &[0, 4, 7, 10],
+            ChordQuality::Major7 => &[0, 4, 7, 11],
+            ChordQuality::Minor7 => &[0, 3, 7, 10],
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Dominant7 => "7",
+            ChordQuality::Major7 => "maj7",
+            ChordQuality::Minor7 => "m7",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chord {
+    /// Pitch class of the root, 0 for C
+    pub root: usize,
+    pub quality: ChordQuality,
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", NOTES[self.root], self.quality.suffix())
+    }
+}
+
+/// The chord that best fits a chroma vector, by matching it against a template
+/// of every major, minor and seventh chord. `None` when nothing fits well, like
+/// for a single note or noise.
+pub fn detect_chord(chroma: &[f32; 12]) -> Option<Chord> {
+    let norm = chroma.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if norm <= 0.0 {
+        return None;
+    }
+    let max = chroma.iter().copied().fold(0.0, f32::max);
+    let (chord, score) = (0..12)
+        .flat_map(|root| {
+            ChordQuality::ALL
+                .iter()
+                .map(move |&quality| Chord { root, quality })
+        })
+        .filter(|chord| {
+            chord
+                .quality
+                .intervals()
+                .iter()
+                .all(|interval| chroma[(chord.root + interval) % 12] >= max * CHORD_MIN_TONE)
+        })
+        .map(|chord| {
+            let intervals = chord.quality.intervals();
+            let dot = intervals
+                .iter()
+                .map(|interval| chroma[(chord.root + interval) % 12])
+                .sum::<f32>();
+            (chord, dot / (norm * (intervals.len() as f32).sqrt()))
+        })
+        // on a tie the simpler chord, which comes first, wins
+        .fold(
+            None,
+            |best: Option<(Chord, f32)>, (chord, score)| match best {
+                Some((_, best_score)) if best_score >= score => best,
+                _ => Some((chord, score)),
+            },
+        )?;
+    (score >= CHORD_MIN_SCORE).then_some(chord)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chroma vector with the pitch classes in `notes` equally strong
+    fn chroma_of(notes: &[usize]) -> [f32; 12] {
+        let mut chroma = [0.0; 12];
+        notes.iter().for_each(|note| chroma[*note] = 1.0);
+        chroma
+    }
+
+    fn chord_name(notes: &[usize]) -> Option<String> {
+        detect_chord(&chroma_of(notes)).map(|chord| chord.to_string())
+    }
+
+    #[test]
+    fn triads_and_sevenths() {
+        assert_eq!(
+            detect_chord(&chroma_of(&[9, 0, 4])),
+            Some(Chord {
+                root: 9,
+                quality: ChordQuality::Minor
+            })
+        );
+        assert_eq!(chord_name(&[9, 0, 4]).as_deref(), Some("Am"));
+        assert_eq!(chord_name(&[2, 6, 9]).as_deref(), Some("D"));
+        // the triad inside a seventh chord fits it less well
+        assert_eq!(chord_name(&[7, 11, 2, 5]).as_deref(), Some("G7"));
+        assert_eq!(chord_name(&[0, 4, 7, 11]).as_deref(), Some("Cmaj7"));
+        assert_eq!(chord_name(&[2, 5, 9, 0]).as_deref(), Some("Dm7"));
+    }
+
+    #[test]
+    fn a_note_with_its_harmonics_is_no_chord() {
+        let mut chroma = chroma_of(&[0]);
+        // the 3rd and 5th harmonics hint at a C major chord
+        chroma[7] = 0.3;
+        chroma[4] = 0.2;
+        assert_eq!(detect_chord(&chroma), None);
+    }
+
+    #[test]
+    fn silence_is_no_chord() {
+        assert_eq!(detect_chord(&[0.0; 12]), None);
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::{
+    audio::{WindowFunction, ZERO_PADDING_FACTORS},
+    instrument::Instrument,
+    pitch::PitchDetector,
+    theme::{ChartMarker, ThemeName},
+};
+
+/// Listens to your instrument and shows what it hears.
+#[derive(Parser, Debug, Default)]
+#[command(version, about)]
+pub struct Cli {
+    /// File with the notes to practice in the tutor screen
+    pub file: Option<String>,
+
+    /// Audio host to use, e.g. `alsa`, `jack` (needs the `jack` feature), `wasapi` or
+    /// `coreaudio`. See --list-hosts
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// List the audio hosts available on this machine and exit
+    #[arg(long)]
+    pub list_hosts: bool,
+
+    /// Input device to listen on, either its name or its index
+    #[arg(long)]
+    pub device: Option<String>,
+
+    /// Sample rate in Hz to analyze at, resampling if the input can't provide it.
+    /// Without it the device's rate is used, preferring 48000 or 44100
+    #[arg(long)]
+    pub sample_rate: Option<u32>,
+
+    /// Also listen on this device (name or index) and show its pitch next to the main one,
+    /// e.g. to practice a duet
+    #[arg(long)]
+    pub second_device: Option<String>,
+
+    /// Analyze what the computer is playing instead of the microphone
+    #[arg(long, conflicts_with = "device")]
+    pub loopback: bool,
+
+    /// Input channel to analyze, counting from 1
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub channel: Option<u16>,
+
+    /// Analyze the average of all input channels
+    #[arg(long, conflicts_with = "channel")]
+    pub downmix: bool,
+
+    /// Analyze every input channel separately instead of only the first one
+    #[arg(long, conflicts_with_all = ["channel", "downmix"])]
+    pub per_channel: bool,
+
+    /// Automatically even out the input level, so quiet and loud mics behave the same
+    #[arg(long)]
+    pub agc: bool,
+
+    /// Silence input quieter than this many dBFS (e.g. -50) so room noise isn't analyzed
+    #[arg(long, allow_negative_numbers = true)]
+    pub noise_gate: Option<f32>,
+
+    /// Instrument being played, which sets the frequency range that's analyzed and shown
+    #[arg(long, value_enum)]
+    pub instrument: Option<Instrument>,
+
+    /// Color theme: default, light, high-contrast or solarized. `T` cycles through them
+    #[arg(long, value_enum)]
+    pub theme: Option<ThemeName>,
+
+    /// What the fft and time domain charts are drawn with: braille (default), half-block, block
+    /// or dot, for fonts that draw braille broken. `M` cycles through them
+    #[arg(long, value_enum)]
+    pub marker: Option<ChartMarker>,
+
+    /// How to find the fundamental: hps (default), yin, which octave-errors less on low notes,
+    /// pyin, which also ignores noise it's unsure about, or cepstrum, which suits voices
+    #[arg(long, value_enum)]
+    pub pitch_detector: Option<PitchDetector>,
+
+    /// Window function applied before the fft (default hann)
+    #[arg(long, value_enum)]
+    pub window: Option<WindowFunction>,
+
+    /// Only listen for the notes the instrument can play, by a goertzel filter per note, instead
+    /// of analyzing the whole spectrum. Uses far less cpu, for small boards like a Raspberry Pi
+    #[arg(long)]
+    pub goertzel: bool,
+
+    /// Pad each window with zeros to 2 or 4 times its length before the fft, for a smoother
+    /// spectrum and finer peaks without listening any longer (default 1, no padding)
+    #[arg(long, value_parser = zero_padding)]
+    pub zero_padding: Option<usize>,
+
+    /// Highest frequency in Hz the spectrum charts show (default: the instrument's,
+    /// 1500 without one). Raise it for piccolo or cymbals
+    #[arg(long, value_parser = positive_f32)]
+    pub max_frequency: Option<f32>,
+
+    /// Cutoff in Hz of the high-pass filter that removes dc offset and rumble before
+    /// analysis (default 20), 0 turns it off
+    #[arg(long, value_parser = non_negative_f32)]
+    pub high_pass: Option<f32>,
+
+    /// Play the input back on an output device (its name or index, the default one
+    /// without a value) so you can hear yourself. Use headphones to avoid feedback
+    #[arg(long, num_args = 0..=1, default_missing_value = "", conflicts_with = "audio_file")]
+    pub monitor: Option<String>,
+
+    /// How far behind the input the monitor plays, in milliseconds. Lower it to
+    /// hear yourself sooner, raise it if the playback crackles
+    #[arg(long, default_value_t = 50, requires = "monitor")]
+    pub monitor_latency: u64,
+
+    /// Record the input to this wav file while analyzing it (`r` toggles recording)
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+
+    /// Times a second the screen is redrawn (default 30). Keys are handled as they come
+    #[arg(long, value_parser = positive_f32)]
+    pub fps: Option<f32>,
+
+    /// Times a second the latest analysis is taken in (default 20), which moves the note
+    /// readings and the charts on
+    #[arg(long, value_parser = positive_f32)]
+    pub data_rate: Option<f32>,
+
+    /// Config file to use instead of the one in the config directory
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Analyze a wav file instead of listening to an input device
+    #[arg(long, conflicts_with_all = ["device", "loopback", "host", "second_device"])]
+    pub audio_file: Option<PathBuf>,
+
+    /// Playback speed for --audio-file, 1 is real time
+    #[arg(long, default_value_t = 1.0, requires = "audio_file", value_parser = positive_f32)]
+    pub speed: f32,
+}
+
+fn non_negative_f32(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(value) if value >= 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("`{s}` isn't a number of at least 0")),
+    }
+}
+
+fn zero_padding(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(value) if ZERO_PADDING_FACTORS.contains(&value) => Ok(value),
+        _ => Err(format!("`{s}` isn't 1, 2 or 4")),
+    }
+}
+
+fn positive_f32(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("`{s}` isn't a positive number")),
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Result, WrapErr};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::{
+    app::RefreshOptions,
+    audio::{AnalysisOptions, NoiseGateOptions},
+    instrument::Instrument,
+    layout::LayoutOptions,
+    logging::{PROJECT_NAME, project_directory},
+    theme::ThemeOptions,
+    tuning::TuningOptions,
+};
+
+lazy_static! {
+    pub static ref CONFIG_FOLDER: Option<PathBuf> =
+        std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone()))
+            .ok()
+            .map(PathBuf::from);
+}
+
+const CONFIG_FILE: &str = "config.toml";
+
+pub fn get_config_dir() -> PathBuf {
+    if let Some(s) = CONFIG_FOLDER.clone() {
+        s
+    } else if let Some(proj_dirs) = project_directory() {
+        proj_dirs.config_local_dir().to_path_buf()
+    } else {
+        PathBuf::from(".").join(".config")
+    }
+}
+
+/// Where the config file goes when `--config` doesn't say
+pub fn config_path() -> PathBuf {
+    get_config_dir().join(CONFIG_FILE)
+}
+
+/// Writes a config file with just the instrument, by its name, and the input
+/// device where the next start finds it. Returns its path.
+pub fn write_starter_config(instrument: &str, device: Option<&str>) -> Result<PathBuf> {
+    let path = config_path();
+    std::fs::create_dir_all(get_config_dir())?;
+    let mut content = format!(
+        "# see the readme for everything that can go here\ninstrument = \"{instrument}\"\n"
+    );
+    if let Some(device) = device {
+        // device names can have quotes and backslashes in them
+        content += &format!("device = {}\n", toml::Value::from(device));
+    }
+    std::fs::write(&path, content)
+        .wrap_err_with(|| format!("couldn't write config file {}", path.display()))?;
+    Ok(path)
+}
+
+/// Settings read from the config file. Anything missing from the file keeps its
+/// default, and command line options win over the file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Input device to listen on, by its name, when `--device` doesn't say
+    pub device: Option<String>,
+    pub noise_gate: NoiseGateOptions,
+    pub analysis: AnalysisOptions,
+    pub instrument: Instrument,
+    pub tuning: TuningOptions,
+    pub theme: ThemeOptions,
+    pub refresh: RefreshOptions,
+    /// Screens of the user's own, after the built-in ones
+    pub layouts: Vec<LayoutOptions>,
+}
+
+impl Config {
+    /// Loads the config from `path`, or from the config dir if there's a config
+    /// file there. No file at all just means the defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let path = config_path();
+                if !path.exists() {
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+        let content = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("couldn't read config file {}", path.display()))?;
+        toml::from_str(&content)
+            .wrap_err_with(|| format!("couldn't parse config file {}", path.display()))
+    }
+}
@@ -0,0 +1,166 @@
+use std::f32::consts::PI;
+
+/// Midi note of the lowest constant-q bin, C1
+pub const CQT_LOWEST_MIDI: i32 = 24;
+/// Semitone bins of the constant-q transform, seven octaves from C1 to B7
+pub const CQT_BINS: usize = 7 * 12;
+
+/// Constant-q transform with one bin per semitone. Every bin gets a window a
+/// fixed number of its own periods long, so low notes are as well resolved as
+/// high ones instead of sharing a few wide fft bins. Bins whose window would be
+/// longer than the analysis window get the whole window, which makes it a
+/// variable-q transform at the bottom: below about 181 Hz at 44.1 kHz with
+/// 4096 samples, see `constant_q_from`.
+#[derive(Default)]
+pub struct ConstantQ {
+    /// Sample rate and window length the kernels were made for
+    made_for: (u32, usize),
+    /// The first bin whose window fits in the analysis window
+    constant_q_from: usize,
+    /// Per bin, where its window starts in the samples and the hann-weighted
+    /// complex exponential to correlate with
+    kernels: Vec<(usize, Vec<(f32, f32)>)>,
+}
+
+impl ConstantQ {
+    /// Amplitude of each semitone from C1 up, 1 being a full scale sine. Uses
+    /// the newest samples, as long a stretch as each bin needs.
+    pub fn apply(&mut self, samples: &[f32], sample_rate: u32) -> Vec<f32> {
+        if self.made_for != (sample_rate, samples.len()) {
+            self.make_kernels(sample_rate, samples.len());
+        }
+        self.kernels
+            .iter()
+            .map(|(start, kernel)| {
+                let (re, im) = samples[*start..]
+                    .iter()
+                    .zip(kernel)
+                    .fold((0.0, 0.0), |(re, im), (sample, (k_re, k_im))| {
+                        (re + sample * k_re, im + sample * k_im)
+                    });
+                (re * re + im * im).sqrt()
+            })
+            .collect()
+    }
+
+    /// The first bin that's really constant-q for the last window. The ones
+    /// below it wanted a longer window than there was, so they smear into
+    /// their neighbouring semitones.
+    pub fn constant_q_from(&self) -> usize {
+        self.constant_q_from
+    }
+
+    fn make_kernels(&mut self, sample_rate: u32, len: usize) {
+        self.made_for = (sample_rate, len);
+        // enough periods per window to tell neighbouring semitones apart
+        let q = 1.0 / (2.0_f32.powf(1.0 / 12.0) - 1.0);
+        let wanted_length = |bin: usize| {
+            let frequency = midi_to_hz(CQT_LOWEST_MIDI + bin as i32);
+            (q * sample_rate as f32 / frequency).ceil() as usize
+        };
+        self.constant_q_from = (0..CQT_BINS)
+            .find(|bin| wanted_length(*bin) <= len)
+            .unwrap_or(CQT_BINS);
+        self.kernels = (0..CQT_BINS)
+            .map(|bin| {
+                let frequency = midi_to_hz(CQT_LOWEST_MIDI + bin as i32);
+                let n = wanted_length(bin).min(len);
+                let window = (0..n)
+                    .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / n as f32).cos())
+                    .collect::<Vec<_>>();
+                // a sine of amplitude 1 comes out as 1
+                let gain = 2.0 / window.iter().sum::<f32>();
+                let kernel = window
+                    .iter()
+                    .enumerate()
+                    .map(|(i, w)| {
+                        let phase = 2.0 * PI * frequency * i as f32 / sample_rate as f32;
+                        (w * gain * phase.cos(), -w * gain * phase.sin())
+                    })
+                    .collect();
+                (len - n, kernel)
+            })
+            .collect();
+    }
+}
+
+/// Folds constant-q amplitudes into the 12 pitch classes, C first, scaled so
+/// the strongest is 1. All zeros for silence.
+pub fn chroma(cqt: &[f32]) -> [f32; 12] {
+    let mut chroma = [0.0; 12];
+    for (i, amplitude) in cqt.iter().enumerate() {
+        chroma[(CQT_LOWEST_MIDI as usize + i) % 12] += amplitude * amplitude;
+    }
+    let max = chroma.iter().copied().fold(0.0, f32::max);
+    if max > 0.0 {
+        chroma.iter_mut().for_each(|c| *c /= max);
+    }
+    chroma
+}
+
+/// Frequency of a midi note in equal temperament with A4 at 440 Hz
+pub fn midi_to_hz(midi_note_number: i32) -> f32 {
+    440.0 * 2.0_f32.powf((midi_note_number - 69) as f32 / 12.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, sine};
+
+    #[test]
+    fn a_sine_lands_in_its_semitone_at_full_scale() {
+        let cqt = ConstantQ::default().apply(&sine(440.0, 4096), SAMPLE_RATE);
+        let a4 = (69 - CQT_LOWEST_MIDI) as usize;
+        assert_near(cqt[a4], 1.0, 0.05);
+        // the hann window's main lobe reaches halfway into the next semitones
+        // and stops at the ones after
+        assert!(
+            cqt[a4 - 1] < 0.6 && cqt[a4 + 1] < 0.6,
+            "{:?}",
+            &cqt[a4 - 2..=a4 + 2]
+        );
+        assert!(
+            cqt[a4 - 2] < 0.1 && cqt[a4 + 2] < 0.1,
+            "{:?}",
+            &cqt[a4 - 2..=a4 + 2]
+        );
+    }
+
+    #[test]
+    fn kernels_below_the_window_length_are_not_constant_q() {
+        let mut cqt = ConstantQ::default();
+        cqt.apply(&[0.0; 4096], SAMPLE_RATE);
+        let from = cqt.constant_q_from() as i32;
+        // about 181 Hz for 4096 samples at 44.1 kHz
+        assert!(midi_to_hz(CQT_LOWEST_MIDI + from) >= 181.0);
+        assert!(midi_to_hz(CQT_LOWEST_MIDI + from - 1) < 181.0);
+        // every kernel fits in the window
+        assert!(
+            cqt.kernels
+                .iter()
+                .all(|(start, kernel)| start + kernel.len() == 4096)
+        );
+    }
+
+    #[test]
+    fn chroma_folds_the_octaves_together() {
+        let samples = sine(220.0, 8192)
+            .iter()
+            .zip(sine(880.0, 8192))
+            .map(|(a, b)| (a + b) / 2.0)
+            .collect::<Vec<_>>();
+        let chroma = chroma(&ConstantQ::default().apply(&samples, SAMPLE_RATE));
+        let loudest = (0..12)
+            .max_by(|a, b| chroma[*a].total_cmp(&chroma[*b]))
+            .unwrap();
+        // A is the tenth pitch class from C
+        assert_eq!(loudest, 9);
+        assert_eq!(chroma[9], 1.0);
+    }
+
+    #[test]
+    fn silence_has_no_chroma() {
+        assert_eq!(chroma(&[0.0; CQT_BINS]), [0.0; 12]);
+    }
+}
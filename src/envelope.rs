@@ -0,0 +1,184 @@
+use std::time::Duration;
+
+/// Samples per level reading, ~12 ms at 44.1 kHz
+const ENVELOPE_HOP: usize = 512;
+/// Quieter than this (dBFS) and no note is sounding
+const NOTE_MIN_DB: f32 = -50.0;
+/// The attack goes on while the level keeps rising by more than this per hop
+const ATTACK_MIN_RISE_DB: f32 = 0.5;
+/// The release starts once the level is this far below the note's peak
+const RELEASE_DROP_DB: f32 = 10.0;
+/// The note is over once the level is this far below its peak
+const NOTE_END_DROP_DB: f32 = 40.0;
+/// A jump this big from one hop to the next is a new note, even without a
+/// gap in between
+const REATTACK_RISE_DB: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnvelopePhase {
+    /// Getting louder, up to the note's peak
+    Attack,
+    /// Held near the peak
+    Sustain,
+    /// Dying away after being let go
+    Release,
+}
+
+impl EnvelopePhase {
+    pub fn name(self) -> &'static str {
+        match self {
+            EnvelopePhase::Attack => "attack",
+            EnvelopePhase::Sustain => "sustain",
+            EnvelopePhase::Release => "release",
+        }
+    }
+}
+
+/// Where the note that's sounding is in its envelope, and how long each
+/// phase has lasted so far
+#[derive(Clone, Copy, Debug)]
+pub struct NoteEnvelope {
+    pub phase: EnvelopePhase,
+    pub attack: Duration,
+    pub sustain: Duration,
+    pub release: Duration,
+    /// Loudest level of the note so far, in dBFS
+    pub peak_db: f32,
+}
+
+impl NoteEnvelope {
+    /// How long the note has been played for, before it was let go
+    pub fn held(&self) -> Duration {
+        self.attack + self.sustain
+    }
+}
+
+/// Follows the loudness of one channel every ~12 ms and cuts it into notes,
+/// each into attack, sustain and release
+#[derive(Default)]
+pub struct EnvelopeTracker {
+    note: Option<NoteEnvelope>,
+    /// Level of the previous hop in dBFS
+    previous_db: Option<f32>,
+}
+
+impl EnvelopeTracker {
+    /// Takes the next samples, in windows of any multiple of ~512, and gives
+    /// the envelope of the note sounding at their end. `onset` says a note
+    /// was attacked in them, which starts a new one even when the level
+    /// doesn't jump, like a legato change of note.
+    pub fn add(&mut self, samples: &[f32], sample_rate: u32, onset: bool) -> Option<NoteEnvelope> {
+        let hop = Duration::from_secs_f32(ENVELOPE_HOP as f32 / sample_rate as f32);
+        for (i, chunk) in samples.chunks_exact(ENVELOPE_HOP).enumerate() {
+            let mean_square = chunk.iter().map(|s| s * s).sum::<f32>() / ENVELOPE_HOP as f32;
+            let level = 10.0 * (mean_square + 1e-12).log10();
+            let jumped = self
+                .previous_db
+                .is_some_and(|previous| level - previous >= REATTACK_RISE_DB);
+            self.previous_db = Some(level);
+            // an attack jumps up too, that's the same note still
+            let reattacked = (jumped || onset && i == 0)
+                && self
+                    .note
+                    .is_some_and(|note| note.phase != EnvelopePhase::Attack);
+            if let Some(note) = self.note.as_ref()
+                && (level < NOTE_MIN_DB || level < note.peak_db - NOTE_END_DROP_DB)
+            {
+                self.note = None;
+            }
+            let note = match self.note.as_mut() {
+                Some(note) if !reattacked => note,
+                _ => {
+                    if level >= NOTE_MIN_DB {
+                        self.note = Some(NoteEnvelope {
+                            phase: EnvelopePhase::Attack,
+                            attack: hop,
+                            sustain: Duration::ZERO,
+                            release: Duration::ZERO,
+                            peak_db: level,
+                        });
+                    }
+                    continue;
+                }
+            };
+            if note.phase == EnvelopePhase::Attack && level <= note.peak_db + ATTACK_MIN_RISE_DB {
+                // it stopped getting louder
+                note.phase = EnvelopePhase::Sustain;
+            }
+            if note.phase == EnvelopePhase::Sustain && level < note.peak_db - RELEASE_DROP_DB {
+                note.phase = EnvelopePhase::Release;
+            }
+            note.peak_db = note.peak_db.max(level);
+            match note.phase {
+                EnvelopePhase::Attack => note.attack += hop,
+                EnvelopePhase::Sustain => note.sustain += hop,
+                EnvelopePhase::Release => note.release += hop,
+            }
+        }
+        self.note
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, sine};
+
+    /// `secs` of A4 with its amplitude following `envelope(t)`
+    fn enveloped(secs: f32, envelope: impl Fn(f32) -> f32) -> Vec<f32> {
+        sine(440.0, (secs * SAMPLE_RATE as f32) as usize)
+            .into_iter()
+            .enumerate()
+            .map(|(i, sample)| sample * envelope(i as f32 / SAMPLE_RATE as f32))
+            .collect()
+    }
+
+    /// A note rising to half scale for 100 ms, then held
+    fn attacked(secs: f32) -> Vec<f32> {
+        enveloped(secs, |t| 0.5 * (t / 0.1).min(1.0))
+    }
+
+    /// The envelope after feeding `samples` in analysis windows
+    fn track(tracker: &mut EnvelopeTracker, samples: &[f32]) -> Option<NoteEnvelope> {
+        samples
+            .chunks(4096)
+            .map(|window| tracker.add(window, SAMPLE_RATE, false))
+            .last()?
+    }
+
+    #[test]
+    fn an_attacked_note_is_sustained() {
+        let note = track(&mut EnvelopeTracker::default(), &attacked(0.5)).unwrap();
+        assert_eq!(note.phase, EnvelopePhase::Sustain);
+        // within a hop or two of it
+        assert_near(note.attack.as_secs_f32(), 0.1, 0.03);
+        assert_near(note.held().as_secs_f32(), 0.5, 0.03);
+        // half scale is 6 dB below a full scale sine's -3 dBFS
+        assert_near(note.peak_db, -9.03, 0.1);
+    }
+
+    #[test]
+    fn a_note_let_go_is_released_then_over() {
+        let mut tracker = EnvelopeTracker::default();
+        track(&mut tracker, &attacked(0.5));
+        // dying away at 60 dB a second
+        let decay = enveloped(1.0, |t| 0.5 * 10.0_f32.powf(-3.0 * t));
+        let note = track(&mut tracker, &decay[..SAMPLE_RATE as usize / 4]).unwrap();
+        assert_eq!(note.phase, EnvelopePhase::Release);
+        assert!(note.release > Duration::ZERO, "{note:?}");
+        assert!(track(&mut tracker, &decay[SAMPLE_RATE as usize / 4..]).is_none());
+    }
+
+    #[test]
+    fn a_jump_in_level_is_a_new_note() {
+        let mut tracker = EnvelopeTracker::default();
+        track(&mut tracker, &enveloped(0.5, |_| 0.1));
+        let note = track(&mut tracker, &enveloped(0.1, |_| 0.5)).unwrap();
+        assert!(note.held() < Duration::from_secs_f32(0.2), "{note:?}");
+    }
+
+    #[test]
+    fn silence_is_no_note() {
+        assert!(track(&mut EnvelopeTracker::default(), &[0.0; 8192]).is_none());
+    }
+}
@@ -0,0 +1,102 @@
+use realfft::num_complex::Complex;
+
+/// Lowest and highest frequency the flatness looks at, where instruments and
+/// voices put their energy
+pub const FLATNESS_RANGE: (f32, f32) = (50.0, 5000.0);
+/// Share of the power the rolloff frequency has below it
+const ROLLOFF_SHARE: f32 = 0.85;
+
+/// Numbers describing the shape of a window's spectrum, the usual inputs for
+/// telling sounds apart
+#[derive(Clone, Copy, Debug)]
+pub struct SpectralFeatures {
+    /// Center of mass of the magnitude spectrum in Hz, higher for brighter
+    /// sounds
+    pub centroid: f32,
+    /// Frequency in Hz below which 85% of the power is
+    pub rolloff: f32,
+    /// How much the spectrum grew since the previous window of the same
+    /// channel, big at note onsets
+    pub flux: f32,
+    /// How noise-like the window is, from 0 (a pure tone) to 1 (white noise)
+    pub flatness: f32,
+}
+
+impl Default for SpectralFeatures {
+    fn default() -> Self {
+        Self {
+            centroid: 0.0,
+            rolloff: 0.0,
+            flux: 0.0,
+            flatness: 1.0,
+        }
+    }
+}
+
+impl SpectralFeatures {
+    /// Features of the bins of an `n` point fft up to the nyquist frequency.
+    /// The flux needs the previous window, so it's left at 0 here.
+    pub fn new(spectrum: &[Complex<f32>], sample_rate: u32, n: usize) -> Self {
+        let bin_width = sample_rate as f32 / n as f32;
+        let (weighted_sum, magnitude_sum) = spectrum.iter().enumerate().fold(
+            (0.0, 0.0),
+            |(weighted_sum, magnitude_sum), (i, bin)| {
+                let magnitude = bin.norm();
+                (
+                    weighted_sum + i as f32 * bin_width * magnitude,
+                    magnitude_sum + magnitude,
+                )
+            },
+        );
+        let centroid = if magnitude_sum > 0.0 {
+            weighted_sum / magnitude_sum
+        } else {
+            0.0
+        };
+        let total_power = spectrum.iter().map(|bin| bin.norm_sqr()).sum::<f32>();
+        let mut power_so_far = 0.0;
+        let rolloff_bin = spectrum
+            .iter()
+            .position(|bin| {
+                power_so_far += bin.norm_sqr();
+                power_so_far >= total_power * ROLLOFF_SHARE
+            })
+            .unwrap_or(0);
+        Self {
+            centroid,
+            rolloff: if total_power > 0.0 {
+                rolloff_bin as f32 * bin_width
+            } else {
+                0.0
+            },
+            flux: 0.0,
+            flatness: spectral_flatness(spectrum, sample_rate, n),
+        }
+    }
+}
+
+/// Geometric over arithmetic mean of the power spectrum: close to 0 for pitched
+/// sounds, which pile their energy into a few harmonics, and towards 1 for noise.
+fn spectral_flatness(spectrum: &[Complex<f32>], sample_rate: u32, n: usize) -> f32 {
+    let bin_width = sample_rate as f32 / n as f32;
+    let first = (FLATNESS_RANGE.0 / bin_width).ceil() as usize;
+    let last = ((FLATNESS_RANGE.1 / bin_width) as usize).min(spectrum.len() - 1);
+    if first > last {
+        return 1.0;
+    }
+    flatness(spectrum[first..=last].iter().map(|bin| bin.norm_sqr()))
+}
+
+/// Geometric over arithmetic mean of some powers
+pub fn flatness(powers: impl ExactSizeIterator<Item = f32>) -> f32 {
+    let count = powers.len() as f64;
+    if count == 0.0 {
+        return 1.0;
+    }
+    let (log_sum, sum) = powers
+        .map(|power| (power as f64).max(1e-20))
+        .fold((0.0, 0.0), |(log_sum, sum), power| {
+            (log_sum + power.ln(), sum + power)
+        });
+    ((log_sum / count).exp() / (sum / count)) as f32
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::VecDeque,
+    f64::consts::{FRAC_1_SQRT_2, PI},
+};
+
+/// Cutoff of the anti-aliasing low-pass before decimating, in parts of the
+/// decimated nyquist frequency. What's above it folds back down, so the
+/// filter gets an octave to fall off in
+const DECIMATION_CUTOFF: f64 = 0.5;
+/// Low-pass sections the anti-aliasing filter chains, 12 dB per octave each
+const DECIMATION_SECTIONS: usize = 3;
+
+/// Second order iir filter (direct form I)
+#[derive(Clone)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// Butterworth high-pass, from the audio eq cookbook
+    pub fn high_pass(cutoff: f64, sample_rate: f64) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * FRAC_1_SQRT_2);
+        let cos_w0 = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self::new(
+            [
+                (1.0 + cos_w0) / 2.0 / a0,
+                -(1.0 + cos_w0) / a0,
+                (1.0 + cos_w0) / 2.0 / a0,
+            ],
+            [-2.0 * cos_w0 / a0, (1.0 - alpha) / a0],
+        )
+    }
+
+    /// Butterworth low-pass, from the audio eq cookbook
+    pub fn low_pass(cutoff: f64, sample_rate: f64) -> Self {
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * FRAC_1_SQRT_2);
+        let cos_w0 = w0.cos();
+        let a0 = 1.0 + alpha;
+        Self::new(
+            [
+                (1.0 - cos_w0) / 2.0 / a0,
+                (1.0 - cos_w0) / a0,
+                (1.0 - cos_w0) / 2.0 / a0,
+            ],
+            [-2.0 * cos_w0 / a0, (1.0 - alpha) / a0],
+        )
+    }
+
+    /// The first K-weighting stage, with the standard's coefficients
+    /// recalculated for any sample rate
+    pub fn k_shelf(sample_rate: f64) -> Self {
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10.0_f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    /// The second K-weighting stage, a high-pass at about 38 Hz
+    pub fn k_high_pass(sample_rate: f64) -> Self {
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Self::new(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        )
+    }
+
+    pub fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// Low-passes a signal and keeps every few samples of it, so a window of the
+/// same length covers that many times as long and its fft bins get that much
+/// narrower. Remembers a window of the decimated signal, so every window that
+/// comes in still gives one to analyze, just overlapping the previous ones.
+pub struct Decimator {
+    /// Cutoff of the low-pass, in parts of the decimated nyquist frequency
+    cutoff: f64,
+    filters: Vec<Biquad>,
+    factor: usize,
+    sample_rate: u32,
+    /// Input samples left to skip before the next one is kept
+    skip: usize,
+    window: VecDeque<f32>,
+}
+
+impl Default for Decimator {
+    fn default() -> Self {
+        Self::new(DECIMATION_CUTOFF)
+    }
+}
+
+impl Decimator {
+    /// A decimator whose low-pass cuts off at `cutoff` of the decimated
+    /// nyquist frequency
+    pub fn new(cutoff: f64) -> Self {
+        Self {
+            cutoff,
+            filters: vec![],
+            factor: 0,
+            sample_rate: 0,
+            skip: 0,
+            window: VecDeque::new(),
+        }
+    }
+
+    /// Just `samples` decimated by `factor`, one for every `factor` of them,
+    /// as if nothing came before them
+    pub fn decimate_once(mut self, samples: &[f32], sample_rate: u32, factor: usize) -> Vec<f32> {
+        let window = self.add(samples, sample_rate, factor);
+        window[samples.len() - samples.len().div_ceil(factor)..].to_vec()
+    }
+
+    /// Takes a window of input at `sample_rate` and gives the latest window of
+    /// the same length decimated by `factor`, at `sample_rate / factor`. It
+    /// starts out as silence.
+    pub fn add(&mut self, samples: &[f32], sample_rate: u32, factor: usize) -> Vec<f32> {
+        if (factor, sample_rate) != (self.factor, self.sample_rate) {
+            let cutoff = self.cutoff * sample_rate as f64 / 2.0 / factor as f64;
+            self.filters = vec![Biquad::low_pass(cutoff, sample_rate as f64); DECIMATION_SECTIONS];
+            self.factor = factor;
+            self.sample_rate = sample_rate;
+            self.skip = 0;
+            self.window = VecDeque::from(vec![0.0; samples.len()]);
+        }
+        for sample in samples {
+            let filtered = self
+                .filters
+                .iter_mut()
+                .fold(*sample as f64, |x, filter| filter.process(x));
+            if self.skip == 0 {
+                self.window.push_back(filtered as f32);
+                self.skip = factor;
+            }
+            self.skip -= 1;
+        }
+        let excess = self.window.len().saturating_sub(samples.len());
+        self.window.drain(..excess);
+        self.window.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, sine};
+
+    const FACTOR: usize = 4;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// The rms of the decimated window once the filter settled
+    fn decimated_rms(frequency: f32) -> f32 {
+        let samples = sine(frequency, 4 * 4096);
+        let mut decimator = Decimator::default();
+        let mut window = vec![];
+        for chunk in samples.chunks(4096) {
+            window = decimator.add(chunk, SAMPLE_RATE, FACTOR);
+        }
+        rms(&window)
+    }
+
+    #[test]
+    fn decimator_keeps_what_fits_below_the_new_nyquist() {
+        // a full scale sine has an rms of 1/√2
+        assert_near(decimated_rms(440.0), 0.5_f32.sqrt(), 0.02);
+    }
+
+    #[test]
+    fn decimator_filters_out_what_would_alias() {
+        // above the decimated nyquist of 5512 Hz
+        let aliased = decimated_rms(8000.0);
+        assert!(aliased < 0.01, "{aliased}");
+    }
+
+    #[test]
+    fn decimator_keeps_one_sample_in_factor() {
+        let samples = sine(440.0, 1001);
+        let decimated = Decimator::default().decimate_once(&samples, SAMPLE_RATE, FACTOR);
+        assert_eq!(decimated.len(), 1001_usize.div_ceil(FACTOR));
+    }
+}
@@ -0,0 +1,164 @@
+use std::f32::consts::PI;
+
+use realfft::num_complex::Complex;
+
+use crate::{filter::Decimator, peaks::refine_peak};
+
+/// Rate the lpc runs at, the first formants are well below its nyquist
+const LPC_SAMPLE_RATE: f32 = 11025.0;
+/// Poles of the lpc model: two per formant below 5.5 kHz, and a couple for
+/// the tilt of the glottis and the lips
+const LPC_ORDER: usize = 12;
+/// Evens out the -6 dB/octave slope of voiced sound so the higher formants
+/// show up in the model
+const PRE_EMPHASIS: f32 = 0.97;
+/// Points the lpc envelope is evaluated at, from 0 Hz to its nyquist
+const ENVELOPE_POINTS: usize = 512;
+/// Peaks of the envelope below this are the fundamental, not a formant
+const FORMANT_MIN_HZ: f32 = 150.0;
+/// Cutoff of the low-pass before dropping samples, in parts of the lpc's
+/// nyquist frequency. Higher than the spectrum's decimation, the second
+/// formant of a bright vowel is up near 3 kHz
+const ANTI_ALIAS_CUTOFF: f64 = 0.9;
+
+/// The two lowest resonances of the vocal tract, which tell the vowels apart
+#[derive(Clone, Copy, Debug)]
+pub struct Formants {
+    /// Goes up the more open the mouth is
+    pub f1: f32,
+    /// Goes up the further forward the tongue is
+    pub f2: f32,
+}
+
+impl Formants {
+    /// F1 and F2 of a voiced window, the first two peaks of its lpc spectral
+    /// envelope. `None` when the envelope doesn't have two.
+    pub fn estimate(samples: &[f32], sample_rate: u32) -> Option<Self> {
+        let factor = (sample_rate as f32 / LPC_SAMPLE_RATE).round().max(1.0) as usize;
+        let lpc_rate = sample_rate as f32 / factor as f32;
+        let decimated = if factor > 1 {
+            Decimator::new(ANTI_ALIAS_CUTOFF).decimate_once(samples, sample_rate, factor)
+        } else {
+            samples.to_vec()
+        };
+        if decimated.len() <= LPC_ORDER {
+            return None;
+        }
+        // pre-emphasized and hamming windowed
+        let last = (decimated.len() - 1) as f32;
+        let frame = decimated
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                let previous = if i > 0 { decimated[i - 1] } else { 0.0 };
+                let window = 0.54 - 0.46 * (2.0 * PI * i as f32 / last).cos();
+                (sample - PRE_EMPHASIS * previous) * window
+            })
+            .collect::<Vec<_>>();
+        let autocorrelation = (0..=LPC_ORDER)
+            .map(|lag| {
+                frame[lag..]
+                    .iter()
+                    .zip(&frame)
+                    .map(|(a, b)| a * b)
+                    .sum::<f32>()
+            })
+            .collect::<Vec<_>>();
+        let coefficients = levinson_durbin(&autocorrelation)?;
+        let envelope = (0..ENVELOPE_POINTS)
+            .map(|k| {
+                let omega = PI * k as f32 / ENVELOPE_POINTS as f32;
+                let response = coefficients
+                    .iter()
+                    .enumerate()
+                    .map(|(i, a)| Complex::from_polar(*a, -omega * i as f32))
+                    .sum::<Complex<f32>>();
+                1.0 / response.norm_sqr().max(1e-20)
+            })
+            .collect::<Vec<_>>();
+        let hz_per_point = lpc_rate / 2.0 / ENVELOPE_POINTS as f32;
+        let mut peaks = (1..ENVELOPE_POINTS - 1)
+            .filter(|&k| envelope[k] > envelope[k - 1] && envelope[k] >= envelope[k + 1])
+            .map(|k| (k as f32 + refine_peak(&envelope, k).0) * hz_per_point)
+            .filter(|frequency| *frequency >= FORMANT_MIN_HZ);
+        Some(Self {
+            f1: peaks.next()?,
+            f2: peaks.next()?,
+        })
+    }
+}
+
+/// Coefficients of the all-pole model 1 / (1 + a1 z^-1 + ... + ap z^-p) that
+/// best predicts a signal with this `autocorrelation`, the first being 1.
+/// `None` for silence.
+fn levinson_durbin(autocorrelation: &[f32]) -> Option<Vec<f32>> {
+    let order = autocorrelation.len() - 1;
+    let mut coefficients = vec![0.0; order + 1];
+    coefficients[0] = 1.0;
+    let mut error = autocorrelation[0];
+    for i in 1..=order {
+        if error <= 0.0 {
+            return None;
+        }
+        let reflection = -(0..i)
+            .map(|j| coefficients[j] * autocorrelation[i - j])
+            .sum::<f32>()
+            / error;
+        let previous = coefficients.clone();
+        for j in 1..i {
+            coefficients[j] += reflection * previous[i - j];
+        }
+        coefficients[i] = reflection;
+        error *= 1.0 - reflection * reflection;
+    }
+    Some(coefficients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near};
+
+    /// A pulse train at `pitch` through a resonance at each of `formants`, a
+    /// crude voice singing a vowel
+    fn vowel(pitch: f32, formants: &[f32]) -> Vec<f32> {
+        let period = (SAMPLE_RATE as f32 / pitch) as usize;
+        let mut samples = (0..4096)
+            .map(|i| if i % period == 0 { 1.0 } else { 0.0 })
+            .collect::<Vec<f32>>();
+        for formant in formants {
+            // two poles, 80 Hz wide
+            let radius = (-PI * 80.0 / SAMPLE_RATE as f32).exp();
+            let theta = 2.0 * PI * formant / SAMPLE_RATE as f32;
+            let (a1, a2) = (2.0 * radius * theta.cos(), -radius * radius);
+            let (mut y1, mut y2) = (0.0, 0.0);
+            for sample in samples.iter_mut() {
+                let y = *sample + a1 * y1 + a2 * y2;
+                (y2, y1) = (y1, y);
+                *sample = y;
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn formants_of_an_ah() {
+        let formants =
+            Formants::estimate(&vowel(120.0, &[730.0, 1090.0, 2440.0]), SAMPLE_RATE).unwrap();
+        assert_near(formants.f1, 730.0, 60.0);
+        assert_near(formants.f2, 1090.0, 80.0);
+    }
+
+    #[test]
+    fn formants_of_an_ee() {
+        let formants =
+            Formants::estimate(&vowel(120.0, &[270.0, 2290.0, 3010.0]), SAMPLE_RATE).unwrap();
+        assert_near(formants.f1, 270.0, 60.0);
+        assert_near(formants.f2, 2290.0, 120.0);
+    }
+
+    #[test]
+    fn no_formants_in_silence() {
+        assert!(Formants::estimate(&[0.0; 4096], SAMPLE_RATE).is_none());
+    }
+}
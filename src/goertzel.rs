@@ -0,0 +1,133 @@
+use crate::{cqt::midi_to_hz, peaks::refine_peak};
+
+/// Most notes the goertzel mode listens for, four octaves
+pub const GOERTZEL_NOTES: usize = 48;
+/// Lowest note listened for when the instrument has no lower limit, C2
+const GOERTZEL_DEFAULT_LOWEST_MIDI: i32 = 36;
+/// Lowest note listened for at all, the piano's A0
+const GOERTZEL_LOWEST_MIDI: i32 = 21;
+/// Semitones above a note where its harmonics 2 to 6 land
+const HARMONIC_SEMITONES: [i32; 5] = [12, 19, 24, 28, 31];
+/// Cents either side of the loudest note its pitch is refined between, the
+/// refined pitch lands within half of it
+const REFINE_CENTS: f32 = 50.0;
+
+/// The magnitude of one frequency in `samples`, tapered by `window`, by the
+/// goertzel algorithm: a single fft bin for a handful of multiplies a sample.
+/// On the same scale as the fft's magnitudes.
+pub fn goertzel(samples: &[f32], window: &[f32], sample_rate: u32, frequency: f32) -> f32 {
+    let omega = 2.0 * std::f32::consts::PI * frequency / sample_rate as f32;
+    let coefficient = 2.0 * omega.cos();
+    let (s1, s2) = samples
+        .iter()
+        .zip(window)
+        .fold((0.0, 0.0), |(s1, s2), (sample, w)| {
+            (sample * w + coefficient * s1 - s2, s1)
+        });
+    (s1 * s1 + s2 * s2 - coefficient * s1 * s2).max(0.0).sqrt()
+}
+
+/// Midi notes listened for in `pitch_range` (Hz), up to `GOERTZEL_NOTES`
+/// from the bottom
+pub fn goertzel_notes(pitch_range: (f32, f32)) -> std::ops::RangeInclusive<i32> {
+    let hz_to_midi = |hz: f32| 69.0 + 12.0 * (hz / 440.0).log2();
+    let lowest = if pitch_range.0 > 0.0 {
+        (hz_to_midi(pitch_range.0).ceil() as i32).max(GOERTZEL_LOWEST_MIDI)
+    } else {
+        GOERTZEL_DEFAULT_LOWEST_MIDI
+    };
+    let highest =
+        (lowest + GOERTZEL_NOTES as i32 - 1).min(hz_to_midi(pitch_range.1).floor() as i32);
+    lowest..=highest
+}
+
+/// The loudest of `notes` refined to a fraction of a semitone, and how much of
+/// the energy of all the notes it and its harmonics have. `magnitudes` holds a
+/// magnitude per note.
+pub fn goertzel_pitch(
+    samples: &[f32],
+    window: &[f32],
+    sample_rate: u32,
+    notes: &std::ops::RangeInclusive<i32>,
+    magnitudes: &[f32],
+) -> Option<(f32, f32)> {
+    let (loudest, _) = magnitudes
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    let total = magnitudes.iter().map(|m| m * m).sum::<f32>();
+    if total <= 0.0 {
+        return None;
+    }
+    let on_harmonics = std::iter::once(0)
+        .chain(HARMONIC_SEMITONES)
+        .filter_map(|semitones| magnitudes.get(loudest + semitones as usize))
+        .map(|m| m * m)
+        .sum::<f32>();
+    let frequency = midi_to_hz(notes.start() + loudest as i32);
+    // parabola through the log magnitudes a quarter tone either side
+    let at_cents = |cents: f32| {
+        goertzel(
+            samples,
+            window,
+            sample_rate,
+            frequency * 2.0_f32.powf(cents / 1200.0),
+        )
+    };
+    let around = [
+        at_cents(-REFINE_CENTS),
+        at_cents(0.0),
+        at_cents(REFINE_CENTS),
+    ];
+    let (shift, _) = refine_peak(&around, 1);
+    Some((
+        frequency * 2.0_f32.powf(shift * REFINE_CENTS / 1200.0),
+        on_harmonics / total,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, hann, sine};
+
+    const LEN: usize = 4096;
+
+    #[test]
+    fn goertzel_gives_a_full_scale_sine_half_the_window() {
+        let window = hann(LEN);
+        // right on an fft bin, so there's no scalloping
+        let frequency = 40.0 * SAMPLE_RATE as f32 / LEN as f32;
+        let samples = sine(frequency, LEN);
+        let full_scale = window.iter().sum::<f32>() / 2.0;
+        let magnitude = goertzel(&samples, &window, SAMPLE_RATE, frequency);
+        assert_near(magnitude / full_scale, 1.0, 0.01);
+        // and next to nothing a few bins away
+        let off = goertzel(&samples, &window, SAMPLE_RATE, frequency * 1.1);
+        assert!(off < full_scale * 0.01, "{off}");
+    }
+
+    #[test]
+    fn goertzel_notes_stay_in_the_range() {
+        // from E2, midi 40, and E6 is one past the four octaves listened for
+        assert_eq!(goertzel_notes((82.0, 1320.0)), 40..=87);
+        assert_eq!(goertzel_notes((0.0, f32::INFINITY)).count(), GOERTZEL_NOTES);
+    }
+
+    #[test]
+    fn goertzel_pitch_refines_between_the_notes() {
+        let window = hann(LEN);
+        let notes = goertzel_notes((100.0, 2000.0));
+        // a quarter tone above A4
+        let frequency = 440.0 * 2.0_f32.powf(0.25 / 12.0);
+        let samples = sine(frequency, LEN);
+        let magnitudes = notes
+            .clone()
+            .map(|note| goertzel(&samples, &window, SAMPLE_RATE, midi_to_hz(note)))
+            .collect::<Vec<_>>();
+        let (pitch, harmonic_share) =
+            goertzel_pitch(&samples, &window, SAMPLE_RATE, &notes, &magnitudes).unwrap();
+        assert_near(1200.0 * (pitch / frequency).log2(), 0.0, 10.0);
+        assert!(harmonic_share > 0.5, "{harmonic_share}");
+    }
+}
@@ -0,0 +1,27 @@
+use crate::tuning::Tuning;
+
+/// Midi notes of the open strings in standard tuning, low E first
+pub const STANDARD_TUNING: [i32; 6] = [40, 45, 50, 55, 59, 64];
+/// Frets the fretboard shows, open strings aside
+pub const FRETBOARD_FRETS: usize = 15;
+/// Within this many cents of its note a string is in tune
+pub const IN_TUNE_CENTS: f32 = 5.0;
+/// Further than this from every open string, what's playing isn't one: a
+/// fretted note, or a string out by more than a semitone and a half that needs
+/// tuning by ear first
+const STRING_MAX_CENTS: f32 = 150.0;
+
+/// The open string of standard tuning `frequency` is closest to, low E being
+/// 0, and how many cents sharp of it it is. The strings are tuned equal
+/// tempered to the reference pitch of `tuning`.
+pub fn nearest_string(frequency: f32, tuning: &Tuning) -> Option<(usize, f32)> {
+    if !(frequency > 0.0 && frequency.is_finite()) {
+        return None;
+    }
+    STANDARD_TUNING
+        .iter()
+        .map(|midi| 1200.0 * (frequency / tuning.equal_tempered_hz(*midi)).log2())
+        .enumerate()
+        .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+        .filter(|(_, cents)| cents.abs() <= STRING_MAX_CENTS)
+}
@@ -0,0 +1,182 @@
+use crate::peaks::refine_peak;
+
+/// Harmonics looked for above the fundamental, the fundamental included
+const HARMONIC_COUNT: usize = 10;
+/// How far from n times the fundamental a partial is looked for, in parts of
+/// that frequency. Stiff strings go sharp by a few percent up there
+const HARMONIC_SEARCH: f32 = 0.03;
+/// Peaks weaker than this, in parts of the strongest harmonic, don't count
+const HARMONIC_MIN_SHARE: f32 = 0.001;
+/// Partials needed to fit the inharmonicity
+const INHARMONICITY_MIN_PARTIALS: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Harmonic {
+    /// 1 for the fundamental
+    pub number: usize,
+    pub frequency: f32,
+    pub magnitude: f32,
+    /// How far the partial is from `number` times the fundamental
+    pub deviation_cents: f32,
+}
+
+/// The partials of a note, found near the multiples of its fundamental
+#[derive(Clone, Debug, Default)]
+pub struct HarmonicSeries {
+    pub harmonics: Vec<Harmonic>,
+    /// B in f_n = n f_0 sqrt(1 + B n^2), how much the partials stretch above
+    /// the harmonic series. 0 for an ideal string, around 1e-4 to 1e-3 for
+    /// piano strings. `None` when too few partials were found to tell
+    pub inharmonicity: Option<f32>,
+}
+
+impl HarmonicSeries {
+    /// Finds the harmonics of `fundamental` in the magnitudes of an fft with
+    /// bins `bin_width` Hz apart, of a window padded to `zero_padding` times
+    /// its length
+    pub fn new(magnitudes: &[f32], fundamental: f32, bin_width: f32, zero_padding: usize) -> Self {
+        if fundamental <= 0.0 {
+            return Self::default();
+        }
+        let mut harmonics = (1..=HARMONIC_COUNT)
+            .filter_map(|number| {
+                let expected = number as f32 * fundamental;
+                let spread =
+                    (expected * HARMONIC_SEARCH / bin_width).max(2.0 * zero_padding as f32);
+                let first = ((expected / bin_width - spread).floor() as usize).max(1);
+                let last = ((expected / bin_width + spread).ceil() as usize)
+                    .min(magnitudes.len().checked_sub(2)?);
+                let peak =
+                    (first..=last).max_by(|a, b| magnitudes[*a].total_cmp(&magnitudes[*b]))?;
+                // the edge of the search isn't a peak, just the slope of another
+                if peak == first || peak == last {
+                    return None;
+                }
+                let (shift, magnitude) = refine_peak(magnitudes, peak);
+                let frequency = (peak as f32 + shift) * bin_width;
+                Some(Harmonic {
+                    number,
+                    frequency,
+                    magnitude,
+                    deviation_cents: 1200.0 * (frequency / expected).log2(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let strongest = harmonics
+            .iter()
+            .map(|harmonic| harmonic.magnitude)
+            .fold(0.0, f32::max);
+        harmonics.retain(|harmonic| harmonic.magnitude >= strongest * HARMONIC_MIN_SHARE);
+        let inharmonicity = inharmonicity(&harmonics);
+        Self {
+            harmonics,
+            inharmonicity,
+        }
+    }
+}
+
+/// Least squares fit of (f_n / n)^2 = f_0^2 + f_0^2 B n^2, a line in n^2
+fn inharmonicity(harmonics: &[Harmonic]) -> Option<f32> {
+    if harmonics.len() < INHARMONICITY_MIN_PARTIALS {
+        return None;
+    }
+    let points = harmonics
+        .iter()
+        .map(|harmonic| {
+            let n = harmonic.number as f64;
+            (n * n, (harmonic.frequency as f64 / n).powi(2))
+        })
+        .collect::<Vec<_>>();
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let (covariance, variance) =
+        points
+            .iter()
+            .fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+                (
+                    covariance + (x - mean_x) * (y - mean_y),
+                    variance + (x - mean_x) * (x - mean_x),
+                )
+            });
+    let slope = covariance / variance;
+    let intercept = mean_y - slope * mean_x;
+    (intercept > 0.0).then(|| (slope / intercept) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, magnitudes, sine};
+
+    const LEN: usize = 8192;
+
+    /// The harmonic series of a window with a partial at each of `partials`
+    /// (number, frequency), falling off as 1/n
+    fn series(fundamental: f32, partials: impl Iterator<Item = (usize, f32)>) -> HarmonicSeries {
+        let mut samples = vec![0.0; LEN];
+        for (number, frequency) in partials {
+            let partial = sine(frequency, LEN);
+            samples
+                .iter_mut()
+                .zip(partial)
+                .for_each(|(sample, partial)| *sample += partial / number as f32);
+        }
+        let bin_width = SAMPLE_RATE as f32 / LEN as f32;
+        HarmonicSeries::new(&magnitudes(&samples), fundamental, bin_width, 1)
+    }
+
+    #[test]
+    fn a_harmonic_tone_has_every_harmonic_in_place() {
+        let series = series(220.0, (1..=10).map(|n| (n, n as f32 * 220.0)));
+        assert_eq!(series.harmonics.len(), HARMONIC_COUNT);
+        for (n, harmonic) in series.harmonics.iter().enumerate() {
+            assert_eq!(harmonic.number, n + 1);
+            assert_near(harmonic.deviation_cents, 0.0, 2.0);
+        }
+        // each partial is half as loud at twice the number
+        assert_near(
+            series.harmonics[1].magnitude / series.harmonics[0].magnitude,
+            0.5,
+            0.05,
+        );
+        assert_near(series.inharmonicity.unwrap(), 0.0, 1e-5);
+    }
+
+    #[test]
+    fn a_stiff_string_goes_sharp_up_the_series() {
+        let b = 4e-4;
+        let series = series(
+            220.0,
+            (1..=10).map(|n| {
+                let n_squared = (n * n) as f32;
+                (n, n as f32 * 220.0 * (1.0 + b * n_squared).sqrt())
+            }),
+        );
+        let tenth = series.harmonics.last().unwrap();
+        assert_eq!(tenth.number, 10);
+        // 1200 log2(sqrt(1 + 100 B)), 34 cents sharp
+        assert!(tenth.deviation_cents > 30.0, "{tenth:?}");
+        assert_near(series.inharmonicity.unwrap(), b, 5e-5);
+    }
+
+    #[test]
+    fn missing_harmonics_are_left_out() {
+        // a clarinet-like tone, odd harmonics only
+        let series = series(220.0, (1..=10).step_by(2).map(|n| (n, n as f32 * 220.0)));
+        assert!(
+            series
+                .harmonics
+                .iter()
+                .all(|harmonic| harmonic.number % 2 == 1)
+        );
+        assert_eq!(series.harmonics.len(), 5);
+    }
+
+    #[test]
+    fn no_harmonics_without_a_fundamental() {
+        let series = series(0.0, (1..=10).map(|n| (n, n as f32 * 220.0)));
+        assert!(series.harmonics.is_empty());
+        assert_eq!(series.inharmonicity, None);
+    }
+}
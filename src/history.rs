@@ -0,0 +1,146 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The analysis thread fills it, the ui reads it
+pub type SharedSpectrumHistory = Arc<Mutex<SpectrumHistory>>;
+
+/// One analyzed window's spectrum
+#[derive(Clone, Debug)]
+pub struct SpectrumFrame {
+    /// When the window was analyzed
+    pub time: Instant,
+    /// Magnitude of every fft bin from 0 Hz up to the nyquist frequency
+    pub magnitudes: Vec<f32>,
+}
+
+/// The last few spectra of each analyzed channel, oldest first: a time by
+/// frequency matrix for spectrograms and waterfalls.
+#[derive(Clone, Debug)]
+pub struct SpectrumHistory {
+    /// Frames kept per channel
+    depth: usize,
+    /// Frequency between neighbouring bins in Hz
+    bin_hz: f32,
+    channels: Vec<VecDeque<SpectrumFrame>>,
+}
+
+impl SpectrumHistory {
+    pub fn new(depth: usize, channels: usize) -> Self {
+        Self {
+            depth,
+            bin_hz: 0.0,
+            channels: vec![VecDeque::with_capacity(depth); channels],
+        }
+    }
+
+    pub fn shared(depth: usize, channels: usize) -> SharedSpectrumHistory {
+        Arc::new(Mutex::new(Self::new(depth, channels)))
+    }
+
+    /// Adds the magnitudes of a window of `channel`, dropping its oldest frame
+    /// once `depth` are kept
+    pub fn push(&mut self, channel: usize, bin_hz: f32, magnitudes: impl Iterator<Item = f32>) {
+        if self.depth == 0 {
+            return;
+        }
+        if channel >= self.channels.len() {
+            self.channels
+                .resize_with(channel + 1, || VecDeque::with_capacity(self.depth));
+        }
+        if bin_hz != self.bin_hz {
+            // a different sample rate, the old frames don't line up anymore
+            self.channels.iter_mut().for_each(VecDeque::clear);
+            self.bin_hz = bin_hz;
+        }
+        let frames = &mut self.channels[channel];
+        // reuse the oldest frame's buffer
+        let mut frame = if frames.len() == self.depth {
+            frames.pop_front().unwrap()
+        } else {
+            SpectrumFrame {
+                time: Instant::now(),
+                magnitudes: vec![],
+            }
+        };
+        frame.time = Instant::now();
+        frame.magnitudes.clear();
+        frame.magnitudes.extend(magnitudes);
+        frames.push_back(frame);
+    }
+
+    /// Frequency between neighbouring bins of the frames in Hz
+    pub fn bin_hz(&self) -> f32 {
+        self.bin_hz
+    }
+
+    /// Frames of `channel`, oldest first
+    pub fn frames(&self, channel: usize) -> impl ExactSizeIterator<Item = &SpectrumFrame> {
+        self.channels
+            .get(channel)
+            .map(|frames| frames.iter())
+            .unwrap_or_default()
+    }
+
+    /// Time between the oldest and the newest frame of `channel`
+    pub fn duration(&self, channel: usize) -> Duration {
+        let mut frames = self.frames(channel);
+        match (frames.next(), frames.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_magnitudes(history: &SpectrumHistory, channel: usize) -> Vec<f32> {
+        history
+            .frames(channel)
+            .map(|frame| frame.magnitudes[0])
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_frames_oldest_first() {
+        let mut history = SpectrumHistory::new(3, 1);
+        for i in 0..5 {
+            history.push(0, 10.0, std::iter::repeat_n(i as f32, 4));
+        }
+        assert_eq!(first_magnitudes(&history, 0), [2.0, 3.0, 4.0]);
+        assert!(history.frames(0).all(|frame| frame.magnitudes.len() == 4));
+    }
+
+    #[test]
+    fn channels_fill_up_on_their_own() {
+        let mut history = SpectrumHistory::new(3, 1);
+        history.push(0, 10.0, [1.0].into_iter());
+        history.push(2, 10.0, [2.0].into_iter());
+        assert_eq!(first_magnitudes(&history, 0), [1.0]);
+        assert_eq!(first_magnitudes(&history, 1), []);
+        assert_eq!(first_magnitudes(&history, 2), [2.0]);
+        assert_eq!(history.frames(3).len(), 0);
+        assert_eq!(history.duration(3), Duration::ZERO);
+    }
+
+    #[test]
+    fn a_new_bin_width_starts_over() {
+        let mut history = SpectrumHistory::new(3, 2);
+        history.push(0, 10.0, [1.0].into_iter());
+        history.push(1, 10.0, [1.0].into_iter());
+        history.push(0, 5.0, [2.0].into_iter());
+        assert_eq!(first_magnitudes(&history, 0), [2.0]);
+        assert_eq!(history.frames(1).len(), 0);
+    }
+
+    #[test]
+    fn no_depth_keeps_nothing() {
+        let mut history = SpectrumHistory::new(0, 1);
+        history.push(0, 10.0, [1.0].into_iter());
+        assert_eq!(history.frames(0).len(), 0);
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// What's being played, to tune the analysis to its range
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Instrument {
+    /// No assumptions, like before presets existed
+    #[default]
+    Any,
+    Guitar,
+    Bass,
+    Cello,
+    Violin,
+    Voice,
+    Whistle,
+}
+
+/// Analysis settings that suit an instrument
+#[derive(Clone, Copy, Debug)]
+pub struct InstrumentPreset {
+    /// Highest frequency sent to the ui for the spectrum chart
+    pub max_display_hz: f32,
+    /// Where hps looks for the fundamental
+    pub pitch_range: (f32, f32),
+    /// Below this max magnitude nothing counts as a note
+    pub min_magnitude: f32,
+    /// Only every this many samples are analyzed, after a low-pass. Each
+    /// window then covers that many times as long, for the finer bins low
+    /// notes need; 1 analyzes every sample
+    pub decimation: usize,
+}
+
+impl InstrumentPreset {
+    /// This preset with the spectrum going up to `max_display_hz` instead, if
+    /// there is one
+    pub fn with_max_display_hz(mut self, max_display_hz: Option<f32>) -> Self {
+        if let Some(max_display_hz) = max_display_hz {
+            self.max_display_hz = max_display_hz;
+        }
+        self
+    }
+}
+
+impl Instrument {
+    pub const ALL: [Instrument; 7] = [
+        Instrument::Any,
+        Instrument::Guitar,
+        Instrument::Bass,
+        Instrument::Cello,
+        Instrument::Violin,
+        Instrument::Voice,
+        Instrument::Whistle,
+    ];
+
+    pub fn preset(self) -> InstrumentPreset {
+        let (max_display_hz, pitch_range, min_magnitude, decimation) = match self {
+            Instrument::Any => (1500.0, (0.0, f32::INFINITY), 100.0, 1),
+            // low E (82 Hz) up to the 24th fret of the high E
+            Instrument::Guitar => (2000.0, (70.0, 1400.0), 100.0, 1),
+            // low B of a 5 string (31 Hz) up to the high frets of the G string.
+            // its notes are a few Hz apart down there, closer than the bins
+            // of a 4096 sample window, and nothing above 5 kHz matters
+            Instrument::Bass => (800.0, (28.0, 450.0), 150.0, 4),
+            // open C (65 Hz) up to the top of the A string
+            Instrument::Cello => (2000.0, (60.0, 1100.0), 100.0, 2),
+            // open G (196 Hz) up to the end of the fingerboard
+            Instrument::Violin => (4000.0, (180.0, 3600.0), 80.0, 1),
+            // bass singers up to sopranos
+            Instrument::Voice => (2000.0, (75.0, 1100.0), 60.0, 1),
+            Instrument::Whistle => (5000.0, (500.0, 4500.0), 40.0, 1),
+        };
+        InstrumentPreset {
+            max_display_hz,
+            pitch_range,
+            min_magnitude,
+            decimation,
+        }
+    }
+}
+
+impl Display for Instrument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Instrument::Any => "Any",
+            Instrument::Guitar => "Guitar",
+            Instrument::Bass => "Bass",
+            Instrument::Cello => "Cello",
+            Instrument::Violin => "Violin",
+            Instrument::Voice => "Voice",
+            Instrument::Whistle => "Whistle",
+        };
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::tuning::NOTES;
+
+/// Krumhansl-Kessler profiles: how well each scale degree, tonic first, was
+/// judged to fit a major or minor key
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+/// Keys correlating worse than this with what was heard aren't reported
+const KEY_MIN_CORRELATION: f32 = 0.5;
+/// Noisier frames than this (spectral flatness) say nothing about the key
+const KEY_MAX_FLATNESS: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Key {
+    /// Pitch class of the tonic, 0 for C
+    pub tonic: usize,
+    pub minor: bool,
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            NOTES[self.tonic],
+            if self.minor { "minor" } else { "major" }
+        )
+    }
+}
+
+/// Guesses the key from the chroma of the last few seconds, by correlating it
+/// with the Krumhansl-Kessler profile of every major and minor key.
+pub struct KeyEstimator {
+    window: Duration,
+    frames: VecDeque<(Instant, [f32; 12])>,
+    /// Sum of the chroma in `frames`
+    sum: [f32; 12],
+}
+
+impl KeyEstimator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            frames: VecDeque::new(),
+            sum: [0.0; 12],
+        }
+    }
+
+    /// Adds a frame's chroma and returns the most likely key so far
+    pub fn add(&mut self, chroma: &[f32; 12], spectral_flatness: f32) -> Option<Key> {
+        let now = Instant::now();
+        while let Some((time, old)) = self.frames.front()
+            && now.duration_since(*time) > self.window
+        {
+            self.sum.iter_mut().zip(old).for_each(|(sum, c)| *sum -= c);
+            self.frames.pop_front();
+        }
+        if spectral_flatness < KEY_MAX_FLATNESS {
+            self.sum
+                .iter_mut()
+                .zip(chroma)
+                .for_each(|(sum, c)| *sum += c);
+            self.frames.push_back((now, *chroma));
+        }
+        if self.frames.is_empty() {
+            return None;
+        }
+        let (key, correlation) = (0..12)
+            .flat_map(|tonic| [false, true].map(|minor| Key { tonic, minor }))
+            .map(|key| {
+                let profile = if key.minor {
+                    &MINOR_PROFILE
+                } else {
+                    &MAJOR_PROFILE
+                };
+                // rotate the profile so its tonic lands on the key's
+                let rotated: [f32; 12] =
+                    std::array::from_fn(|i| profile[(i + 12 - key.tonic) % 12]);
+                (key, correlation(&self.sum, &rotated))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        (correlation >= KEY_MIN_CORRELATION).then_some(key)
+    }
+}
+
+/// Pearson correlation of two vectors
+fn correlation(a: &[f32; 12], b: &[f32; 12]) -> f32 {
+    let mean_a = a.iter().sum::<f32>() / 12.0;
+    let mean_b = b.iter().sum::<f32>() / 12.0;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (a, b) in a.iter().zip(b) {
+        covariance += (a - mean_a) * (b - mean_b);
+        variance_a += (a - mean_a) * (a - mean_a);
+        variance_b += (b - mean_b) * (b - mean_b);
+    }
+    if variance_a <= 0.0 || variance_b <= 0.0 {
+        return 0.0;
+    }
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chroma vector with the pitch classes in `notes` equally strong
+    fn chroma_of(notes: &[usize]) -> [f32; 12] {
+        let mut chroma = [0.0; 12];
+        notes.iter().for_each(|note| chroma[*note] = 1.0);
+        chroma
+    }
+
+    /// The key after hearing the chords in `progression`, one frame each
+    fn key_of(progression: &[&[usize]]) -> Option<Key> {
+        let mut estimator = KeyEstimator::new(Duration::from_secs(10));
+        progression
+            .iter()
+            .map(|notes| estimator.add(&chroma_of(notes), 0.0))
+            .last()?
+    }
+
+    #[test]
+    fn a_cadence_in_c_major() {
+        // C F G C
+        let key = key_of(&[&[0, 4, 7], &[5, 9, 0], &[7, 11, 2], &[0, 4, 7]]).unwrap();
+        assert_eq!(
+            key,
+            Key {
+                tonic: 0,
+                minor: false
+            }
+        );
+        assert_eq!(key.to_string(), "C major");
+    }
+
+    #[test]
+    fn a_cadence_in_a_minor() {
+        // Am Dm E Am, the E major chord's G# gives it away
+        let key = key_of(&[&[9, 0, 4], &[2, 5, 9], &[4, 8, 11], &[9, 0, 4]]).unwrap();
+        assert_eq!(key.to_string(), "A minor");
+    }
+
+    #[test]
+    fn noise_says_nothing_about_the_key() {
+        let mut estimator = KeyEstimator::new(Duration::from_secs(10));
+        assert_eq!(estimator.add(&chroma_of(&[0, 4, 7]), 0.5), None);
+        assert_eq!(estimator.add(&[0.0; 12], 0.0), None);
+    }
+}
@@ -0,0 +1,101 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use serde::Deserialize;
+
+/// What a cell of a layout can show
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Widget {
+    /// The frequency chart, in whichever view `m` picked
+    Spectrum,
+    TimeDomain,
+    Spectrogram,
+    /// The note in big letters with a needle and a strobe
+    Tuner,
+    /// The pitch of the last few seconds
+    PitchContour,
+    /// The last few notes played, a line of them
+    NoteTicker,
+    Piano,
+    VuMeters,
+    Vibrato,
+    PhaseScope,
+    Vowels,
+    GuitarTuner,
+}
+
+/// A widget in a row, either just its name or with its share of the row's
+/// width, like `{ widget = "tuner", size = 2 }`
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Cell {
+    Widget(Widget),
+    Sized { widget: Widget, size: u16 },
+}
+
+impl Cell {
+    fn widget(self) -> Widget {
+        match self {
+            Cell::Widget(widget) | Cell::Sized { widget, .. } => widget,
+        }
+    }
+
+    fn size(self) -> u16 {
+        match self {
+            Cell::Widget(_) => 1,
+            Cell::Sized { size, .. } => size,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Row {
+    /// Its share of the screen's height
+    #[serde(default = "default_size")]
+    pub size: u16,
+    pub widgets: Vec<Cell>,
+}
+
+fn default_size() -> u16 {
+    1
+}
+
+/// A screen of the user's own from the config file: rows of widgets, each
+/// row taking its share of the height and each widget its share of the row
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutOptions {
+    /// Title of its tab
+    pub name: String,
+    pub rows: Vec<Row>,
+}
+
+impl LayoutOptions {
+    /// Each widget of the layout and where it goes in `area`
+    pub fn areas(&self, area: Rect) -> Vec<(Widget, Rect)> {
+        let rows =
+            Layout::vertical(self.rows.iter().map(|row| Constraint::Fill(row.size))).split(area);
+        self.rows
+            .iter()
+            .zip(rows.iter())
+            .flat_map(|(row, area)| {
+                let cells = Layout::horizontal(
+                    row.widgets.iter().map(|cell| Constraint::Fill(cell.size())),
+                )
+                .split(*area);
+                row.widgets
+                    .iter()
+                    .zip(cells.iter())
+                    .map(|(cell, area)| (cell.widget(), *area))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    pub fn shows(&self, widget: Widget) -> bool {
+        self.rows
+            .iter()
+            .flat_map(|row| &row.widgets)
+            .any(|cell| cell.widget() == widget)
+    }
+}
@@ -1,10 +1,28 @@
-use std::path::PathBuf;
+use std::{
+    collections::VecDeque,
+    fmt::{Debug, Write},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use lazy_static::lazy_static;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
 use tracing_error::ErrorLayer;
-use tracing_subscriber::{self, Layer, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    self, Layer,
+    filter::{LevelFilter, Targets},
+    layer::{Context, SubscriberExt},
+    util::SubscriberInitExt,
+};
+
+/// Most log lines kept for the logs screen, the oldest go first
+const LOG_BUFFER_LINES: usize = 1000;
 
 lazy_static! {
     pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_uppercase().to_string();
@@ -14,14 +32,63 @@ lazy_static! {
             .map(PathBuf::from);
     pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
     pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
+    /// The latest log lines, for the logs screen
+    pub static ref LOG_BUFFER: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+    static ref LOG_START: Instant = Instant::now();
+}
+
+/// A log event kept in memory
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    /// Time since logging started
+    pub elapsed: Duration,
+    pub level: Level,
+    pub message: String,
+}
+
+/// Keeps every event it sees in `LOG_BUFFER`, so they can be read without
+/// leaving the tui
+struct BufferLayer;
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        let mut buffer = LOG_BUFFER.lock().unwrap();
+        if buffer.len() == LOG_BUFFER_LINES {
+            buffer.pop_front();
+        }
+        buffer.push_back(LogLine {
+            elapsed: LOG_START.elapsed(),
+            level: *event.metadata().level(),
+            message: message.0,
+        });
+    }
+}
+
+/// Writes an event's message followed by its other fields
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+pub fn log_path() -> PathBuf {
+    get_data_dir().join(LOG_FILE.clone())
 }
 
-fn project_directory() -> Option<ProjectDirs> {
+pub fn project_directory() -> Option<ProjectDirs> {
     ProjectDirs::from("com", "light", env!("CARGO_PKG_NAME"))
 }
 
 pub fn get_data_dir() -> PathBuf {
-    
     if let Some(s) = DATA_FOLDER.clone() {
         s
     } else if let Some(proj_dirs) = project_directory() {
@@ -34,8 +101,7 @@ pub fn get_data_dir() -> PathBuf {
 pub fn initialize_logging() -> Result<()> {
     let directory = get_data_dir();
     std::fs::create_dir_all(directory.clone())?;
-    let log_path = directory.join(LOG_FILE.clone());
-    let log_file = std::fs::File::create(log_path)?;
+    let log_file = std::fs::File::create(log_path())?;
     // std::env::set_var(
     //     "RUST_LOG",
     //     std::env::var("RUST_LOG")
@@ -49,8 +115,15 @@ pub fn initialize_logging() -> Result<()> {
         .with_target(false)
         .with_ansi(false)
         .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
+    // the logs screen gets our own debug traces whatever the file gets
+    let buffer_subscriber = BufferLayer.with_filter(
+        Targets::new()
+            .with_target(env!("CARGO_CRATE_NAME"), LevelFilter::DEBUG)
+            .with_default(LevelFilter::WARN),
+    );
     tracing_subscriber::registry()
         .with(file_subscriber)
+        .with(buffer_subscriber)
         .with(ErrorLayer::default())
         .init();
     Ok(())
@@ -0,0 +1,120 @@
+use std::collections::VecDeque;
+
+use crate::filter::Biquad;
+
+/// Length of the blocks loudness is measured in, in seconds
+const BLOCK_SECS: f64 = 0.1;
+/// Momentary loudness covers 400 ms
+const MOMENTARY_BLOCKS: usize = 4;
+/// Short-term loudness covers 3 s
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Loudness in LUFS as defined by ITU-R BS.1770: the input is K-weighted (a
+/// high shelf for the head's acoustics plus a high-pass), then the mean square
+/// over a window gives the loudness. All channels are weighted the same, which
+/// is right for mono and stereo.
+pub struct LoudnessMeter {
+    filters: Vec<[Biquad; 2]>,
+    block_len: usize,
+    /// Frames and summed squares of the block being filled
+    block_frames: usize,
+    block_sum: f64,
+    /// Mean squares of the finished blocks, newest last
+    blocks: VecDeque<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let sample_rate = sample_rate as f64;
+        Self {
+            filters: vec![
+                [
+                    Biquad::k_shelf(sample_rate),
+                    Biquad::k_high_pass(sample_rate)
+                ];
+                channels
+            ],
+            block_len: (sample_rate * BLOCK_SECS) as usize,
+            block_frames: 0,
+            block_sum: 0.0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+        }
+    }
+
+    /// Takes interleaved samples with as many channels as the meter was made for.
+    pub fn add(&mut self, data: &[f32]) {
+        for frame in data.chunks_exact(self.filters.len()) {
+            for (sample, [shelf, high_pass]) in frame.iter().zip(self.filters.iter_mut()) {
+                let weighted = high_pass.process(shelf.process(*sample as f64));
+                self.block_sum += weighted * weighted;
+            }
+            self.block_frames += 1;
+            if self.block_frames == self.block_len {
+                if self.blocks.len() == SHORT_TERM_BLOCKS {
+                    self.blocks.pop_front();
+                }
+                self.blocks
+                    .push_back(self.block_sum / self.block_len as f64);
+                self.block_frames = 0;
+                self.block_sum = 0.0;
+            }
+        }
+    }
+
+    /// Loudness of the last 400 ms, in LUFS
+    pub fn momentary(&self) -> f32 {
+        self.loudness(MOMENTARY_BLOCKS)
+    }
+
+    /// Loudness of the last 3 s, in LUFS
+    pub fn short_term(&self) -> f32 {
+        self.loudness(SHORT_TERM_BLOCKS)
+    }
+
+    fn loudness(&self, blocks: usize) -> f32 {
+        let blocks = self.blocks.iter().rev().take(blocks);
+        let n = blocks.len();
+        if n == 0 {
+            return f32::NEG_INFINITY;
+        }
+        let mean_square = blocks.sum::<f64>() / n as f64;
+        (-0.691 + 10.0 * mean_square.log10()) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, sine};
+
+    #[test]
+    fn a_full_scale_sine_at_1_khz_is_minus_3_lufs() {
+        let mut meter = LoudnessMeter::new(SAMPLE_RATE, 1);
+        meter.add(&sine(1000.0, SAMPLE_RATE as usize));
+        assert_near(meter.momentary(), -3.01, 0.1);
+        assert_near(meter.short_term(), -3.01, 0.1);
+    }
+
+    #[test]
+    fn every_channel_adds_up() {
+        let mut meter = LoudnessMeter::new(SAMPLE_RATE, 2);
+        let stereo = sine(1000.0, SAMPLE_RATE as usize)
+            .into_iter()
+            .flat_map(|sample| [sample, sample])
+            .collect::<Vec<_>>();
+        meter.add(&stereo);
+        // twice the power of the same sine in one channel
+        assert_near(meter.momentary(), 0.0, 0.1);
+    }
+
+    #[test]
+    fn short_term_remembers_what_momentary_forgot() {
+        let mut meter = LoudnessMeter::new(SAMPLE_RATE, 1);
+        assert_eq!(meter.momentary(), f32::NEG_INFINITY);
+        meter.add(&sine(1000.0, SAMPLE_RATE as usize));
+        meter.add(&[0.0; SAMPLE_RATE as usize]);
+        assert!(meter.momentary() < -70.0, "{}", meter.momentary());
+        // a second of the sine out of the last two
+        assert_near(meter.short_term(), -6.02, 0.1);
+    }
+}
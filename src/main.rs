@@ -1,22 +1,89 @@
+use std::io::stdout;
+
+use clap::Parser;
 use color_eyre::eyre::Result;
+use ratatui::crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+};
 mod app;
 mod audio;
+mod chord;
+mod cli;
+mod config;
+mod cqt;
+mod envelope;
+mod features;
+mod filter;
+mod formants;
+mod goertzel;
+mod guitar;
+mod harmonics;
+mod history;
+mod instrument;
+mod key;
+mod layout;
 mod logging;
+mod loudness;
+mod mel;
+mod mfcc;
+mod monitor;
+mod multipitch;
+mod onset;
+mod peaks;
+mod pitch;
+mod recorder;
+mod snapshot;
+#[cfg(test)]
+mod test_signals;
+mod theme;
+mod tuning;
+mod vibrato;
 use app::App;
+use cli::Cli;
+use config::Config;
 use logging::initialize_logging;
 
 fn main() -> Result<()> {
-    // todo: maybe do better parsing using clap
-    // can also get this input thru the tui itself
+    // can also get the notes file thru the tui itself
     // if we have a nice file picker or at least
     // auto-complete that is file system-aware
-    let mut args = std::env::args();
-    let _ = args.next(); // ignore binary
-    let file = args.next();
+    let cli = Cli::parse();
+    if cli.list_hosts {
+        let default_host = cpal::default_host().id().name().to_lowercase();
+        for host in audio::available_host_names() {
+            if host == default_host {
+                println!("{host} (default)");
+            } else {
+                println!("{host}");
+            }
+        }
+        return Ok(());
+    }
     initialize_logging()?;
     color_eyre::install()?;
+    // no arguments and no config file, most likely the first time
+    let first_run = std::env::args_os().len() == 1 && !config::config_path().exists();
+    let config = Config::load(cli.config.as_deref())?;
+    let mut app = App::new(cli, config)?;
+    if first_run {
+        app.welcome()?;
+    }
     let terminal = ratatui::init();
-    let app_result = App::new(file)?.run(terminal);
+    // the wheel zooms the frequency chart
+    execute!(stdout(), EnableMouseCapture)?;
+    // the hook ratatui::init put in restores the terminal but knows nothing
+    // of the mouse, which would keep reporting into the shell after a panic
+    let restore_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(stdout(), DisableMouseCapture);
+        restore_hook(info);
+    }));
+    let app_result = app.run(terminal);
+    execute!(stdout(), DisableMouseCapture)?;
     ratatui::restore();
-    app_result
+    if let Some(summary) = app_result? {
+        println!("{summary}");
+    }
+    Ok(())
 }
@@ -0,0 +1,103 @@
+/// Frequency in Hz to mels, the scale pitch is perceived on: roughly linear up
+/// to 1 kHz and logarithmic above
+pub fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+pub fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10.0_f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular filters evenly spaced in mels. Each one averages the fft bins
+/// under it, so the low end keeps its detail while the high end, where the ear
+/// hears less difference, is summed into wide bands.
+pub struct MelFilterbank {
+    /// (sample rate, fft size, highest frequency) the filters were made for
+    made_for: (u32, usize, f32),
+    bands: usize,
+    /// Center of each band in mels
+    centers: Vec<f32>,
+    /// First bin and the weights of the bins from there, per band
+    filters: Vec<(usize, Vec<f32>)>,
+}
+
+impl MelFilterbank {
+    pub fn new(bands: usize) -> Self {
+        Self {
+            made_for: (0, 0, 0.0),
+            bands,
+            centers: vec![],
+            filters: vec![],
+        }
+    }
+
+    /// Mel spectrum of the magnitudes of an `n` point fft, up to `max_hz`. Gives
+    /// (center in mels, magnitude) per band, the magnitude being the weighted
+    /// average of the bins so it's on the same scale as the linear spectrum.
+    pub fn apply(
+        &mut self,
+        magnitudes: impl Fn(usize) -> f32,
+        sample_rate: u32,
+        n: usize,
+        max_hz: f32,
+    ) -> Vec<(f64, f64)> {
+        let max_hz = max_hz.min(sample_rate as f32 / 2.0);
+        if self.made_for != (sample_rate, n, max_hz) {
+            self.make_filters(sample_rate, n, max_hz);
+        }
+        self.centers
+            .iter()
+            .zip(&self.filters)
+            .map(|(center, (first, weights))| {
+                let total_weight = weights.iter().sum::<f32>();
+                let sum = weights
+                    .iter()
+                    .enumerate()
+                    .map(|(i, weight)| weight * magnitudes(first + i))
+                    .sum::<f32>();
+                let magnitude = if total_weight > 0.0 {
+                    sum / total_weight
+                } else {
+                    0.0
+                };
+                (*center as f64, magnitude as f64)
+            })
+            .collect()
+    }
+
+    fn make_filters(&mut self, sample_rate: u32, n: usize, max_hz: f32) {
+        self.made_for = (sample_rate, n, max_hz);
+        let bin_hz = sample_rate as f32 / n as f32;
+        let max_mel = hz_to_mel(max_hz);
+        // band i rises from edge i to edge i + 1 and falls to edge i + 2
+        let edges = (0..self.bands + 2)
+            .map(|i| max_mel * i as f32 / (self.bands + 1) as f32)
+            .collect::<Vec<_>>();
+        self.centers = edges[1..=self.bands].to_vec();
+        self.filters = edges
+            .windows(3)
+            .map(|edge| {
+                let [low, center, high] = [edge[0], edge[1], edge[2]].map(mel_to_hz);
+                let first = (low / bin_hz).ceil() as usize;
+                let last = ((high / bin_hz).floor() as usize).max(first);
+                let weights = (first..=last)
+                    .map(|bin| {
+                        let hz = bin as f32 * bin_hz;
+                        if hz <= center {
+                            (hz - low) / (center - low)
+                        } else {
+                            (high - hz) / (high - center)
+                        }
+                        .max(0.0)
+                    })
+                    .collect::<Vec<_>>();
+                // narrow low bands can fall between two bins, take the nearest then
+                if weights.iter().all(|w| *w <= 0.0) {
+                    ((center / bin_hz).round() as usize, vec![1.0])
+                } else {
+                    (first, weights)
+                }
+            })
+            .collect();
+    }
+}
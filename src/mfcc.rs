@@ -0,0 +1,126 @@
+use std::f32::consts::PI;
+
+use crate::mel::MelFilterbank;
+
+/// Mel bands the cepstral coefficients are taken from
+const MFCC_BANDS: usize = 40;
+
+/// Works out the mfcc of windows, keeping the filterbank between them
+pub struct MfccExtractor {
+    /// Filterbank over the whole spectrum
+    mel: MelFilterbank,
+    coefficients: usize,
+}
+
+impl MfccExtractor {
+    pub fn new(coefficients: usize) -> Self {
+        Self {
+            mel: MelFilterbank::new(MFCC_BANDS),
+            coefficients,
+        }
+    }
+
+    /// The first `coefficients` of the magnitudes of an `n` point fft's bins
+    pub fn extract(
+        &mut self,
+        magnitudes: impl Fn(usize) -> f32,
+        sample_rate: u32,
+        n: usize,
+    ) -> Vec<f32> {
+        let bands = self
+            .mel
+            .apply(magnitudes, sample_rate, n, sample_rate as f32 / 2.0);
+        mfcc(
+            &bands
+                .iter()
+                .map(|(_, magnitude)| *magnitude as f32)
+                .collect::<Vec<_>>(),
+            self.coefficients,
+        )
+    }
+}
+
+/// Mel-frequency cepstral coefficients: the dct of the log energy of each mel
+/// band. The first few describe the rough shape of the spectrum, the timbre,
+/// and leave out the pitch, which is why speech and instrument classifiers
+/// like them. The first is the overall loudness.
+pub fn mfcc(mel_magnitudes: &[f32], coefficients: usize) -> Vec<f32> {
+    let bands = mel_magnitudes.len();
+    let log_energies = mel_magnitudes
+        .iter()
+        .map(|magnitude| (magnitude * magnitude).max(1e-10).ln())
+        .collect::<Vec<_>>();
+    // orthonormal dct-ii
+    (0..coefficients.min(bands))
+        .map(|k| {
+            let scale = if k == 0 {
+                (1.0 / bands as f32).sqrt()
+            } else {
+                (2.0 / bands as f32).sqrt()
+            };
+            scale
+                * log_energies
+                    .iter()
+                    .enumerate()
+                    .map(|(i, energy)| {
+                        energy * (PI * k as f32 * (i as f32 + 0.5) / bands as f32).cos()
+                    })
+                    .sum::<f32>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, harmonic_tone, magnitudes};
+
+    const LEN: usize = 4096;
+
+    /// Magnitudes of a window of a tone at 220 Hz, over a faint noise floor so
+    /// no band is silent
+    fn tone_magnitudes(amplitude: f32) -> Vec<f32> {
+        // a linear congruential generator is plenty for a noise floor
+        let mut noise = 1_u32;
+        let samples = harmonic_tone(220.0, 10, LEN)
+            .into_iter()
+            .map(|sample| {
+                noise = noise.wrapping_mul(1664525).wrapping_add(1013904223);
+                amplitude * (sample + 1e-3 * (noise as f32 / u32::MAX as f32 - 0.5))
+            })
+            .collect::<Vec<_>>();
+        magnitudes(&samples)
+    }
+
+    #[test]
+    fn a_flat_spectrum_only_has_loudness() {
+        let mfcc = mfcc(&[2.0; MFCC_BANDS], 13);
+        assert_eq!(mfcc.len(), 13);
+        // the orthonormal dct of a constant is all in the first coefficient
+        assert_near(mfcc[0], 4.0_f32.ln() * (MFCC_BANDS as f32).sqrt(), 1e-3);
+        assert!(mfcc[1..].iter().all(|c| c.abs() < 1e-3), "{mfcc:?}");
+    }
+
+    #[test]
+    fn a_louder_tone_only_changes_the_first_coefficient() {
+        let mut extractor = MfccExtractor::new(13);
+        let quiet = tone_magnitudes(0.5);
+        let loud = tone_magnitudes(1.0);
+        let quiet = extractor.extract(|bin| quiet[bin], SAMPLE_RATE, LEN);
+        let loud = extractor.extract(|bin| loud[bin], SAMPLE_RATE, LEN);
+        // twice the amplitude is ln 4 more log energy in every band
+        assert_near(
+            loud[0] - quiet[0],
+            4.0_f32.ln() * (MFCC_BANDS as f32).sqrt(),
+            1e-2,
+        );
+        for (quiet, loud) in quiet[1..].iter().zip(&loud[1..]) {
+            assert_near(*loud, *quiet, 1e-2);
+        }
+    }
+
+    #[test]
+    fn no_more_coefficients_than_bands() {
+        assert_eq!(mfcc(&[1.0; 8], 13).len(), 8);
+    }
+}
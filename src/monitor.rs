@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use color_eyre::eyre::{Result, eyre};
+use cpal::{
+    Device, FromSample, Host, SampleFormat, SampleRate, SizedSample, Stream, StreamConfig,
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+use ringbuf::{
+    HeapCons, HeapProd, HeapRb,
+    traits::{Consumer, Observer, Producer, Split},
+};
+
+use crate::audio::find_device;
+
+/// Plays the captured input back so you can hear yourself
+#[derive(Clone, Debug)]
+pub struct MonitorOptions {
+    /// Output device name or index, `None` for the default output device
+    pub device: Option<String>,
+    /// How far behind the input the playback runs. Too little and it crackles
+    pub latency: Duration,
+}
+
+/// Opens an output stream playing whatever is pushed into the returned
+/// producer, which takes interleaved samples with the input's channels and
+/// sample rate.
+pub fn start_monitor(
+    host: &Host,
+    options: &MonitorOptions,
+    input_channels: usize,
+    sample_rate: u32,
+) -> Result<(HeapProd<f32>, Stream)> {
+    let device = find_output_device(host, options.device.as_deref())?;
+    let supported_config = device
+        .supported_output_configs()?
+        .filter(|range| {
+            (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&sample_rate)
+        })
+        // there's no resampling here, but any channel count works
+        .min_by_key(|range| {
+            (
+                range.channels().abs_diff(input_channels as u16),
+                range.sample_format() != SampleFormat::F32,
+            )
+        })
+        .ok_or_else(|| {
+            eyre!(
+                "{} can't play {sample_rate} Hz",
+                device
+                    .name()
+                    .unwrap_or_else(|_| "The output device".to_string())
+            )
+        })?
+        .with_sample_rate(SampleRate(sample_rate));
+    tracing::info!("monitoring on {supported_config:?}");
+    let latency_samples =
+        (options.latency.as_secs_f32() * sample_rate as f32) as usize * input_channels;
+    // room for the latency plus as much again for jitter
+    let (mut producer, consumer) = HeapRb::<f32>::new(latency_samples * 2 + 8192).split();
+    // start out `latency` behind the input
+    producer.push_iter(std::iter::repeat_n(0.0, latency_samples));
+    let config = supported_config.config();
+    let stream = match supported_config.sample_format() {
+        SampleFormat::I8 => build_monitor_stream::<i8>(&device, &config, consumer, input_channels),
+        SampleFormat::I16 => {
+            build_monitor_stream::<i16>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::I32 => {
+            build_monitor_stream::<i32>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::I64 => {
+            build_monitor_stream::<i64>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::U8 => build_monitor_stream::<u8>(&device, &config, consumer, input_channels),
+        SampleFormat::U16 => {
+            build_monitor_stream::<u16>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::U32 => {
+            build_monitor_stream::<u32>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::U64 => {
+            build_monitor_stream::<u64>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::F32 => {
+            build_monitor_stream::<f32>(&device, &config, consumer, input_channels)
+        }
+        SampleFormat::F64 => {
+            build_monitor_stream::<f64>(&device, &config, consumer, input_channels)
+        }
+        sample_format => Err(eyre!("Unsupported sample format {sample_format}")),
+    }?;
+    stream.play()?;
+    Ok((producer, stream))
+}
+
+/// Builds the output stream for devices taking samples as `T`. Input channels
+/// are spread over the output's, so a mono mic plays on both sides.
+fn build_monitor_stream<T>(
+    device: &Device,
+    config: &StreamConfig,
+    mut consumer: HeapCons<f32>,
+    input_channels: usize,
+) -> Result<Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let output_channels = config.channels as usize;
+    // a little slack so a tiny latency doesn't drop everything
+    let max_queued = (consumer.occupied_len() * 2).max(1024 * input_channels);
+    let mut frame = vec![0.0; input_channels];
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            // the clocks of two devices drift apart, drop what piled up past the latency
+            let excess = consumer.occupied_len().saturating_sub(max_queued);
+            consumer.skip(excess / input_channels * input_channels);
+            for output_frame in data.chunks_exact_mut(output_channels) {
+                if consumer.occupied_len() >= input_channels {
+                    consumer.pop_slice(&mut frame);
+                } else {
+                    // ran dry, play silence until the input catches up
+                    frame.fill(0.0);
+                }
+                for (channel, sample) in output_frame.iter_mut().enumerate() {
+                    *sample = T::from_sample(frame[channel % input_channels]);
+                }
+            }
+        },
+        |err| tracing::error!("monitor stream error: {err}"),
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Finds an output device by index or name, or the default one
+pub fn find_output_device(host: &Host, selector: Option<&str>) -> Result<Device> {
+    let Some(selector) = selector else {
+        return host
+            .default_output_device()
+            .ok_or_else(|| eyre!("No default output device found"));
+    };
+    find_device(host.output_devices()?, selector, "output")
+}
@@ -0,0 +1,154 @@
+/// Harmonics that count towards a candidate's salience
+const MULTI_PITCH_HARMONICS: usize = 10;
+/// Candidate fundamentals per semitone
+const MULTI_PITCH_STEPS_PER_SEMITONE: usize = 10;
+/// A further pitch needs at least this share of the first one's salience
+const MULTI_PITCH_MIN_SALIENCE: f32 = 0.3;
+/// Widest range searched for notes, the piano's A0 to C8, whatever the
+/// instrument allows
+const MULTI_PITCH_RANGE: (f32, f32) = (27.5, 4186.0);
+/// A pitch this close to a harmonic of a lower one is taken for that harmonic
+const HARMONIC_TOLERANCE_CENTS: f32 = 50.0;
+/// Harmonic weights, (f0 + alpha) / (h f0 + beta), from Klapuri's "Multiple
+/// fundamental frequency estimation by summing harmonic amplitudes"
+const SALIENCE_ALPHA: f32 = 27.0;
+const SALIENCE_BETA: f32 = 320.0;
+
+/// Finds up to `max_pitches` simultaneous fundamentals in the magnitudes of an
+/// `n` point fft of a window padded to `zero_padding` times its length,
+/// strongest first, by iterative spectral subtraction: the
+/// candidate whose harmonics add up to the most is taken, its harmonics are
+/// removed from the spectrum and the search starts over on what's left. Stops
+/// early when what's left is much weaker than the first pitch.
+///
+/// Harmonics two notes share go with the first, so the second needs enough
+/// harmonics of its own to be found. A note with strong upper harmonics can
+/// still have them found before itself, so pitches on a harmonic of a lower
+/// one are dropped at the end. An octave or a twelfth above a note is lost
+/// either way.
+pub fn detect_pitches(
+    magnitudes: &[f32],
+    sample_rate: u32,
+    n: usize,
+    zero_padding: usize,
+    pitch_range: (f32, f32),
+    max_pitches: usize,
+) -> Vec<f32> {
+    let bin_width = sample_rate as f32 / n as f32;
+    let mut residual = magnitudes.to_vec();
+    let lowest = pitch_range.0.max(MULTI_PITCH_RANGE.0);
+    let highest = pitch_range.1.min(MULTI_PITCH_RANGE.1);
+    if lowest >= highest {
+        return vec![];
+    }
+    let semitones = 12.0 * (highest / lowest).log2();
+    let candidates = (0..=(semitones * MULTI_PITCH_STEPS_PER_SEMITONE as f32) as usize)
+        .map(|step| {
+            lowest * 2.0_f32.powf(step as f32 / MULTI_PITCH_STEPS_PER_SEMITONE as f32 / 12.0)
+        })
+        .collect::<Vec<_>>();
+    let mut pitches = vec![];
+    let mut first_salience = None;
+    while pitches.len() < max_pitches {
+        let Some((f0, salience)) = candidates
+            .iter()
+            .map(|f0| (*f0, salience(&residual, *f0, bin_width, zero_padding)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            break;
+        };
+        let first = *first_salience.get_or_insert(salience);
+        if salience <= 0.0 || salience < first * MULTI_PITCH_MIN_SALIENCE {
+            break;
+        }
+        pitches.push(f0);
+        // all of them, the ones past those counted still add up for a note
+        // an octave or a fifth higher
+        for harmonic in 1..=(residual.len() as f32 * bin_width / f0) as usize {
+            let bin = (harmonic as f32 * f0 / bin_width).round() as usize;
+            // the window spreads every partial over a few bins, more of them
+            // when padded
+            for k in bin.saturating_sub(2 * zero_padding)..=bin + 2 * zero_padding {
+                if let Some(magnitude) = residual.get_mut(k) {
+                    *magnitude = 0.0;
+                }
+            }
+        }
+    }
+    let is_harmonic_of_another = |pitch: f32| {
+        pitches.iter().any(|lower| {
+            let ratio = pitch / lower;
+            ratio >= 1.5
+                && (1200.0 * (ratio / ratio.round()).log2()).abs() < HARMONIC_TOLERANCE_CENTS
+        })
+    };
+    pitches
+        .iter()
+        .copied()
+        .filter(|pitch| !is_harmonic_of_another(*pitch))
+        .collect()
+}
+
+/// Weighted sum of the strongest bin near each harmonic of `f0`, within
+/// `spread` bins
+fn salience(magnitudes: &[f32], f0: f32, bin_width: f32, spread: usize) -> f32 {
+    (1..=MULTI_PITCH_HARMONICS)
+        .map_while(|harmonic| {
+            let bin = (harmonic as f32 * f0 / bin_width).round() as usize;
+            let peak = magnitudes
+                .get(bin.saturating_sub(spread)..=bin + spread)?
+                .iter()
+                .copied()
+                .fold(0.0, f32::max);
+            let weight = (f0 + SALIENCE_ALPHA) / (harmonic as f32 * f0 + SALIENCE_BETA);
+            Some(weight * peak)
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, harmonic_tone, magnitudes};
+
+    const LEN: usize = 8192;
+
+    /// The notes found in a mix of harmonic tones at `frequencies`, lowest first
+    fn pitches_of(frequencies: &[f32]) -> Vec<f32> {
+        let mut mix = vec![0.0; LEN];
+        for frequency in frequencies {
+            let tone = harmonic_tone(*frequency, 8, LEN);
+            mix.iter_mut()
+                .zip(tone)
+                .for_each(|(mix, sample)| *mix += sample);
+        }
+        let mut pitches = detect_pitches(&magnitudes(&mix), SAMPLE_RATE, LEN, 1, (50.0, 2000.0), 6);
+        pitches.sort_by(f32::total_cmp);
+        pitches
+    }
+
+    #[track_caller]
+    fn assert_notes(pitches: &[f32], expected: &[f32]) {
+        assert_eq!(pitches.len(), expected.len(), "{pitches:?}");
+        for (pitch, expected) in pitches.iter().zip(expected) {
+            assert_near(1200.0 * (pitch / expected).log2(), 0.0, 15.0);
+        }
+    }
+
+    #[test]
+    fn finds_every_note_of_a_triad() {
+        // C4 E4 G4
+        let triad = [261.63, 329.63, 392.0];
+        assert_notes(&pitches_of(&triad), &triad);
+    }
+
+    #[test]
+    fn a_single_note_is_one_pitch() {
+        assert_notes(&pitches_of(&[220.0]), &[220.0]);
+    }
+
+    #[test]
+    fn silence_has_no_pitches() {
+        assert_eq!(pitches_of(&[]), []);
+    }
+}
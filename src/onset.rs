@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+
+use crate::peaks::refine_peak;
+
+/// Frames of flux the threshold is worked out from
+const ONSET_HISTORY: usize = 10;
+/// How far above the recent average flux an onset has to be
+const ONSET_SENSITIVITY: f32 = 1.5;
+/// Flux below this is never an onset, so tiny wiggles in silence don't count
+const ONSET_MIN_FLUX: f32 = 0.05;
+
+/// Finds note attacks by spectral flux: how much the spectrum grew since the
+/// previous window. Only growth counts, so a note dying away doesn't look like
+/// one starting. A frame is an onset when its flux peaks well above the
+/// average of the last few.
+#[derive(Default)]
+pub struct OnsetDetector {
+    /// Log magnitudes of the previous window
+    previous: Vec<f32>,
+    recent_flux: VecDeque<f32>,
+    /// The previous frame was an onset, a note only starts once
+    was_onset: bool,
+}
+
+impl OnsetDetector {
+    /// Takes the magnitudes of the next window's bins, and says whether a note
+    /// started in it
+    pub fn detect(&mut self, magnitudes: impl Iterator<Item = f32>) -> bool {
+        let mut flux = 0.0;
+        let mut bins = 0;
+        for (i, magnitude) in magnitudes.enumerate() {
+            // log compression, so loud partials don't drown out the rest
+            let magnitude = (1.0 + magnitude).ln();
+            if let Some(previous) = self.previous.get_mut(i) {
+                flux += (magnitude - *previous).max(0.0);
+                *previous = magnitude;
+            } else {
+                self.previous.push(magnitude);
+            }
+            bins += 1;
+        }
+        // a different window size, start over
+        self.previous.truncate(bins);
+        let flux = flux / bins.max(1) as f32;
+        let average = if self.recent_flux.is_empty() {
+            f32::INFINITY
+        } else {
+            self.recent_flux.iter().sum::<f32>() / self.recent_flux.len() as f32
+        };
+        let onset = !self.was_onset && flux > ONSET_MIN_FLUX && flux > average * ONSET_SENSITIVITY;
+        if self.recent_flux.len() == ONSET_HISTORY {
+            self.recent_flux.pop_front();
+        }
+        self.recent_flux.push_back(flux);
+        self.was_onset = onset;
+        onset
+    }
+
+    /// Spectral flux of the last window, the mean rise in log magnitude per bin
+    pub fn flux(&self) -> f32 {
+        self.recent_flux.back().copied().unwrap_or(0.0)
+    }
+}
+
+/// Samples per step of the tempo estimator's onset envelope, ~12 ms at 44.1 kHz
+const TEMPO_HOP: usize = 512;
+/// Seconds of onset envelope the tempo is worked out from
+const TEMPO_WINDOW_SECS: f32 = 8.0;
+/// Less than this much envelope and there's no telling the tempo yet
+const TEMPO_MIN_SECS: f32 = 4.0;
+/// Tempos the estimator looks for
+const TEMPO_RANGE_BPM: (f32, f32) = (60.0, 200.0);
+/// Tempos near this are preferred, which settles doubled or halved tempos
+const TEMPO_PRIOR_BPM: f32 = 120.0;
+/// Beats that line up worse than this (normalized autocorrelation) don't
+/// count as a tempo
+const TEMPO_MIN_CORRELATION: f32 = 0.1;
+/// Rises in log energy smaller than this don't count towards the envelope, a
+/// steady tone wobbles that much from hop to hop and would make up a tempo
+const TEMPO_MIN_RISE: f32 = 0.2;
+
+/// Live tempo from the autocorrelation of an onset envelope: how much the
+/// loudness jumps up every ~12 ms. If notes come on a beat, the envelope lines
+/// up with itself shifted by one beat.
+#[derive(Default)]
+pub struct TempoEstimator {
+    /// Onset strength per hop, oldest first
+    envelope: VecDeque<f32>,
+    /// Log energy of the previous hop
+    previous_energy: Option<f32>,
+    sample_rate: u32,
+}
+
+impl TempoEstimator {
+    /// Takes the next samples, in windows of any multiple of ~512, and gives
+    /// the tempo in beats per minute once it's clear
+    pub fn add(&mut self, samples: &[f32], sample_rate: u32) -> Option<f32> {
+        if sample_rate != self.sample_rate {
+            self.envelope.clear();
+            self.previous_energy = None;
+            self.sample_rate = sample_rate;
+        }
+        let hop_secs = TEMPO_HOP as f32 / sample_rate as f32;
+        let max_len = (TEMPO_WINDOW_SECS / hop_secs) as usize;
+        for hop in samples.chunks_exact(TEMPO_HOP) {
+            let energy = (hop.iter().map(|s| s * s).sum::<f32>() + 1e-10).ln();
+            let onset_strength = self.previous_energy.map_or(0.0, |previous| {
+                (energy - previous - TEMPO_MIN_RISE).max(0.0)
+            });
+            self.previous_energy = Some(energy);
+            if self.envelope.len() == max_len {
+                self.envelope.pop_front();
+            }
+            self.envelope.push_back(onset_strength);
+        }
+        if (self.envelope.len() as f32) < TEMPO_MIN_SECS / hop_secs {
+            return None;
+        }
+        let envelope = self.envelope.make_contiguous();
+        let mean = envelope.iter().sum::<f32>() / envelope.len() as f32;
+        let correlation_at = |lag: usize| {
+            envelope
+                .iter()
+                .zip(&envelope[lag..])
+                .map(|(a, b)| (a - mean) * (b - mean))
+                .sum::<f32>()
+        };
+        let energy = correlation_at(0);
+        if energy <= 0.0 {
+            return None;
+        }
+        let lag_for = |bpm: f32| 60.0 / bpm / hop_secs;
+        let lags = lag_for(TEMPO_RANGE_BPM.1).floor() as usize
+            ..=lag_for(TEMPO_RANGE_BPM.0).ceil() as usize;
+        let weighted = |lag: usize| {
+            let bpm = 60.0 / (lag as f32 * hop_secs);
+            // a log-normal preference around the prior, an octave wide
+            let octaves = (bpm / TEMPO_PRIOR_BPM).log2();
+            correlation_at(lag) / energy * (-0.5 * octaves * octaves).exp()
+        };
+        let lag = lags
+            .clone()
+            .max_by(|a, b| weighted(*a).total_cmp(&weighted(*b)))?;
+        if correlation_at(lag) / energy < TEMPO_MIN_CORRELATION {
+            return None;
+        }
+        // parabolic interpolation for a tempo between the lags
+        let shift = if lags.contains(&(lag - 1)) && lags.contains(&(lag + 1)) {
+            let around = [
+                correlation_at(lag - 1),
+                correlation_at(lag),
+                correlation_at(lag + 1),
+            ];
+            refine_peak(&around, 1).0
+        } else {
+            0.0
+        };
+        Some(60.0 / ((lag as f32 + shift) * hop_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{harmonic_tone, magnitudes};
+
+    /// Magnitudes of a window of a tone at `frequency`, 20 harmonics strong
+    fn tone(frequency: f32, amplitude: f32) -> Vec<f32> {
+        let samples = harmonic_tone(frequency, 20, 2048)
+            .into_iter()
+            .map(|sample| sample * amplitude)
+            .collect::<Vec<_>>();
+        magnitudes(&samples)
+    }
+
+    #[test]
+    fn a_note_starting_is_an_onset_once() {
+        let mut detector = OnsetDetector::default();
+        let silence = tone(440.0, 0.0);
+        for _ in 0..ONSET_HISTORY {
+            assert!(!detector.detect(silence.iter().copied()));
+        }
+        let note = tone(440.0, 1.0);
+        assert!(detector.detect(note.iter().copied()));
+        // holding it isn't another one
+        assert!(!detector.detect(note.iter().copied()));
+    }
+
+    #[test]
+    fn a_note_dying_away_is_no_onset() {
+        let mut detector = OnsetDetector::default();
+        for amplitude in [1.0, 1.0, 0.8, 0.6, 0.4, 0.2, 0.0] {
+            assert!(!detector.detect(tone(440.0, amplitude).into_iter()));
+        }
+    }
+
+    #[test]
+    fn a_new_note_over_an_old_one_is_an_onset() {
+        let mut detector = OnsetDetector::default();
+        let held = tone(440.0, 1.0);
+        for _ in 0..ONSET_HISTORY {
+            detector.detect(held.iter().copied());
+        }
+        let chord = held
+            .iter()
+            .zip(tone(330.0, 1.0))
+            .map(|(a, b)| a + b)
+            .collect::<Vec<_>>();
+        assert!(detector.detect(chord.into_iter()));
+    }
+}
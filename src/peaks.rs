@@ -0,0 +1,107 @@
+/// Peaks below this are rumble and dc, not worth a label
+const PEAK_MIN_HZ: f32 = 20.0;
+/// Peaks weaker than this, in parts of the loudest bin, are left out (-60 dB)
+const PEAK_MIN_SHARE: f32 = 0.001;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Peak {
+    pub frequency: f32,
+    pub magnitude: f32,
+}
+
+/// The `count` strongest peaks in the magnitudes of an fft with bins
+/// `bin_width` Hz apart, of a window padded to `zero_padding` times its
+/// length, strongest first. A peak has to be the loudest bin within
+/// `zero_padding` bins, the width of a bin of the unpadded window, so the
+/// padding's ripples don't pass for peaks.
+pub fn find_peaks(
+    magnitudes: &[f32],
+    bin_width: f32,
+    zero_padding: usize,
+    count: usize,
+) -> Vec<Peak> {
+    let loudest = magnitudes.iter().copied().fold(0.0, f32::max);
+    let first = ((PEAK_MIN_HZ / bin_width).ceil() as usize).max(zero_padding);
+    let Some(last) = magnitudes.len().checked_sub(zero_padding + 1) else {
+        return vec![];
+    };
+    let mut peaks = (first..=last)
+        .filter(|&bin| {
+            let magnitude = magnitudes[bin];
+            magnitude > 0.0
+                && magnitude >= loudest * PEAK_MIN_SHARE
+                && magnitudes[bin - zero_padding..bin]
+                    .iter()
+                    .all(|other| *other < magnitude)
+                && magnitudes[bin + 1..=bin + zero_padding]
+                    .iter()
+                    .all(|other| *other <= magnitude)
+        })
+        .map(|bin| {
+            let (shift, magnitude) = refine_peak(magnitudes, bin);
+            Peak {
+                frequency: (bin as f32 + shift) * bin_width,
+                magnitude,
+            }
+        })
+        .collect::<Vec<_>>();
+    peaks.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+    peaks.truncate(count);
+    peaks
+}
+
+/// Where between the bins the peak at `bin` really is, as a shift of at most
+/// half a bin, and its magnitude there. A parabola through the log magnitudes
+/// is the shape of the window's peak.
+pub fn refine_peak(magnitudes: &[f32], bin: usize) -> (f32, f32) {
+    let log_magnitude = |bin: usize| magnitudes[bin].max(1e-10).ln();
+    let (l, c, r) = (
+        log_magnitude(bin - 1),
+        log_magnitude(bin),
+        log_magnitude(bin + 1),
+    );
+    let (shift, peak) = parabolic_vertex(l, c, r);
+    // a parabola opening upwards has a dip there, not a peak
+    if peak < c {
+        return (0.0, c.exp());
+    }
+    (shift.clamp(-0.5, 0.5), peak.exp())
+}
+
+/// The vertex of the parabola through `yl`, `yc` and `yr` a step apart, as
+/// its offset from `yc` in steps and its value. Three points in a line have
+/// no vertex, that's an offset of 0 at `yc`.
+pub fn parabolic_vertex(yl: f32, yc: f32, yr: f32) -> (f32, f32) {
+    let denominator = yl - 2.0 * yc + yr;
+    if denominator.abs() <= f32::EPSILON {
+        return (0.0, yc);
+    }
+    let offset = 0.5 * (yl - yr) / denominator;
+    (offset, yc - 0.25 * (yl - yr) * offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::assert_near;
+
+    #[test]
+    fn parabolic_vertex_finds_the_top() {
+        // 3 - (x - 0.3)^2 at -1, 0 and 1
+        let (offset, value) = parabolic_vertex(1.31, 2.91, 2.51);
+        assert_near(offset, 0.3, 1e-4);
+        assert_near(value, 3.0, 1e-4);
+    }
+
+    #[test]
+    fn parabolic_vertex_of_a_line_stays_put() {
+        assert_eq!(parabolic_vertex(1.0, 2.0, 3.0), (0.0, 2.0));
+    }
+
+    #[test]
+    fn refine_peak_leaves_a_dip_alone() {
+        let (shift, magnitude) = refine_peak(&[2.0, 1.0, 1.5], 1);
+        assert_eq!(shift, 0.0);
+        assert_near(magnitude, 1.0, 1e-4);
+    }
+}
@@ -0,0 +1,578 @@
+use std::{ops::Range, sync::Arc};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use realfft::{ComplexToReal, RealFftPlanner, num_complex::Complex};
+use serde::Deserialize;
+
+use crate::peaks::{parabolic_vertex, refine_peak};
+
+/// How the fundamental frequency is found
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PitchDetector {
+    /// Harmonic product spectrum, multiplies the spectrum with its downsampled
+    /// copies so the harmonics pile up on the fundamental
+    #[default]
+    Hps,
+    /// YIN, looks for the period in the time domain. Fewer octave errors on
+    /// low notes
+    Yin,
+    /// Probabilistic YIN, tries many thresholds and tracks the pitch over time.
+    /// Also tells how sure it is that there's a pitch at all
+    Pyin,
+    /// Cepstrum, finds the spacing of the harmonics. Copes with the strong
+    /// formants of a voice better than hps
+    Cepstrum,
+}
+
+/// Which algorithm came up with a pitch. Mostly the chosen detector, but hps
+/// hands low notes over to the autocorrelation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PitchMethod {
+    #[default]
+    Hps,
+    Autocorrelation,
+    Yin,
+    Pyin,
+    Cepstrum,
+    /// The loudest of the notes the goertzel mode listens for
+    Goertzel,
+}
+
+impl PitchMethod {
+    pub fn name(self) -> &'static str {
+        match self {
+            PitchMethod::Hps => "HPS",
+            PitchMethod::Autocorrelation => "autocorrelation",
+            PitchMethod::Yin => "YIN",
+            PitchMethod::Pyin => "pYIN",
+            PitchMethod::Cepstrum => "cepstrum",
+            PitchMethod::Goertzel => "Goertzel",
+        }
+    }
+}
+
+/// A detected fundamental
+#[derive(Clone, Copy, Debug)]
+pub struct Pitch {
+    pub frequency: f32,
+    /// How likely it is that the window is pitched at all, 0 to 1
+    pub confidence: f32,
+}
+
+/// Dips of the normalized difference below this count as a period
+const YIN_THRESHOLD: f32 = 0.15;
+
+/// YIN pitch detection (de Cheveigné and Kawahara, 2002): the difference
+/// function, its cumulative mean normalization, an absolute threshold and
+/// parabolic interpolation. The buffer is kept between windows.
+#[derive(Default)]
+pub struct Yin {
+    difference: Vec<f32>,
+}
+
+impl Yin {
+    /// The fundamental frequency of `samples`, looking only within
+    /// `pitch_range` (Hz). `None` when there's nothing periodic in range.
+    pub fn detect(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        pitch_range: (f32, f32),
+    ) -> Option<Pitch> {
+        let (min_tau, max_tau) = tau_range(samples.len(), sample_rate, pitch_range)?;
+        let difference = &mut self.difference;
+        normalized_difference(samples, max_tau, difference);
+        let tau = dip_below(difference, min_tau..max_tau, YIN_THRESHOLD).or_else(|| {
+            // nothing under the threshold, settle for the deepest dip
+            (min_tau..max_tau).min_by(|a, b| difference[*a].total_cmp(&difference[*b]))
+        })?;
+        Some(Pitch {
+            frequency: sample_rate as f32 / refine_tau(difference, tau),
+            // the normalized difference is how aperiodic the window is
+            confidence: (1.0 - difference[tau]).clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// pYIN tries this many thresholds, evenly spaced from 0.01 to 1
+const PYIN_THRESHOLDS: usize = 100;
+/// Shape of the beta distribution the thresholds are weighted by. Beta(2, 18)
+/// has its mean at 0.1, close to the single threshold plain YIN uses
+const PYIN_BETA: (f32, f32) = (2.0, 18.0);
+/// Dips that no threshold reaches still get this share of the probability of
+/// the deepest dip, so a slightly breathy note isn't called unpitched
+const PYIN_ABSOLUTE_MIN_WEIGHT: f32 = 0.01;
+/// How far the pitch usually moves between windows, in semitones. Candidates
+/// further from the previous pitch are less likely to be picked
+const PYIN_TRANSITION_SEMITONES: f32 = 2.0;
+/// Even a big jump keeps this much of its probability, so a new note wins
+/// over a lingering old one
+const PYIN_MIN_TRANSITION: f32 = 0.1;
+
+/// Probabilistic YIN (Mauch and Dixon, 2014). Instead of one threshold it tries
+/// a whole distribution of them; every threshold votes for the dip it would
+/// pick, and the votes make up the probability of each candidate period. The
+/// votes that went anywhere are the probability the window is pitched at all.
+/// The candidates are then weighed against the previous pitch, a cheap stand-in
+/// for the paper's hidden markov model.
+pub struct Pyin {
+    difference: Vec<f32>,
+    /// Prior probability of each threshold
+    threshold_weights: Vec<f32>,
+    /// Candidate periods and their probability
+    candidates: Vec<(usize, f32)>,
+    previous: Option<f32>,
+}
+
+impl Default for Pyin {
+    fn default() -> Self {
+        let (a, b) = PYIN_BETA;
+        let weights = (1..=PYIN_THRESHOLDS)
+            .map(|i| {
+                let x = i as f32 / PYIN_THRESHOLDS as f32;
+                x.powf(a - 1.0) * (1.0 - x).powf(b - 1.0)
+            })
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f32>();
+        Self {
+            difference: Vec::new(),
+            threshold_weights: weights.into_iter().map(|w| w / total).collect(),
+            candidates: Vec::new(),
+            previous: None,
+        }
+    }
+}
+
+impl Pyin {
+    /// The most likely fundamental frequency of `samples` within `pitch_range`
+    /// (Hz), with the probability that there's a pitch at all. `None` when
+    /// there's nothing periodic in range.
+    pub fn detect(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        pitch_range: (f32, f32),
+    ) -> Option<Pitch> {
+        let Some((min_tau, max_tau)) = tau_range(samples.len(), sample_rate, pitch_range) else {
+            self.previous = None;
+            return None;
+        };
+        let difference = &mut self.difference;
+        normalized_difference(samples, max_tau, difference);
+        let candidates = &mut self.candidates;
+        candidates.clear();
+        for (i, weight) in self.threshold_weights.iter().enumerate() {
+            let threshold = (i + 1) as f32 / PYIN_THRESHOLDS as f32;
+            let Some(tau) = dip_below(difference, min_tau..max_tau, threshold) else {
+                continue;
+            };
+            match candidates.iter_mut().find(|(t, _)| *t == tau) {
+                Some((_, probability)) => *probability += weight,
+                None => candidates.push((tau, *weight)),
+            }
+        }
+        let mut confidence = candidates.iter().map(|(_, p)| p).sum::<f32>();
+        if candidates.is_empty() {
+            let tau =
+                (min_tau..max_tau).min_by(|a, b| difference[*a].total_cmp(&difference[*b]))?;
+            candidates.push((tau, PYIN_ABSOLUTE_MIN_WEIGHT));
+            confidence = PYIN_ABSOLUTE_MIN_WEIGHT;
+        }
+        let previous = self.previous;
+        let likelihood = |frequency: f32, probability: f32| {
+            let Some(previous) = previous else {
+                return probability;
+            };
+            let semitones = 12.0 * (frequency / previous).log2() / PYIN_TRANSITION_SEMITONES;
+            probability
+                * (-0.5 * semitones * semitones)
+                    .exp()
+                    .max(PYIN_MIN_TRANSITION)
+        };
+        let (frequency, _) = candidates
+            .iter()
+            .map(|&(tau, probability)| {
+                let frequency = sample_rate as f32 / refine_tau(difference, tau);
+                (frequency, likelihood(frequency, probability))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        self.previous = Some(frequency);
+        Some(Pitch {
+            frequency,
+            confidence: confidence.min(1.0),
+        })
+    }
+}
+
+/// Shortest and longest period (in samples) to look for
+fn tau_range(len: usize, sample_rate: u32, pitch_range: (f32, f32)) -> Option<(usize, usize)> {
+    // half the window is compared against shifted copies of itself
+    let width = len / 2;
+    let max_tau = ((sample_rate as f32 / pitch_range.0.max(1.0)) as usize).min(width - 1);
+    let min_tau = ((sample_rate as f32 / pitch_range.1) as usize).max(2);
+    (min_tau < max_tau).then_some((min_tau, max_tau))
+}
+
+/// Fills `difference` with the cumulative mean normalized difference of
+/// `samples` for every lag up to `max_tau`
+fn normalized_difference(samples: &[f32], max_tau: usize, difference: &mut Vec<f32>) {
+    let width = samples.len() / 2;
+    difference.clear();
+    difference.push(1.0);
+    let mut running_sum = 0.0;
+    for tau in 1..=max_tau {
+        let d = samples[..width]
+            .iter()
+            .zip(&samples[tau..tau + width])
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>();
+        running_sum += d;
+        difference.push(if running_sum > 0.0 {
+            d * tau as f32 / running_sum
+        } else {
+            1.0
+        });
+    }
+}
+
+/// The bottom of the first dip in `range` that goes below `threshold`
+fn dip_below(difference: &[f32], mut range: Range<usize>, threshold: f32) -> Option<usize> {
+    let end = range.end;
+    range
+        .find(|&tau| difference[tau] < threshold)
+        .map(|mut tau| {
+            // walk down to the bottom of the dip
+            while tau + 1 < end && difference[tau + 1] < difference[tau] {
+                tau += 1;
+            }
+            tau
+        })
+}
+
+/// The period at `tau` with parabolic interpolation for sub-sample accuracy
+fn refine_tau(difference: &[f32], tau: usize) -> f32 {
+    let (shift, _) = parabolic_vertex(difference[tau - 1], difference[tau], difference[tau + 1]);
+    tau as f32 + shift
+}
+
+/// Normalized autocorrelation peaks lower than this aren't periodic enough to
+/// count
+const AUTOCORRELATION_MIN_PEAK: f32 = 0.3;
+/// Peaks this close to the highest one count as the period
+const AUTOCORRELATION_PEAK_RATIO: f32 = 0.9;
+
+/// Pitch from the autocorrelation of the window: the lag where the signal
+/// lines up best with itself is the period. Its resolution doesn't depend on
+/// the fft size, which makes it better than the spectrum for low notes.
+#[derive(Default)]
+pub struct Autocorrelation {
+    correlation: Vec<f32>,
+}
+
+impl Autocorrelation {
+    /// The fundamental frequency of `samples` within `pitch_range` (Hz).
+    /// `None` when there's no clear period.
+    pub fn detect(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        pitch_range: (f32, f32),
+    ) -> Option<Pitch> {
+        let (min_tau, max_tau) = tau_range(samples.len(), sample_rate, pitch_range)?;
+        let width = samples.len() / 2;
+        let energy = samples[..width].iter().map(|s| s * s).sum::<f32>();
+        if energy <= 0.0 {
+            return None;
+        }
+        let correlation = &mut self.correlation;
+        correlation.clear();
+        correlation.extend((0..=max_tau).map(|tau| {
+            samples[..width]
+                .iter()
+                .zip(&samples[tau..tau + width])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+                / energy
+        }));
+        // skip the peak around lag 0, it always wins
+        let past_main_lobe = (1..max_tau).find(|&tau| correlation[tau] < 0.0)?;
+        let lags = min_tau.max(past_main_lobe)..max_tau;
+        let highest = lags
+            .clone()
+            .map(|tau| correlation[tau])
+            .fold(f32::NEG_INFINITY, f32::max);
+        if highest < AUTOCORRELATION_MIN_PEAK {
+            return None;
+        }
+        // multiples of the period line up about as well, take the first peak
+        // that's nearly as high as the best one
+        let tau = lags.clone().find(|&tau| {
+            correlation[tau] >= highest * AUTOCORRELATION_PEAK_RATIO
+                && correlation[tau] >= correlation[tau - 1]
+                && correlation[tau] >= correlation[tau + 1]
+        })?;
+        Some(Pitch {
+            frequency: sample_rate as f32 / refine_tau(correlation, tau),
+            confidence: correlation[tau].clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// Highest pitch the cepstrum looks for. Shorter quefrencies hold the
+/// spectral envelope, the formants, rather than the pitch
+const CEPSTRUM_MAX_HZ: f32 = 2000.0;
+/// A cepstral peak this high, in nepers of ripple in the log spectrum, means
+/// the harmonics stand well clear of the noise
+const CEPSTRUM_CONFIDENT_PEAK: f32 = 0.2;
+
+/// Pitch from the real cepstrum, the inverse fft of the log spectrum. Evenly
+/// spaced harmonics turn into one peak at the period no matter how the
+/// formants shape them, which suits voices better than hps.
+pub struct Cepstrum {
+    ifft: Arc<dyn ComplexToReal<f32>>,
+    log_spectrum: Vec<Complex<f32>>,
+    cepstrum: Vec<f32>,
+    scratch: Vec<Complex<f32>>,
+}
+
+impl Cepstrum {
+    pub fn new(n: usize) -> Self {
+        let ifft = RealFftPlanner::new().plan_fft_inverse(n);
+        Self {
+            log_spectrum: ifft.make_input_vec(),
+            cepstrum: ifft.make_output_vec(),
+            scratch: ifft.make_scratch_vec(),
+            ifft,
+        }
+    }
+
+    /// The fundamental frequency from the fft `spectrum` of a window, the bins
+    /// up to nyquist, looking only within `pitch_range` (Hz)
+    pub fn detect(
+        &mut self,
+        spectrum: &[Complex<f32>],
+        sample_rate: u32,
+        pitch_range: (f32, f32),
+    ) -> Option<Pitch> {
+        let n = self.cepstrum.len();
+        // the log spectrum is real, so its inverse fft is too
+        for (log_bin, bin) in self.log_spectrum.iter_mut().zip(spectrum) {
+            *log_bin = Complex {
+                re: bin.norm().max(1e-10).ln(),
+                im: 0.0,
+            };
+        }
+        self.ifft
+            .process_with_scratch(
+                &mut self.log_spectrum,
+                &mut self.cepstrum,
+                &mut self.scratch,
+            )
+            .ok()?;
+        let min_q = (sample_rate as f32 / pitch_range.1.min(CEPSTRUM_MAX_HZ)) as usize;
+        let max_q = ((sample_rate as f32 / pitch_range.0.max(1.0)) as usize).min(n / 2 - 1);
+        if min_q < 1 || min_q >= max_q {
+            return None;
+        }
+        let cepstrum = &self.cepstrum;
+        let q = (min_q..=max_q).max_by(|a, b| cepstrum[*a].total_cmp(&cepstrum[*b]))?;
+        if cepstrum[q] <= 0.0 {
+            return None;
+        }
+        Some(Pitch {
+            frequency: sample_rate as f32 / refine_tau(cepstrum, q),
+            // the inverse fft isn't normalized
+            confidence: (cepstrum[q] / n as f32 / CEPSTRUM_CONFIDENT_PEAK).clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// Harmonics of a candidate fundamental the octave check weighs
+const OCTAVE_CHECK_HARMONICS: usize = 6;
+/// Share of the energy in the odd harmonics (1st, 3rd, 5th) below which a
+/// candidate isn't a fundamental. An octave below the note they fall between
+/// its partials and get next to nothing
+const OCTAVE_MIN_ODD_SHARE: f32 = 0.1;
+/// Octaves the check goes down at most, hps lands up to two above on a weak
+/// fundamental
+const OCTAVE_MAX_STEPS_DOWN: u32 = 2;
+
+/// How the octave check moved a pitch the hps found
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OctaveCorrection {
+    /// The detected pitch had no odd harmonics of its own, so it was a
+    /// sub-harmonic and the note is an octave up
+    Up { from: f32 },
+    /// Half the detected pitch has odd harmonics too, so the detected pitch
+    /// was one of its partials
+    Down { from: f32, octaves: u32 },
+}
+
+impl OctaveCorrection {
+    pub fn describe(self) -> String {
+        match self {
+            OctaveCorrection::Up { from } => {
+                format!("up from {from:.1} Hz, no odd partials")
+            }
+            OctaveCorrection::Down { from, octaves } => {
+                format!("down {octaves} from {from:.1} Hz, f0/2 has odd partials")
+            }
+        }
+    }
+}
+
+/// Checks `frequency` against its octaves above and below in the magnitude
+/// spectrum, bins `bin_width` Hz apart and padded `zero_padding` times, and
+/// gives the right one with what was changed. Energy at f0 and its odd
+/// multiples means f0 is a fundamental; when it only shows up at the even ones
+/// the note is 2·f0, and when f0/2 has some too the note is f0/2.
+pub fn correct_octave(
+    magnitudes: &[f32],
+    frequency: f32,
+    bin_width: f32,
+    zero_padding: usize,
+    pitch_range: (f32, f32),
+) -> (f32, Option<OctaveCorrection>) {
+    if frequency <= 0.0 {
+        return (frequency, None);
+    }
+    let is_fundamental = |candidate: f32| {
+        odd_harmonic_share(magnitudes, candidate, bin_width, zero_padding)
+            .is_some_and(|share| share >= OCTAVE_MIN_ODD_SHARE)
+    };
+    if !is_fundamental(frequency) {
+        let up = 2.0 * frequency;
+        if up <= pitch_range.1 && is_fundamental(up) {
+            let up = refine_harmonic(magnitudes, up, bin_width, zero_padding);
+            return (up, Some(OctaveCorrection::Up { from: frequency }));
+        }
+        return (frequency, None);
+    }
+    let mut corrected = frequency;
+    let mut octaves = 0;
+    while octaves < OCTAVE_MAX_STEPS_DOWN
+        && corrected / 2.0 >= pitch_range.0
+        && is_fundamental(corrected / 2.0)
+    {
+        corrected /= 2.0;
+        octaves += 1;
+    }
+    let correction = (octaves > 0).then_some(OctaveCorrection::Down {
+        from: frequency,
+        octaves,
+    });
+    (corrected, correction)
+}
+
+/// `frequency` measured again off its loudest harmonic, since doubling a
+/// sub-harmonic doubles its error too
+fn refine_harmonic(magnitudes: &[f32], frequency: f32, bin_width: f32, zero_padding: usize) -> f32 {
+    (1..=OCTAVE_CHECK_HARMONICS)
+        .filter_map(|harmonic| {
+            let bin = (harmonic as f32 * frequency / bin_width).round() as usize;
+            let first = bin.saturating_sub(zero_padding).max(1);
+            let around = magnitudes.get(first..=bin + zero_padding)?;
+            let loudest = first + around.iter().position_max_by(|a, b| a.total_cmp(b))?;
+            (loudest + 1 < magnitudes.len()).then_some((harmonic, loudest))
+        })
+        .max_by(|(_, a), (_, b)| magnitudes[*a].total_cmp(&magnitudes[*b]))
+        .map_or(frequency, |(harmonic, bin)| {
+            let (shift, _) = refine_peak(magnitudes, bin);
+            (bin as f32 + shift) * bin_width / harmonic as f32
+        })
+}
+
+/// How much of the energy at the first few multiples of `frequency` is at the
+/// odd ones. `None` when they're all silent or past the spectrum.
+fn odd_harmonic_share(
+    magnitudes: &[f32],
+    frequency: f32,
+    bin_width: f32,
+    zero_padding: usize,
+) -> Option<f32> {
+    let mut odd = 0.0;
+    let mut total = 0.0;
+    for harmonic in 1..=OCTAVE_CHECK_HARMONICS {
+        let bin = (harmonic as f32 * frequency / bin_width).round() as usize;
+        // the strongest bin within one unpadded bin, the partial may be a
+        // little off the exact multiple
+        let Some(around) = magnitudes.get(bin.saturating_sub(zero_padding)..=bin + zero_padding)
+        else {
+            break;
+        };
+        let energy = around.iter().fold(0.0f32, |max, m| max.max(m * m));
+        if harmonic % 2 == 1 {
+            odd += energy;
+        }
+        total += energy;
+    }
+    (total > 0.0).then_some(odd / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near, harmonic_tone, spectrum};
+
+    #[test]
+    fn yin_finds_a_sine() {
+        let samples = harmonic_tone(220.0, 1, 4096);
+        let pitch = Yin::default()
+            .detect(&samples, SAMPLE_RATE, (50.0, 2000.0))
+            .unwrap();
+        assert_near(pitch.frequency, 220.0, 0.5);
+        assert!(pitch.confidence > 0.9, "{}", pitch.confidence);
+    }
+
+    #[test]
+    fn yin_finds_the_fundamental_of_a_harmonic_tone() {
+        let samples = harmonic_tone(196.0, 6, 4096);
+        let pitch = Yin::default()
+            .detect(&samples, SAMPLE_RATE, (50.0, 2000.0))
+            .unwrap();
+        assert_near(pitch.frequency, 196.0, 0.5);
+    }
+
+    #[test]
+    fn yin_needs_a_window_of_two_periods() {
+        let samples = harmonic_tone(110.0, 1, 256);
+        // half the window is shorter than any period in range
+        assert!(
+            Yin::default()
+                .detect(&samples, SAMPLE_RATE, (50.0, 150.0))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn pyin_finds_the_fundamental_of_a_harmonic_tone() {
+        let samples = harmonic_tone(196.0, 6, 4096);
+        let mut pyin = Pyin::default();
+        let pitch = pyin.detect(&samples, SAMPLE_RATE, (50.0, 2000.0)).unwrap();
+        assert_near(pitch.frequency, 196.0, 0.5);
+        assert!(pitch.confidence > 0.9, "{}", pitch.confidence);
+        // and keeps it on the next window
+        let pitch = pyin.detect(&samples, SAMPLE_RATE, (50.0, 2000.0)).unwrap();
+        assert_near(pitch.frequency, 196.0, 0.5);
+    }
+
+    #[test]
+    fn refine_tau_lands_between_samples() {
+        // a parabola with its bottom at 10.25
+        let difference = (0..20)
+            .map(|tau| (tau as f32 - 10.25).powi(2))
+            .collect::<Vec<_>>();
+        assert_near(refine_tau(&difference, 10), 10.25, 1e-4);
+    }
+
+    #[test]
+    fn cepstrum_finds_the_spacing_of_the_harmonics() {
+        let spectrum = spectrum(&harmonic_tone(150.0, 20, 4096));
+        let pitch = Cepstrum::new(4096)
+            .detect(&spectrum, SAMPLE_RATE, (50.0, 2000.0))
+            .unwrap();
+        assert_near(pitch.frequency, 150.0, 2.0);
+    }
+}
@@ -0,0 +1,43 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{self, Sender},
+};
+
+use color_eyre::eyre::Result;
+
+use crate::audio::AudioMessage;
+
+/// Starts writing interleaved samples to a wav file on its own thread, so the
+/// audio callback never waits on the disk. The file is finalized once the
+/// returned sender is dropped.
+pub fn start_recording(
+    path: PathBuf,
+    channels: u16,
+    sample_rate: u32,
+    ui_channel: Sender<AudioMessage>,
+) -> Result<Sender<Vec<f32>>> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec)?;
+    let (tx, rx) = mpsc::channel::<Vec<f32>>();
+    std::thread::spawn(move || {
+        let result = rx
+            .iter()
+            .try_for_each(|samples| samples.into_iter().try_for_each(|s| writer.write_sample(s)))
+            .and_then(|_| writer.finalize());
+        let message = match result {
+            Ok(()) => AudioMessage::Status(format!("Saved recording to {}", path.display())),
+            Err(err) => {
+                tracing::error!("recording to {} failed: {err}", path.display());
+                AudioMessage::Error(format!("Recording to {} failed: {err}", path.display()))
+            }
+        };
+        let _ = ui_channel.send(message);
+        let _ = ui_channel.send(AudioMessage::RecordingStopped(path));
+    });
+    Ok(tx)
+}
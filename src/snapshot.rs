@@ -0,0 +1,173 @@
+use std::{fmt::Write, path::PathBuf};
+
+use color_eyre::eyre::Result;
+
+use crate::audio::FreqData;
+
+/// Size of the svg drawing of the spectrum
+const SVG_SIZE: (f64, f64) = (800.0, 400.0);
+/// dBFS at the bottom of the svg drawing, 0 dBFS is at the top
+const SVG_FLOOR_DB: f64 = -100.0;
+
+/// Writes the spectrum of `data` to `<stem>.csv`, with its readings in
+/// comments above it, and a drawing of it in dBFS to `<stem>.svg`. Returns
+/// both paths.
+pub fn save_snapshot(data: &FreqData, stem: PathBuf) -> Result<(PathBuf, PathBuf)> {
+    let csv_path = stem.with_extension("csv");
+    std::fs::write(&csv_path, csv(data))?;
+    let svg_path = stem.with_extension("svg");
+    std::fs::write(&svg_path, svg(data))?;
+    Ok((csv_path, svg_path))
+}
+
+fn csv(data: &FreqData) -> String {
+    let mut csv = String::new();
+    let _ = writeln!(csv, "# sample rate: {} Hz", data.sample_rate);
+    let _ = writeln!(csv, "# fft size: {}", data.samples_n);
+    let _ = writeln!(csv, "# channel: {}", data.channel + 1);
+    let _ = writeln!(
+        csv,
+        "# fundamental: {:.2} Hz ({}, {:.0}% sure)",
+        data.fundamental_frequency,
+        data.pitch_method.name(),
+        data.pitch_confidence * 100.0
+    );
+    let _ = writeln!(csv, "# loudest bin: {:.2} Hz", data.peak_frequency);
+    let _ = writeln!(
+        csv,
+        "# peaks: {}",
+        data.peaks
+            .iter()
+            .map(|peak| format!("{:.2} Hz", peak.frequency))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let _ = writeln!(csv, "frequency_hz,magnitude,dbfs");
+    for (frequency, magnitude) in &data.data {
+        let _ = writeln!(
+            csv,
+            "{frequency:.3},{magnitude:.6},{:.2}",
+            data.to_dbfs(*magnitude)
+        );
+    }
+    csv
+}
+
+fn svg(data: &FreqData) -> String {
+    let (width, height) = SVG_SIZE;
+    let top_hz = data
+        .data
+        .last()
+        .map_or(1.0, |(frequency, _)| frequency.max(1.0));
+    let points = data
+        .data
+        .iter()
+        .map(|(frequency, magnitude)| {
+            let db = data.to_dbfs(*magnitude).clamp(SVG_FLOOR_DB, 0.0);
+            format!(
+                "{:.1},{:.1}",
+                frequency / top_hz * width,
+                db / SVG_FLOOR_DB * height
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="black"/>"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<polyline points="{points}" fill="none" stroke="cyan" stroke-width="1"/>"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="4" y="14" fill="gray" font-size="12">0 dBFS, {:.0} Hz at the right, fundamental {:.2} Hz</text>"#,
+        top_hz, data.fundamental_frequency
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="4" y="{}" fill="gray" font-size="12">{SVG_FLOOR_DB} dBFS</text>"#,
+        height - 4.0
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peaks::Peak;
+
+    /// Three bins: silence, full scale at the fundamental and half of it
+    fn data() -> FreqData {
+        FreqData {
+            data: vec![(0.0, 0.0), (10.0, 1.0), (20.0, 0.5)],
+            full_scale: 1.0,
+            sample_rate: 44100,
+            samples_n: 4096,
+            fundamental_frequency: 10.0,
+            pitch_confidence: 0.8,
+            peak_frequency: 10.0,
+            peaks: vec![Peak {
+                frequency: 10.0,
+                magnitude: 1.0,
+            }],
+            ..FreqData::default()
+        }
+    }
+
+    #[test]
+    fn csv_has_the_readings_then_a_row_per_bin() {
+        let csv = csv(&data());
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "# sample rate: 44100 Hz");
+        assert_eq!(lines[3], "# fundamental: 10.00 Hz (HPS, 80% sure)");
+        assert_eq!(lines[5], "# peaks: 10.00 Hz");
+        assert_eq!(
+            lines[6..],
+            [
+                "frequency_hz,magnitude,dbfs",
+                "0.000,0.000000,-inf",
+                "10.000,1.000000,0.00",
+                "20.000,0.500000,-6.02",
+            ]
+        );
+    }
+
+    #[test]
+    fn svg_spans_the_spectrum_from_the_floor_to_full_scale() {
+        let svg = svg(&data());
+        let points = svg
+            .split(r#"points=""#)
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .unwrap()
+            .split(' ')
+            .map(|point| {
+                let (x, y) = point.split_once(',').unwrap();
+                (x.parse::<f64>().unwrap(), y.parse::<f64>().unwrap())
+            })
+            .collect::<Vec<_>>();
+        // silence sits on the floor, full scale at the top and the last bin
+        // at the right edge
+        assert_eq!(points, [(0.0, 400.0), (400.0, 0.0), (800.0, 24.1)]);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn save_snapshot_writes_both_files() {
+        let stem = std::env::temp_dir().join(format!("snapshot-test-{}", std::process::id()));
+        let data = data();
+        let (csv_path, svg_path) = save_snapshot(&data, stem).unwrap();
+        assert_eq!(std::fs::read_to_string(&csv_path).unwrap(), csv(&data));
+        assert_eq!(std::fs::read_to_string(&svg_path).unwrap(), svg(&data));
+        std::fs::remove_file(csv_path).unwrap();
+        std::fs::remove_file(svg_path).unwrap();
+    }
+}
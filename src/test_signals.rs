@@ -0,0 +1,62 @@
+use std::f32::consts::PI;
+
+use realfft::{RealFftPlanner, num_complex::Complex};
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// `len` samples of a full scale sine at `frequency`
+pub fn sine(frequency: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+        .collect()
+}
+
+/// `len` samples of a tone at `frequency` with `harmonics` partials falling
+/// off as 1/h, at half scale
+pub fn harmonic_tone(frequency: f32, harmonics: usize, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            (1..=harmonics)
+                .map(|h| (2.0 * PI * h as f32 * frequency * t).sin() / h as f32)
+                .sum::<f32>()
+                * 0.5
+        })
+        .collect()
+}
+
+/// A hann window of `len` samples
+pub fn hann(len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / len as f32).cos())
+        .collect()
+}
+
+/// The fft of `samples` through a hann window, the bins from 0 Hz up to the
+/// nyquist frequency
+pub fn spectrum(samples: &[f32]) -> Vec<Complex<f32>> {
+    let fft = RealFftPlanner::new().plan_fft_forward(samples.len());
+    let mut input = samples
+        .iter()
+        .zip(hann(samples.len()))
+        .map(|(sample, window)| sample * window)
+        .collect::<Vec<_>>();
+    let mut spectrum = fft.make_output_vec();
+    fft.process(&mut input, &mut spectrum).unwrap();
+    spectrum
+}
+
+/// Magnitudes of the bins of `samples` through a hann window, from 0 Hz up to
+/// the nyquist frequency
+pub fn magnitudes(samples: &[f32]) -> Vec<f32> {
+    spectrum(samples).iter().map(|bin| bin.norm()).collect()
+}
+
+/// Fails the test unless `actual` is closer than `tolerance` to `expected`
+#[track_caller]
+pub fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() < tolerance,
+        "{actual} isn't within {tolerance} of {expected}"
+    );
+}
@@ -0,0 +1,388 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use ratatui::{style::Color, symbols::Marker};
+use serde::Deserialize;
+
+/// The built-in color themes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// Bright colors on a dark terminal
+    #[default]
+    Default,
+    /// Darker colors for a terminal with a light background
+    Light,
+    /// Only the brightest colors, for projectors and bad eyes
+    HighContrast,
+    /// Ethan Schoonover's Solarized dark palette
+    Solarized,
+}
+
+impl ThemeName {
+    pub const ALL: [ThemeName; 4] = [
+        ThemeName::Default,
+        ThemeName::Light,
+        ThemeName::HighContrast,
+        ThemeName::Solarized,
+    ];
+
+    /// The theme after this one, back to the first after the last
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|name| *name == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeName::Default => Theme {
+                signal: Color::Cyan,
+                muted: Color::Gray,
+                faint: Color::DarkGray,
+                highlight: Color::Yellow,
+                good: Color::Green,
+                warning: Color::Yellow,
+                bad: Color::Red,
+                peak: Color::Magenta,
+                bright: Color::White,
+                spectrum: vec![
+                    Color::Rgb(90, 60, 200),
+                    Color::Rgb(51, 204, 255),
+                    Color::Rgb(255, 255, 140),
+                ],
+                heat: vec![
+                    Color::Rgb(0, 0, 0),
+                    Color::Rgb(20, 20, 120),
+                    Color::Rgb(140, 30, 160),
+                    Color::Rgb(220, 50, 50),
+                    Color::Rgb(250, 200, 40),
+                    Color::Rgb(255, 255, 255),
+                ],
+                diverging: [
+                    Color::Rgb(0, 0, 255),
+                    Color::Rgb(255, 255, 255),
+                    Color::Rgb(255, 0, 0),
+                ],
+                white_keys: Color::White,
+                black_keys: Color::Black,
+            },
+            ThemeName::Light => Theme {
+                signal: Color::Blue,
+                muted: Color::DarkGray,
+                faint: Color::Gray,
+                highlight: Color::Rgb(175, 95, 0),
+                good: Color::Rgb(0, 135, 0),
+                warning: Color::Rgb(175, 95, 0),
+                bad: Color::Red,
+                peak: Color::Magenta,
+                bright: Color::Black,
+                spectrum: vec![
+                    Color::Rgb(150, 170, 220),
+                    Color::Rgb(0, 90, 200),
+                    Color::Rgb(200, 0, 120),
+                ],
+                heat: vec![
+                    Color::Rgb(255, 255, 255),
+                    Color::Rgb(250, 220, 120),
+                    Color::Rgb(230, 100, 40),
+                    Color::Rgb(150, 20, 110),
+                    Color::Rgb(40, 20, 110),
+                    Color::Rgb(0, 0, 0),
+                ],
+                diverging: [
+                    Color::Rgb(0, 60, 220),
+                    Color::Rgb(255, 255, 255),
+                    Color::Rgb(220, 20, 20),
+                ],
+                white_keys: Color::White,
+                black_keys: Color::Black,
+            },
+            ThemeName::HighContrast => Theme {
+                signal: Color::White,
+                muted: Color::White,
+                faint: Color::Gray,
+                highlight: Color::LightYellow,
+                good: Color::LightGreen,
+                warning: Color::LightYellow,
+                bad: Color::LightRed,
+                peak: Color::LightMagenta,
+                bright: Color::White,
+                spectrum: vec![Color::Gray, Color::White],
+                heat: vec![Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255)],
+                diverging: [
+                    Color::Rgb(80, 80, 255),
+                    Color::Rgb(0, 0, 0),
+                    Color::Rgb(255, 80, 80),
+                ],
+                white_keys: Color::White,
+                black_keys: Color::Black,
+            },
+            ThemeName::Solarized => Theme {
+                signal: Color::Rgb(42, 161, 152),
+                muted: Color::Rgb(131, 148, 150),
+                faint: Color::Rgb(88, 110, 117),
+                highlight: Color::Rgb(181, 137, 0),
+                good: Color::Rgb(133, 153, 0),
+                warning: Color::Rgb(203, 75, 22),
+                bad: Color::Rgb(220, 50, 47),
+                peak: Color::Rgb(211, 54, 130),
+                bright: Color::Rgb(253, 246, 227),
+                spectrum: vec![
+                    Color::Rgb(88, 110, 117),
+                    Color::Rgb(38, 139, 210),
+                    Color::Rgb(211, 54, 130),
+                ],
+                heat: vec![
+                    Color::Rgb(0, 43, 54),
+                    Color::Rgb(38, 139, 210),
+                    Color::Rgb(108, 113, 196),
+                    Color::Rgb(220, 50, 47),
+                    Color::Rgb(181, 137, 0),
+                    Color::Rgb(253, 246, 227),
+                ],
+                diverging: [
+                    Color::Rgb(38, 139, 210),
+                    Color::Rgb(253, 246, 227),
+                    Color::Rgb(220, 50, 47),
+                ],
+                white_keys: Color::Rgb(253, 246, 227),
+                black_keys: Color::Rgb(7, 54, 66),
+            },
+        }
+    }
+}
+
+impl Display for ThemeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ThemeName::Default => "default",
+            ThemeName::Light => "light",
+            ThemeName::HighContrast => "high contrast",
+            ThemeName::Solarized => "solarized",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What the lines of the fft and time domain charts are drawn with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartMarker {
+    /// Braille dots, the finest, but some fonts draw them broken
+    #[default]
+    Braille,
+    /// Half a cell, twice as fine as `block` upwards
+    HalfBlock,
+    /// A whole cell
+    Block,
+    Dot,
+}
+
+impl ChartMarker {
+    pub const ALL: [ChartMarker; 4] = [
+        ChartMarker::Braille,
+        ChartMarker::HalfBlock,
+        ChartMarker::Block,
+        ChartMarker::Dot,
+    ];
+
+    /// The marker after this one, back to the first after the last
+    pub fn next(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|marker| *marker == self)
+            .unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn marker(self) -> Marker {
+        match self {
+            ChartMarker::Braille => Marker::Braille,
+            ChartMarker::HalfBlock => Marker::HalfBlock,
+            ChartMarker::Block => Marker::Block,
+            ChartMarker::Dot => Marker::Dot,
+        }
+    }
+}
+
+impl Display for ChartMarker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ChartMarker::Braille => "braille",
+            ChartMarker::HalfBlock => "half block",
+            ChartMarker::Block => "block",
+            ChartMarker::Dot => "dot",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What each part of the ui is drawn in
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// The lines of the charts
+    pub signal: Color,
+    /// Axes, labels and anything less important than what's next to it
+    pub muted: Color,
+    /// Placeholders, gridlines and what hasn't been played yet
+    pub faint: Color,
+    /// The note to play next, harmonics and what's selected
+    pub highlight: Color,
+    /// In tune, or a safe level
+    pub good: Color,
+    /// A little off, or getting loud
+    pub warning: Color,
+    /// Clipping
+    pub bad: Color,
+    /// The strongest peaks of the spectrum
+    pub peak: Color,
+    /// What has to stand out from the background, like the bars' peak markers
+    pub bright: Color,
+    /// The fft chart's line goes through these from quiet to loud
+    pub spectrum: Vec<Color>,
+    /// The spectrogram goes through these from quiet to loud
+    pub heat: Vec<Color>,
+    /// Negative, zero and positive values of the mfcc heatmap
+    pub diverging: [Color; 3],
+    /// The piano's white keys, when they aren't lit up
+    pub white_keys: Color,
+    /// The piano's black keys, when they aren't lit up
+    pub black_keys: Color,
+}
+
+impl Theme {
+    /// Color `t` of the way from quiet to loud on the spectrogram
+    pub fn heat(&self, t: f32) -> Color {
+        gradient(&self.heat, t)
+    }
+
+    /// Color of the fft chart's line `t` of the way up the magnitude axis
+    pub fn spectrum(&self, t: f32) -> Color {
+        gradient(&self.spectrum, t)
+    }
+
+    /// Color of a mfcc from -1 to 1
+    pub fn diverging(&self, t: f32) -> Color {
+        gradient(&self.diverging, (t + 1.0) / 2.0)
+    }
+}
+
+/// Theme settings from the config file: a built-in theme, with any of its
+/// colors changed. Colors are names like "light-blue" or hex like "#268bd2".
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeOptions {
+    pub name: ThemeName,
+    pub marker: ChartMarker,
+    pub signal: Option<Color>,
+    pub muted: Option<Color>,
+    pub faint: Option<Color>,
+    pub highlight: Option<Color>,
+    pub good: Option<Color>,
+    pub warning: Option<Color>,
+    pub bad: Option<Color>,
+    pub peak: Option<Color>,
+    pub bright: Option<Color>,
+    pub spectrum: Option<Vec<Color>>,
+    pub heat: Option<Vec<Color>>,
+    pub diverging: Option<[Color; 3]>,
+    pub white_keys: Option<Color>,
+    pub black_keys: Option<Color>,
+}
+
+impl ThemeOptions {
+    /// The built-in theme `name` with the colors set here instead of its own
+    pub fn theme(&self, name: ThemeName) -> Theme {
+        let theme = name.theme();
+        Theme {
+            signal: self.signal.unwrap_or(theme.signal),
+            muted: self.muted.unwrap_or(theme.muted),
+            faint: self.faint.unwrap_or(theme.faint),
+            highlight: self.highlight.unwrap_or(theme.highlight),
+            good: self.good.unwrap_or(theme.good),
+            warning: self.warning.unwrap_or(theme.warning),
+            bad: self.bad.unwrap_or(theme.bad),
+            peak: self.peak.unwrap_or(theme.peak),
+            bright: self.bright.unwrap_or(theme.bright),
+            spectrum: match &self.spectrum {
+                // a gradient needs both ends
+                Some(spectrum) if spectrum.len() >= 2 => spectrum.clone(),
+                _ => theme.spectrum,
+            },
+            heat: match &self.heat {
+                // a gradient needs both ends
+                Some(heat) if heat.len() >= 2 => heat.clone(),
+                _ => theme.heat,
+            },
+            diverging: self.diverging.unwrap_or(theme.diverging),
+            white_keys: self.white_keys.unwrap_or(theme.white_keys),
+            black_keys: self.black_keys.unwrap_or(theme.black_keys),
+        }
+    }
+}
+
+/// Color `t` of the way along `colors`, blended between the two it falls
+/// between
+fn gradient(colors: &[Color], t: f32) -> Color {
+    let position = t.clamp(0.0, 1.0) * (colors.len() - 1) as f32;
+    let below = (position as usize).min(colors.len() - 2);
+    let fraction = position - below as f32;
+    let (from, to) = (rgb(colors[below]), rgb(colors[below + 1]));
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+/// Red, green and blue of `color`, named colors as xterm draws them
+fn rgb(color: Color) -> (u8, u8, u8) {
+    /// xterm's 16 named colors, in ansi order
+    const NAMED: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Reset => NAMED[7],
+        Color::Black => NAMED[0],
+        Color::Red => NAMED[1],
+        Color::Green => NAMED[2],
+        Color::Yellow => NAMED[3],
+        Color::Blue => NAMED[4],
+        Color::Magenta => NAMED[5],
+        Color::Cyan => NAMED[6],
+        Color::Gray => NAMED[7],
+        Color::DarkGray => NAMED[8],
+        Color::LightRed => NAMED[9],
+        Color::LightGreen => NAMED[10],
+        Color::LightYellow => NAMED[11],
+        Color::LightBlue => NAMED[12],
+        Color::LightMagenta => NAMED[13],
+        Color::LightCyan => NAMED[14],
+        Color::White => NAMED[15],
+        Color::Indexed(i @ 0..16) => NAMED[i as usize],
+        // the 6x6x6 color cube
+        Color::Indexed(i @ 16..232) => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + 40 * n };
+            let i = i - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        // the grays
+        Color::Indexed(i) => {
+            let gray = 8 + 10 * (i - 232);
+            (gray, gray, gray)
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::{Result, eyre};
+use serde::Deserialize;
+
+pub const NOTES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Midi note number of A4, the reference pitch
+const A4: i32 = 69;
+/// Readings within this many seconds are averaged together for the drift
+const DRIFT_BUCKET_SECS: f32 = 10.0;
+/// How long someone has to have played for before there's a drift to tell
+const DRIFT_MIN_SECS: f32 = 60.0;
+
+/// How the octave is divided into notes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Temperament {
+    /// Twelve equal semitones, what pianos and guitars are tuned to
+    #[default]
+    Equal,
+    /// 5-limit just intonation, pure thirds and fifths above the tonic
+    Just,
+    /// Stacked pure fifths, pure fourths and fifths but wide thirds
+    Pythagorean,
+}
+
+impl Temperament {
+    /// Ratio of each semitone above the tonic to the tonic
+    fn ratios(self) -> [f32; 12] {
+        match self {
+            Temperament::Equal => std::array::from_fn(|i| 2.0_f32.powf(i as f32 / 12.0)),
+            Temperament::Just => [
+                1.0,
+                16.0 / 15.0,
+                9.0 / 8.0,
+                6.0 / 5.0,
+                5.0 / 4.0,
+                4.0 / 3.0,
+                45.0 / 32.0,
+                3.0 / 2.0,
+                8.0 / 5.0,
+                5.0 / 3.0,
+                9.0 / 5.0,
+                15.0 / 8.0,
+            ],
+            Temperament::Pythagorean => [
+                1.0,
+                256.0 / 243.0,
+                9.0 / 8.0,
+                32.0 / 27.0,
+                81.0 / 64.0,
+                4.0 / 3.0,
+                729.0 / 512.0,
+                3.0 / 2.0,
+                128.0 / 81.0,
+                27.0 / 16.0,
+                16.0 / 9.0,
+                243.0 / 128.0,
+            ],
+        }
+    }
+}
+
+/// Tuning settings from the config file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TuningOptions {
+    pub temperament: Temperament,
+    /// The note just and pythagorean tuning are pure against
+    pub tonic: String,
+    /// Frequency of A4, the tonic is tuned to it as in equal temperament
+    pub reference_hz: f32,
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        Self {
+            temperament: Temperament::Equal,
+            tonic: "C".to_string(),
+            reference_hz: 440.0,
+        }
+    }
+}
+
+/// A frequency placed on the nearest note
+#[derive(Clone, Debug)]
+pub struct NoteReading {
+    /// Scientific pitch notation, like "E2" for a guitar's low E
+    pub name: String,
+    /// How far the frequency is from the note, in cents
+    pub cents: f32,
+    pub midi_note_number: i32,
+}
+
+/// Turns frequencies into notes for a temperament
+#[derive(Clone, Debug)]
+pub struct Tuning {
+    ratios: [f32; 12],
+    /// Pitch class of the tonic, 0 for C
+    tonic: usize,
+    /// Frequency of the tonic in octave 4
+    tonic_hz: f32,
+}
+
+impl Tuning {
+    pub fn new(options: &TuningOptions) -> Result<Self> {
+        let tonic = NOTES
+            .iter()
+            .position(|note| note.eq_ignore_ascii_case(options.tonic.trim()))
+            .ok_or_else(|| {
+                eyre!(
+                    "Unknown tonic `{}`, use one of {}",
+                    options.tonic,
+                    NOTES.join(", ")
+                )
+            })?;
+        if options.reference_hz <= 0.0 {
+            return Err(eyre!("The reference pitch has to be above 0 Hz"));
+        }
+        let tonic_midi = 60 + tonic as i32;
+        Ok(Self {
+            ratios: options.temperament.ratios(),
+            tonic,
+            tonic_hz: options.reference_hz * 2.0_f32.powf((tonic_midi - A4) as f32 / 12.0),
+        })
+    }
+
+    /// The nearest note to `freq`, `None` for frequencies that aren't a pitch
+    pub fn note(&self, freq: f32) -> Option<NoteReading> {
+        if !(freq > 0.0 && freq.is_finite()) {
+            return None;
+        }
+        let octave = (freq / self.tonic_hz).log2().floor();
+        let ratio = freq / (self.tonic_hz * 2.0_f32.powf(octave));
+        // the tonic an octave up is a candidate too, for frequencies just under it
+        let (degree, cents) = self
+            .ratios
+            .iter()
+            .chain(std::iter::once(&2.0))
+            .map(|note_ratio| 1200.0 * (ratio / note_ratio).log2())
+            .enumerate()
+            .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))?;
+        let midi_note_number = 60 + self.tonic as i32 + 12 * octave as i32 + degree as i32;
+        Some(NoteReading {
+            name: note_from_midi_note_number(midi_note_number),
+            cents,
+            midi_note_number,
+        })
+    }
+
+    pub fn note_name(&self, freq: f32) -> Option<String> {
+        self.note(freq).map(|note| note.name)
+    }
+
+    /// Frequency of a midi note in equal temperament from the same reference
+    /// pitch, what fretted instruments are tuned to whatever the temperament
+    pub fn equal_tempered_hz(&self, midi_note_number: i32) -> f32 {
+        self.tonic_hz * 2.0_f32.powf((midi_note_number - 60 - self.tonic as i32) as f32 / 12.0)
+    }
+}
+
+/// How far off the notes were over a whole session, and how that moved: wind
+/// instruments go sharp as they warm up, strings go flat as they stretch
+#[derive(Debug)]
+pub struct TuningDrift {
+    start: Instant,
+    /// (sum of cents, readings) per note played
+    notes: BTreeMap<String, (f32, usize)>,
+    /// (sum of cents, readings) per `DRIFT_BUCKET_SECS` since the start
+    buckets: Vec<(f32, usize)>,
+}
+
+impl Default for TuningDrift {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            notes: BTreeMap::new(),
+            buckets: vec![],
+        }
+    }
+}
+
+impl TuningDrift {
+    /// Counts a note played now
+    pub fn add(&mut self, reading: &NoteReading) {
+        let (sum, count) = self.notes.entry(reading.name.clone()).or_default();
+        *sum += reading.cents;
+        *count += 1;
+        let bucket = (self.start.elapsed().as_secs_f32() / DRIFT_BUCKET_SECS) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, (0.0, 0));
+        }
+        self.buckets[bucket].0 += reading.cents;
+        self.buckets[bucket].1 += 1;
+    }
+
+    /// Average cents off of each note played, and how many readings that's from
+    pub fn note_averages(&self) -> impl Iterator<Item = (&str, f32, usize)> {
+        self.notes
+            .iter()
+            .map(|(note, (sum, count))| (note.as_str(), sum / *count as f32, *count))
+    }
+
+    /// How many cents the tuning moved from the first note played to the last,
+    /// from a line fit through the averages over time, and over how long
+    pub fn drift(&self) -> Option<(f32, Duration)> {
+        let points = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, count))| *count > 0)
+            .map(|(i, (sum, count))| {
+                (
+                    (i as f32 + 0.5) * DRIFT_BUCKET_SECS,
+                    sum / *count as f32,
+                    *count as f32,
+                )
+            })
+            .collect::<Vec<_>>();
+        let (first, last) = (points.first()?.0, points.last()?.0);
+        if last - first < DRIFT_MIN_SECS {
+            return None;
+        }
+        // weighted by readings, a bucket with one stray note counts for little
+        let weight = points.iter().map(|(_, _, w)| w).sum::<f32>();
+        let mean_t = points.iter().map(|(t, _, w)| t * w).sum::<f32>() / weight;
+        let mean_cents = points.iter().map(|(_, c, w)| c * w).sum::<f32>() / weight;
+        let (covariance, variance) =
+            points
+                .iter()
+                .fold((0.0, 0.0), |(covariance, variance), (t, c, w)| {
+                    (
+                        covariance + w * (t - mean_t) * (c - mean_cents),
+                        variance + w * (t - mean_t) * (t - mean_t),
+                    )
+                });
+        let span = last - first;
+        Some((covariance / variance * span, Duration::from_secs_f32(span)))
+    }
+
+    /// Like "your tuning drifted +8 cents over 20 minutes"
+    pub fn summary(&self) -> Option<String> {
+        let (cents, span) = self.drift()?;
+        let minutes = (span.as_secs_f32() / 60.0).round() as u64;
+        Some(format!(
+            "your tuning drifted {cents:+.0} cents over {minutes} minute{}",
+            if minutes == 1 { "" } else { "s" }
+        ))
+    }
+}
+
+/// Scientific pitch notation, like "E2" for a guitar's low E. Midi note 60 is
+/// C4, middle C
+pub fn note_from_midi_note_number(midi_note_number: i32) -> String {
+    let i = midi_note_number.rem_euclid(12) as usize;
+    let octave = midi_note_number.div_euclid(12) - 1;
+    format!("{}{octave}", NOTES[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_signals::assert_near;
+
+    fn tuning(temperament: Temperament, tonic: &str) -> Tuning {
+        Tuning::new(&TuningOptions {
+            temperament,
+            tonic: tonic.to_string(),
+            ..TuningOptions::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn equal_temperament_is_in_tune_with_a_440() {
+        let tuning = tuning(Temperament::Equal, "C");
+        let a4 = tuning.note(440.0).unwrap();
+        assert_eq!(a4.name, "A4");
+        assert_near(a4.cents, 0.0, 0.01);
+        let c4 = tuning.note(261.626).unwrap();
+        assert_eq!(c4.name, "C4");
+        assert_near(c4.cents, 0.0, 0.01);
+        // about a fifth of a semitone sharp
+        assert_near(tuning.note(445.0).unwrap().cents, 19.56, 0.01);
+    }
+
+    #[test]
+    fn just_thirds_are_pure_against_the_tonic() {
+        let tuning = tuning(Temperament::Just, "C");
+        let pure_third = tuning.note(261.626 * 5.0 / 4.0).unwrap();
+        assert_eq!(pure_third.name, "E4");
+        assert_near(pure_third.cents, 0.0, 0.01);
+        // the equal tempered third is 14 cents wide of it
+        assert_near(tuning.note(329.628).unwrap().cents, 13.69, 0.01);
+    }
+
+    #[test]
+    fn pythagorean_thirds_are_wide() {
+        let tuning = tuning(Temperament::Pythagorean, "C");
+        let third = tuning.note(329.628).unwrap();
+        assert_eq!(third.name, "E4");
+        assert_near(third.cents, -7.82, 0.01);
+    }
+
+    #[test]
+    fn the_tonic_keeps_its_equal_tempered_pitch() {
+        let tuning = tuning(Temperament::Just, "A");
+        assert_near(tuning.note(440.0).unwrap().cents, 0.0, 0.01);
+        let pure_third = tuning.note(550.0).unwrap();
+        assert_eq!(pure_third.name, "C#5");
+        assert_near(pure_third.cents, 0.0, 0.01);
+    }
+
+    #[test]
+    fn just_under_the_tonic_is_the_octave_above() {
+        let tuning = tuning(Temperament::Just, "C");
+        let flat_c5 = tuning.note(523.251 * 2.0_f32.powf(-10.0 / 1200.0)).unwrap();
+        assert_eq!(flat_c5.name, "C5");
+        assert_near(flat_c5.cents, -10.0, 0.01);
+    }
+
+    #[test]
+    fn bad_tuning_options_are_errors() {
+        let options = TuningOptions {
+            tonic: "H".to_string(),
+            ..TuningOptions::default()
+        };
+        assert!(Tuning::new(&options).is_err());
+        let options = TuningOptions {
+            reference_hz: 0.0,
+            ..TuningOptions::default()
+        };
+        assert!(Tuning::new(&options).is_err());
+        assert!(tuning(Temperament::Equal, "c").note(0.0).is_none());
+    }
+}
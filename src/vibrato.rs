@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+
+use crate::{peaks::refine_peak, pitch::Yin};
+
+/// Samples per pitch estimate of the contour
+const CONTOUR_FRAME: usize = 2048;
+/// Samples between pitch estimates, ~12 ms at 44.1 kHz. The analysis windows
+/// come ~93 ms apart, too slow to see a 6 Hz wobble
+const CONTOUR_HOP: usize = 512;
+/// Seconds of pitch contour the vibrato is measured on
+const VIBRATO_WINDOW_SECS: f32 = 1.0;
+/// Vibrato rates looked for, in Hz
+const VIBRATO_RATE_RANGE: (f32, f32) = (3.0, 9.0);
+/// Pitch estimates less sure than this leave a gap in the contour
+const CONTOUR_MIN_CONFIDENCE: f32 = 0.5;
+/// The contour has to line up with itself a cycle later this well
+/// (normalized autocorrelation) to be a vibrato and not just wandering
+const VIBRATO_MIN_CORRELATION: f32 = 0.5;
+/// Shallower wobbles than this, in cents either way, aren't a vibrato
+const VIBRATO_MIN_DEPTH_CENTS: f32 = 8.0;
+/// A contour spanning more than this many cents is a change of note
+const VIBRATO_MAX_SPAN_CENTS: f32 = 300.0;
+/// Lowest pitch followed, the contour frames are too short for lower ones
+const CONTOUR_MIN_HZ: f32 = 50.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vibrato {
+    /// Wobbles per second
+    pub rate_hz: f32,
+    /// How far the pitch swings either way from its center
+    pub depth_cents: f32,
+}
+
+/// Follows the pitch every ~12 ms with YIN and measures the vibrato of the
+/// last second: the rate from the autocorrelation of the contour, the depth
+/// from its spread around the trend.
+#[derive(Default)]
+pub struct VibratoTracker {
+    yin: Yin,
+    /// Samples not yet covered by a whole contour frame
+    pending: Vec<f32>,
+    /// Pitch in cents from A4 per hop, `None` where it wasn't clear
+    contour: VecDeque<Option<f32>>,
+    sample_rate: u32,
+}
+
+impl VibratoTracker {
+    /// Takes the next samples and gives the vibrato of the last second, if the
+    /// whole second was one note with a regular wobble
+    pub fn add(
+        &mut self,
+        samples: &[f32],
+        sample_rate: u32,
+        pitch_range: (f32, f32),
+    ) -> Option<Vibrato> {
+        if sample_rate != self.sample_rate {
+            self.pending.clear();
+            self.contour.clear();
+            self.sample_rate = sample_rate;
+        }
+        let frame_rate = sample_rate as f32 / CONTOUR_HOP as f32;
+        let max_len = (VIBRATO_WINDOW_SECS * frame_rate) as usize;
+        let pitch_range = (pitch_range.0.max(CONTOUR_MIN_HZ), pitch_range.1);
+        self.pending.extend_from_slice(samples);
+        let mut start = 0;
+        while start + CONTOUR_FRAME <= self.pending.len() {
+            let cents = self
+                .yin
+                .detect(
+                    &self.pending[start..start + CONTOUR_FRAME],
+                    sample_rate,
+                    pitch_range,
+                )
+                .filter(|pitch| pitch.confidence >= CONTOUR_MIN_CONFIDENCE && pitch.frequency > 0.0)
+                .map(|pitch| 1200.0 * (pitch.frequency / 440.0).log2());
+            if self.contour.len() == max_len {
+                self.contour.pop_front();
+            }
+            self.contour.push_back(cents);
+            start += CONTOUR_HOP;
+        }
+        self.pending.drain(..start);
+        if self.contour.len() < max_len {
+            return None;
+        }
+        let contour = self.contour.iter().copied().collect::<Option<Vec<_>>>()?;
+        let (min, max) = contour
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), c| {
+                (min.min(*c), max.max(*c))
+            });
+        if max - min > VIBRATO_MAX_SPAN_CENTS {
+            return None;
+        }
+        // take out a slow glide so only the wobble is left
+        let count = contour.len() as f32;
+        let mean_x = (count - 1.0) / 2.0;
+        let mean_y = contour.iter().sum::<f32>() / count;
+        let (covariance, variance) =
+            contour
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+                    let dx = x as f32 - mean_x;
+                    (covariance + dx * (y - mean_y), variance + dx * dx)
+                });
+        let slope = covariance / variance;
+        let wobble = contour
+            .iter()
+            .enumerate()
+            .map(|(x, y)| y - mean_y - slope * (x as f32 - mean_x))
+            .collect::<Vec<_>>();
+        let correlation_at = |lag: usize| {
+            wobble
+                .iter()
+                .zip(&wobble[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f32>()
+        };
+        let energy = correlation_at(0);
+        if energy <= 0.0 {
+            return None;
+        }
+        let lags = (frame_rate / VIBRATO_RATE_RANGE.1).floor() as usize
+            ..=(frame_rate / VIBRATO_RATE_RANGE.0).ceil() as usize;
+        let lag = lags
+            .clone()
+            .max_by(|a, b| correlation_at(*a).total_cmp(&correlation_at(*b)))?;
+        if correlation_at(lag) / energy < VIBRATO_MIN_CORRELATION {
+            return None;
+        }
+        // parabolic interpolation for a rate between the lags
+        let shift = if lags.contains(&(lag - 1)) && lags.contains(&(lag + 1)) {
+            let around = [
+                correlation_at(lag - 1),
+                correlation_at(lag),
+                correlation_at(lag + 1),
+            ];
+            refine_peak(&around, 1).0
+        } else {
+            0.0
+        };
+        // a sine's amplitude is its rms times the square root of 2
+        let depth_cents = (energy / count).sqrt() * std::f32::consts::SQRT_2;
+        (depth_cents >= VIBRATO_MIN_DEPTH_CENTS).then(|| Vibrato {
+            rate_hz: frame_rate / (lag as f32 + shift),
+            depth_cents,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::test_signals::{SAMPLE_RATE, assert_near};
+
+    /// Two seconds of a sine whose pitch follows `cents(t)`, in cents from
+    /// `frequency`
+    fn gliding(frequency: f32, cents: impl Fn(f32) -> f32) -> Vec<f32> {
+        let mut phase = 0.0;
+        (0..2 * SAMPLE_RATE as usize)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                phase +=
+                    2.0 * PI * frequency * 2.0_f32.powf(cents(t) / 1200.0) / SAMPLE_RATE as f32;
+                0.5 * phase.sin()
+            })
+            .collect()
+    }
+
+    /// A4 with its pitch swinging `depth_cents` either way `rate_hz` times a
+    /// second
+    fn sung(rate_hz: f32, depth_cents: f32) -> Vec<f32> {
+        gliding(440.0, |t| depth_cents * (2.0 * PI * rate_hz * t).sin())
+    }
+
+    /// What the tracker says after the whole of `samples`, in analysis windows
+    fn track(samples: &[f32]) -> Option<Vibrato> {
+        let mut tracker = VibratoTracker::default();
+        samples
+            .chunks(4096)
+            .map(|window| tracker.add(window, SAMPLE_RATE, (100.0, 1000.0)))
+            .last()?
+    }
+
+    #[test]
+    fn finds_the_rate_and_depth_of_a_vibrato() {
+        let vibrato = track(&sung(5.5, 40.0)).unwrap();
+        assert_near(vibrato.rate_hz, 5.5, 0.3);
+        assert_near(vibrato.depth_cents, 40.0, 5.0);
+    }
+
+    #[test]
+    fn a_steady_note_has_no_vibrato() {
+        assert_eq!(track(&sung(5.5, 0.0)), None);
+    }
+
+    #[test]
+    fn a_slide_is_no_vibrato() {
+        // two seconds up an octave
+        assert_eq!(track(&gliding(220.0, |t| 600.0 * t)), None);
+    }
+}